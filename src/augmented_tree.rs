@@ -0,0 +1,890 @@
+use std::cmp::Ordering;
+use std::ops::{Deref, DerefMut};
+
+use super::*;
+
+///
+/// A monoid over the data of a `Tree`'s `Node`s, used by `AugmentedTree` to summarize subtrees.
+///
+/// The aggregate of a subtree is `lift` of the subtree root's data, `combine`d from left to right
+/// with the aggregates of each of its children (in order).  `combine` must be associative and
+/// `identity` must leave any value unchanged when combined with it on either side.  `combine` does
+/// not have to be commutative: reordering children updates the aggregates as well.
+///
+/// ```
+/// use id_tree::Aggregate;
+///
+/// // the total size of a folder
+/// struct TotalSize;
+///
+/// impl Aggregate<u64> for TotalSize {
+///     type Value = u64;
+///
+///     fn identity(&self) -> u64 {
+///         0
+///     }
+///
+///     fn lift(&self, size: &u64) -> u64 {
+///         *size
+///     }
+///
+///     fn combine(&self, left: &u64, right: &u64) -> u64 {
+///         left + right
+///     }
+/// }
+/// ```
+///
+pub trait Aggregate<T> {
+    ///
+    /// The summary of a subtree.
+    ///
+    type Value;
+
+    ///
+    /// The summary of nothing at all.
+    ///
+    fn identity(&self) -> Self::Value;
+
+    ///
+    /// The summary of a single `Node`'s data, not counting its children.
+    ///
+    fn lift(&self, data: &T) -> Self::Value;
+
+    ///
+    /// Joins the summaries of two adjacent parts of a subtree.
+    ///
+    fn combine(&self, left: &Self::Value, right: &Self::Value) -> Self::Value;
+}
+
+///
+/// A `Tree` that keeps the `Aggregate` of every `Node`'s subtree up to date as it is modified.
+///
+/// After each operation only the `Node`s whose subtrees changed are recomputed: the affected
+/// `Node`s themselves and their ancestors.  Every `Node` also keeps the aggregates of its children
+/// combined pairwise in a balanced tree, so a change to one child's aggregate only takes
+/// O(log(number of children)) calls to `combine` to reach its parent.  Inserting a `Node` or
+/// editing its data therefore costs O(depth * log(number of children)) instead of depending on the
+/// size of the `Tree`.  Operations that rearrange a list of children (removing, moving, swapping
+/// or sorting `Node`s) recombine that whole list once, just as the `Tree` shifts it.
+///
+/// Data can only be edited through `AugmentedTree::get_mut`, which hands out a guard that updates
+/// the aggregates when it is dropped.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+/// # struct TotalSize;
+/// # impl Aggregate<u64> for TotalSize {
+/// #     type Value = u64;
+/// #     fn identity(&self) -> u64 { 0 }
+/// #     fn lift(&self, size: &u64) -> u64 { *size }
+/// #     fn combine(&self, left: &u64, right: &u64) -> u64 { left + right }
+/// # }
+///
+/// let mut tree = AugmentedTree::new(TotalSize);
+///
+/// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+/// let docs_id = tree.insert(Node::new(0), UnderNode(&root_id)).unwrap();
+/// let file_id = tree.insert(Node::new(300), UnderNode(&docs_id)).unwrap();
+/// tree.insert(Node::new(20), UnderNode(&root_id)).unwrap();
+///
+/// assert_eq!(tree.aggregate(&docs_id), Ok(&300));
+/// assert_eq!(tree.aggregate(&root_id), Ok(&320));
+///
+/// *tree.get_mut(&file_id).unwrap() = 500;
+///
+/// assert_eq!(tree.aggregate(&docs_id), Ok(&500));
+/// assert_eq!(tree.total(), 520);
+/// ```
+///
+#[derive(Debug)]
+pub struct AugmentedTree<T, A>
+where
+    A: Aggregate<T>,
+{
+    tree: Tree<T>,
+    aggregate: A,
+    identity: A::Value,
+    summaries: Vec<Option<Summary<A::Value>>>,
+}
+
+///
+/// The aggregates kept for a single `Node`.
+///
+#[derive(Debug)]
+struct Summary<V> {
+    // the aggregate of the Node's whole subtree
+    value: V,
+    // the position of the Node among its siblings
+    position: usize,
+    // the aggregates of the children combined pairwise, laid out like a binary heap: entry i (for
+    // 0 < i < width) combines entries 2i and 2i + 1, and entries width and up are the aggregates
+    // of the children themselves, padded with the identity
+    combined: Vec<Option<V>>,
+    width: usize,
+}
+
+impl<T, A> AugmentedTree<T, A>
+where
+    A: Aggregate<T>,
+{
+    ///
+    /// Creates a new, empty `AugmentedTree` that summarizes its subtrees with the given
+    /// `Aggregate`.
+    ///
+    pub fn new(aggregate: A) -> AugmentedTree<T, A> {
+        AugmentedTree {
+            tree: Tree::new(),
+            identity: aggregate.identity(),
+            aggregate,
+            summaries: Vec::new(),
+        }
+    }
+
+    ///
+    /// Creates an `AugmentedTree` from an existing `Tree`, computing the aggregate of every `Node`
+    /// (including orphans) in a single post-order pass.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// # struct Count;
+    /// # impl Aggregate<i32> for Count {
+    /// #     type Value = usize;
+    /// #     fn identity(&self) -> usize { 0 }
+    /// #     fn lift(&self, _: &i32) -> usize { 1 }
+    /// #     fn combine(&self, left: &usize, right: &usize) -> usize { left + right }
+    /// # }
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.extend_children(&root_id, 1..4).unwrap();
+    ///
+    /// let tree = AugmentedTree::from_tree(tree, Count);
+    ///
+    /// assert_eq!(tree.aggregate(&root_id), Ok(&4));
+    /// ```
+    ///
+    pub fn from_tree(tree: Tree<T>, aggregate: A) -> AugmentedTree<T, A> {
+        let mut summaries = Vec::with_capacity(tree.nodes.len());
+        summaries.resize_with(tree.nodes.len(), || None);
+
+        let mut augmented = AugmentedTree {
+            tree,
+            identity: aggregate.identity(),
+            aggregate,
+            summaries,
+        };
+
+        let subtree_roots: Vec<NodeId> = augmented
+            .tree
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, node)| match *node {
+                Some(ref node) if node.parent().is_none() => {
+                    Some(augmented.tree.new_node_id(index))
+                }
+                _ => None,
+            })
+            .collect();
+
+        for subtree_root_id in subtree_roots {
+            let post_order: Vec<NodeId> = augmented
+                .tree
+                .traverse_post_order_ids(&subtree_root_id)
+                .expect("AugmentedTree::from_tree: Found an invalid subtree root.")
+                .collect();
+            for node_id in post_order {
+                augmented.rebuild(&node_id);
+            }
+        }
+
+        augmented
+    }
+
+    ///
+    /// Returns an immutable reference to the underlying `Tree`.
+    ///
+    /// All of the read-only `Tree` methods (traversals, `ancestors`, `height`, etc.) are available
+    /// through this reference.
+    ///
+    pub fn tree(&self) -> &Tree<T> {
+        &self.tree
+    }
+
+    ///
+    /// Consumes the `AugmentedTree`, dropping the aggregates and returning the underlying `Tree`.
+    ///
+    pub fn into_tree(self) -> Tree<T> {
+        self.tree
+    }
+
+    ///
+    /// Returns a `Some` value containing the `NodeId` of the root `Node` if it exists.  Otherwise a
+    /// `None` value is returned.
+    ///
+    pub fn root_node_id(&self) -> Option<&NodeId> {
+        self.tree.root_node_id()
+    }
+
+    ///
+    /// Get an immutable reference to a `Node`.
+    ///
+    /// Returns a `Result` containing the immutable reference or a `NodeIdError` if one occurred.
+    ///
+    pub fn get(&self, node_id: &NodeId) -> Result<&Node<T>, NodeIdError> {
+        self.tree.get(node_id)
+    }
+
+    ///
+    /// Get a mutable reference to the data of a `Node`.
+    ///
+    /// The aggregates of the `Node` and its ancestors are updated when the returned guard is
+    /// dropped, so keep it short-lived.
+    ///
+    /// Returns a `Result` containing the guard or a `NodeIdError` if one occurred.
+    ///
+    pub fn get_mut(&mut self, node_id: &NodeId) -> Result<DataMut<'_, T, A>, NodeIdError> {
+        self.tree.get(node_id)?;
+        Ok(DataMut {
+            tree: self,
+            node_id: node_id.clone(),
+        })
+    }
+
+    ///
+    /// Returns the aggregate of the subtree of the `Node` specified by the `NodeId` given (or a
+    /// `NodeIdError` if one occurred).
+    ///
+    pub fn aggregate(&self, node_id: &NodeId) -> Result<&A::Value, NodeIdError> {
+        self.tree.get(node_id)?;
+        Ok(self.value_unsafe(node_id))
+    }
+
+    ///
+    /// Returns the aggregate of the whole `Tree`, which is `Aggregate::identity` if the `Tree` has
+    /// no root.
+    ///
+    pub fn total(&self) -> A::Value
+    where
+        A::Value: Clone,
+    {
+        match self.tree.root_node_id() {
+            Some(root_id) => self.value_unsafe(root_id).clone(),
+            None => self.aggregate.identity(),
+        }
+    }
+
+    ///
+    /// Inserts a new `Node` into the `AugmentedTree`.  The `InsertBehavior` provided will determine
+    /// where the `Node` is inserted.
+    ///
+    /// Returns a `Result` containing the `NodeId` of the `Node` that was inserted or a
+    /// `NodeIdError` if one occurred.
+    ///
+    pub fn insert(
+        &mut self,
+        node: Node<T>,
+        behavior: InsertBehavior,
+    ) -> Result<NodeId, NodeIdError> {
+        let new_id = self.tree.insert(node, behavior)?;
+        if self.summaries.len() <= new_id.index {
+            self.summaries.resize_with(new_id.index + 1, || None);
+        }
+
+        // a new root takes the old one as its child
+        self.rebuild(&new_id);
+        if let Some(parent_id) = self.tree.get_unsafe(&new_id).parent().cloned() {
+            self.push_child(&parent_id);
+            self.update_ancestors(&parent_id);
+        }
+        Ok(new_id)
+    }
+
+    ///
+    /// Remove a `Node` from the `AugmentedTree`.  The `RemoveBehavior` provided determines what
+    /// happens to the removed `Node`'s children.
+    ///
+    /// Orphaned children keep their aggregates.
+    ///
+    /// Returns a `Result` containing the removed `Node` or a `NodeIdError` if one occurred.
+    ///
+    pub fn remove_node(
+        &mut self,
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+    ) -> Result<Node<T>, NodeIdError> {
+        self.tree.get(&node_id)?;
+
+        let parent_id = self.tree.get_unsafe(&node_id).parent().cloned();
+        let removed_ids: Vec<NodeId> = match behavior {
+            RemoveBehavior::DropChildren => self.tree.traverse_pre_order_ids(&node_id)?.collect(),
+            _ => vec![node_id.clone()],
+        };
+
+        let node = self.tree.remove_node(node_id, behavior)?;
+
+        for removed_id in &removed_ids {
+            self.summaries[removed_id.index] = None;
+        }
+        if let Some(parent_id) = parent_id {
+            self.rebuild(&parent_id);
+            self.update_ancestors(&parent_id);
+        }
+
+        Ok(node)
+    }
+
+    ///
+    /// Moves a `Node` in the `AugmentedTree` to a new location based upon the `MoveBehavior`
+    /// provided.
+    ///
+    /// Returns an empty `Result` or a `NodeIdError` if one occurred.
+    ///
+    pub fn move_node(
+        &mut self,
+        node_id: &NodeId,
+        behavior: MoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.tree.get(node_id)?;
+
+        let mut touched = vec![node_id.clone()];
+        touched.extend(self.tree.get_unsafe(node_id).parent().cloned());
+        if let MoveBehavior::ToParent(parent_id) = behavior {
+            touched.push(parent_id.clone());
+        }
+
+        self.tree.move_node(node_id, behavior)?;
+
+        self.update_all(&touched);
+        Ok(())
+    }
+
+    ///
+    /// Swap `Node`s in the `AugmentedTree` based upon the `SwapBehavior` provided.
+    ///
+    /// Returns an empty `Result` or a `NodeIdError` if one occurred.
+    ///
+    pub fn swap_nodes(
+        &mut self,
+        first_id: &NodeId,
+        second_id: &NodeId,
+        behavior: SwapBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.tree.get(first_id)?;
+        self.tree.get(second_id)?;
+
+        // every Node whose children can change is one of the swapped Nodes or one of their parents
+        let mut touched = vec![first_id.clone(), second_id.clone()];
+        touched.extend(self.tree.get_unsafe(first_id).parent().cloned());
+        touched.extend(self.tree.get_unsafe(second_id).parent().cloned());
+
+        self.tree.swap_nodes(first_id, second_id, behavior)?;
+
+        self.update_all(&touched);
+        Ok(())
+    }
+
+    ///
+    /// Sorts the children of one node, in-place, using compare to compare the nodes.  See
+    /// `Tree::sort_children_by` for more information.
+    ///
+    /// Returns an empty `Result` containing a `NodeIdError` if one occurred.
+    ///
+    pub fn sort_children_by<F>(&mut self, node_id: &NodeId, compare: F) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&Node<T>, &Node<T>) -> Ordering,
+    {
+        self.tree.sort_children_by(node_id, compare)?;
+        self.rebuild(node_id);
+        self.update_ancestors(node_id);
+        Ok(())
+    }
+
+    ///
+    /// Moves the node to a position amongst sibling nodes.  See `Tree::make_nth_sibling` for more
+    /// information.
+    ///
+    pub fn make_nth_sibling(&mut self, node_id: &NodeId, pos: usize) -> Result<(), NodeIdError> {
+        self.tree.make_nth_sibling(node_id, pos)?;
+        if let Some(parent_id) = self.tree.get_unsafe(node_id).parent().cloned() {
+            self.rebuild(&parent_id);
+            self.update_ancestors(&parent_id);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Rebuilds each of the given `Node`s that still exist, then updates all of their ancestors.
+    ///
+    /// A `Node` can be rebuilt before some of its children have their final aggregates, but every
+    /// child whose aggregate changes afterwards is one of the given `Node`s or one of their
+    /// ancestors, so the walk up from it passes its new aggregate on to the parent.
+    ///
+    fn update_all(&mut self, node_ids: &[NodeId]) {
+        let node_ids: Vec<&NodeId> = node_ids
+            .iter()
+            .filter(|node_id| self.tree.get(node_id).is_ok())
+            .collect();
+        for node_id in &node_ids {
+            self.rebuild(node_id);
+        }
+        for node_id in &node_ids {
+            self.update_ancestors(node_id);
+        }
+    }
+
+    ///
+    /// Updates the aggregate of a `Node` whose data changed, and then those of its ancestors.
+    ///
+    fn update(&mut self, node_id: &NodeId) {
+        self.recompute(node_id);
+        self.update_ancestors(node_id);
+    }
+
+    ///
+    /// Passes the aggregate of a `Node` on to each of its ancestors in turn, bottom-up.
+    ///
+    fn update_ancestors(&mut self, node_id: &NodeId) {
+        let mut current_id = node_id.clone();
+        while let Some(parent_id) = self.tree.get_unsafe(&current_id).parent().cloned() {
+            let position = self.summary_unsafe(&current_id).position;
+            self.update_child(&parent_id, position);
+            current_id = parent_id;
+        }
+    }
+
+    ///
+    /// Takes in a new last child of the `Node`, which only has to recombine the whole list of
+    /// children when it has outgrown the balanced tree.
+    ///
+    fn push_child(&mut self, node_id: &NodeId) {
+        let children = self.tree.get_unsafe(node_id).children();
+        let position = children.len() - 1;
+        if children.len() > self.summary_unsafe(node_id).width {
+            self.rebuild(node_id);
+        } else {
+            let child_index = children[position].index;
+            if let Some(ref mut summary) = self.summaries[child_index] {
+                summary.position = position;
+            }
+            self.update_child(node_id, position);
+        }
+    }
+
+    ///
+    /// Recombines the aggregates on the way from the child at the given position up to the `Node`.
+    ///
+    fn update_child(&mut self, node_id: &NodeId, position: usize) {
+        let mut index = (self.summary_unsafe(node_id).width + position) / 2;
+        while index > 0 {
+            let value = self.aggregate.combine(
+                self.entry(node_id, 2 * index),
+                self.entry(node_id, 2 * index + 1),
+            );
+            self.summary_mut_unsafe(node_id).combined[index] = Some(value);
+            index /= 2;
+        }
+        self.recompute(node_id);
+    }
+
+    ///
+    /// Recombines all of the `Node`'s children from scratch, after its list of children changed.
+    ///
+    fn rebuild(&mut self, node_id: &NodeId) {
+        let children = self.tree.get_unsafe(node_id).children();
+        for (position, child_id) in children.iter().enumerate() {
+            if let Some(ref mut summary) = self.summaries[child_id.index] {
+                summary.position = position;
+            }
+        }
+
+        let width = match children.len() {
+            0 => 0,
+            len => len.next_power_of_two(),
+        };
+        let mut combined: Vec<Option<A::Value>> = Vec::with_capacity(width);
+        combined.resize_with(width, || None);
+        for index in (1..width).rev() {
+            let value = {
+                let entry = |index: usize| match combined.get(index) {
+                    Some(value) => value
+                        .as_ref()
+                        .expect("AugmentedTree::rebuild: Combined the entries out of order."),
+                    None => self.child_value(node_id, index - width),
+                };
+                self.aggregate
+                    .combine(entry(2 * index), entry(2 * index + 1))
+            };
+            combined[index] = Some(value);
+        }
+
+        let summary = Summary {
+            value: self.aggregate.identity(),
+            // the parent of a new Node sets its position as soon as it takes it in
+            position: self.summaries[node_id.index]
+                .as_ref()
+                .map_or(0, |summary| summary.position),
+            combined,
+            width,
+        };
+        self.summaries[node_id.index] = Some(summary);
+        self.recompute(node_id);
+    }
+
+    ///
+    /// Recomputes the aggregate of a single `Node` from its data and its combined children.
+    ///
+    fn recompute(&mut self, node_id: &NodeId) {
+        let data = self.tree.get_unsafe(node_id).data();
+        let value = match self.summary_unsafe(node_id).width {
+            0 => self.aggregate.lift(data),
+            _ => self
+                .aggregate
+                .combine(&self.aggregate.lift(data), self.entry(node_id, 1)),
+        };
+        self.summary_mut_unsafe(node_id).value = value;
+    }
+
+    // the entry at the given index of the balanced tree over the Node's children
+    fn entry(&self, node_id: &NodeId, index: usize) -> &A::Value {
+        let summary = self.summary_unsafe(node_id);
+        if index < summary.width {
+            summary.combined[index].as_ref().expect(
+                "AugmentedTree::entry: Missing a combined aggregate.  Please report this issue!",
+            )
+        } else {
+            self.child_value(node_id, index - summary.width)
+        }
+    }
+
+    // the aggregate of the child at the given position, or the identity past the last child
+    fn child_value(&self, node_id: &NodeId, position: usize) -> &A::Value {
+        match self.tree.get_unsafe(node_id).children().get(position) {
+            Some(child_id) => self.value_unsafe(child_id),
+            None => &self.identity,
+        }
+    }
+
+    fn value_unsafe(&self, node_id: &NodeId) -> &A::Value {
+        &self.summary_unsafe(node_id).value
+    }
+
+    fn summary_unsafe(&self, node_id: &NodeId) -> &Summary<A::Value> {
+        self.summaries[node_id.index].as_ref().expect(
+            "AugmentedTree::summary_unsafe: Missing an aggregate for a valid NodeId.  Please report \
+             this issue!",
+        )
+    }
+
+    fn summary_mut_unsafe(&mut self, node_id: &NodeId) -> &mut Summary<A::Value> {
+        self.summaries[node_id.index].as_mut().expect(
+            "AugmentedTree::summary_mut_unsafe: Missing an aggregate for a valid NodeId.  Please \
+             report this issue!",
+        )
+    }
+}
+
+///
+/// A mutable reference to the data of a `Node` in an `AugmentedTree`, handed out by
+/// `AugmentedTree::get_mut`.
+///
+/// The aggregates of the `Node` and its ancestors are updated when this is dropped.
+///
+pub struct DataMut<'a, T: 'a, A: 'a>
+where
+    A: Aggregate<T>,
+{
+    tree: &'a mut AugmentedTree<T, A>,
+    node_id: NodeId,
+}
+
+impl<'a, T, A> Deref for DataMut<'a, T, A>
+where
+    A: Aggregate<T>,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.tree.tree.get_unsafe(&self.node_id).data()
+    }
+}
+
+impl<'a, T, A> DerefMut for DataMut<'a, T, A>
+where
+    A: Aggregate<T>,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        self.tree
+            .tree
+            .get_mut(&self.node_id)
+            .expect("DataMut::deref_mut: The NodeId was checked by AugmentedTree::get_mut.")
+            .data_mut()
+    }
+}
+
+impl<'a, T, A> Drop for DataMut<'a, T, A>
+where
+    A: Aggregate<T>,
+{
+    fn drop(&mut self) {
+        self.tree.update(&self.node_id);
+    }
+}
+
+#[cfg(test)]
+mod augmented_tree_tests {
+    use std::cell::Cell;
+
+    use super::super::*;
+
+    use test_fixtures::*;
+    use InsertBehavior::*;
+    use MoveBehavior::*;
+    use RemoveBehavior::*;
+    use SwapBehavior::*;
+
+    struct Sum;
+
+    impl Aggregate<i32> for Sum {
+        type Value = i32;
+
+        fn identity(&self) -> i32 {
+            0
+        }
+
+        fn lift(&self, data: &i32) -> i32 {
+            *data
+        }
+
+        fn combine(&self, left: &i32, right: &i32) -> i32 {
+            left + right
+        }
+    }
+
+    // not commutative, so the order of the children matters as well
+    struct Spell;
+
+    impl Aggregate<i32> for Spell {
+        type Value = String;
+
+        fn identity(&self) -> String {
+            String::new()
+        }
+
+        fn lift(&self, data: &i32) -> String {
+            data.to_string()
+        }
+
+        fn combine(&self, left: &String, right: &String) -> String {
+            format!("{}{}", left, right)
+        }
+    }
+
+    // counts its calls to combine
+    struct Counted<'a>(&'a Cell<usize>);
+
+    impl<'a> Aggregate<i32> for Counted<'a> {
+        type Value = i32;
+
+        fn identity(&self) -> i32 {
+            0
+        }
+
+        fn lift(&self, data: &i32) -> i32 {
+            *data
+        }
+
+        fn combine(&self, left: &i32, right: &i32) -> i32 {
+            self.0.set(self.0.get() + 1);
+            left + right
+        }
+    }
+
+    fn assert_consistent<A>(tree: &AugmentedTree<i32, A>)
+    where
+        A: Aggregate<i32>,
+        A::Value: PartialEq + ::std::fmt::Debug,
+    {
+        for (index, node) in tree.tree.nodes.iter().enumerate() {
+            if node.is_some() {
+                let node_id = tree.tree.new_node_id(index);
+                let expected = tree
+                    .tree
+                    .fold_subtree(&node_id, |data, children: &[A::Value]| {
+                        children
+                            .iter()
+                            .fold(tree.aggregate.lift(data), |value, child| {
+                                tree.aggregate.combine(&value, child)
+                            })
+                    })
+                    .unwrap();
+                assert_eq!(tree.aggregate(&node_id), Ok(&expected));
+            }
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let (tree, ids) = small_tree();
+        let mut tree = AugmentedTree::from_tree(tree, Spell);
+        assert_eq!(tree.aggregate(&ids[0]).unwrap(), "01342");
+        assert_eq!(tree.aggregate(&ids[1]).unwrap(), "134");
+
+        let new_root_id = tree.insert(Node::new(5), AsRoot).unwrap();
+        assert_eq!(tree.aggregate(&new_root_id).unwrap(), "501342");
+        assert_eq!(tree.total(), "501342");
+        assert_consistent(&tree);
+
+        let other_id = Tree::new().insert(Node::new(0), AsRoot).unwrap();
+        assert_eq!(
+            tree.insert(Node::new(6), UnderNode(&other_id)),
+            Err(NodeIdError::InvalidNodeIdForTree)
+        );
+        assert_eq!(
+            tree.aggregate(&other_id),
+            Err(NodeIdError::InvalidNodeIdForTree)
+        );
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let (tree, ids) = small_tree();
+        let mut tree = AugmentedTree::from_tree(tree, Sum);
+
+        tree.remove_node(ids[1].clone(), LiftChildren).unwrap();
+        assert_eq!(tree.total(), 9);
+        assert_consistent(&tree);
+
+        tree.remove_node(ids[3].clone(), OrphanChildren).unwrap();
+        assert_eq!(tree.total(), 6);
+        assert_consistent(&tree);
+
+        tree.remove_node(ids[0].clone(), DropChildren).unwrap();
+        assert_eq!(tree.total(), 0);
+        assert!(tree.aggregate(&ids[4]).is_err());
+
+        // freed slots are reused and get fresh aggregates
+        let root_id = tree.insert(Node::new(7), AsRoot).unwrap();
+        tree.insert(Node::new(8), UnderNode(&root_id)).unwrap();
+        assert_eq!(tree.total(), 15);
+        assert_consistent(&tree);
+    }
+
+    #[test]
+    fn test_move_node() {
+        let (tree, ids) = small_tree();
+        let mut tree = AugmentedTree::from_tree(tree, Spell);
+
+        tree.move_node(&ids[4], ToParent(&ids[2])).unwrap();
+        assert_eq!(tree.total(), "01324");
+        assert_consistent(&tree);
+
+        // moving a Node under its own descendant
+        tree.move_node(&ids[0], ToParent(&ids[3])).unwrap();
+        assert_consistent(&tree);
+
+        tree.move_node(&ids[2], ToRoot).unwrap();
+        assert_consistent(&tree);
+        assert_eq!(tree.total().len(), 5);
+    }
+
+    #[test]
+    fn test_swap_nodes() {
+        let behaviors = || vec![TakeChildren, LeaveChildren, ChildrenOnly];
+        for behavior_index in 0..3 {
+            for first in 0..5 {
+                for second in 0..5 {
+                    let (tree, ids) = small_tree();
+                    let mut tree = AugmentedTree::from_tree(tree, Spell);
+                    let behavior = behaviors().remove(behavior_index);
+                    if tree.swap_nodes(&ids[first], &ids[second], behavior).is_ok() {
+                        assert_consistent(&tree);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_reorder() {
+        let (tree, ids) = small_tree();
+        let mut tree = AugmentedTree::from_tree(tree, Spell);
+
+        tree.make_nth_sibling(&ids[4], 0).unwrap();
+        assert_eq!(tree.total(), "01432");
+
+        tree.sort_children_by(&ids[0], |a, b| b.data().cmp(a.data()))
+            .unwrap();
+        assert_eq!(tree.total(), "02143");
+        assert_consistent(&tree);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let (tree, ids) = small_tree();
+        let mut tree = AugmentedTree::from_tree(tree, Sum);
+
+        *tree.get_mut(&ids[3]).unwrap() += 10;
+        assert_eq!(tree.aggregate(&ids[1]), Ok(&18));
+        assert_eq!(tree.total(), 20);
+
+        {
+            let mut data = tree.get_mut(&ids[2]).unwrap();
+            assert_eq!(*data, 2);
+            *data = 0;
+        }
+        assert_eq!(tree.total(), 18);
+        assert_consistent(&tree);
+    }
+
+    #[test]
+    fn test_wide_node() {
+        let combines = Cell::new(0);
+        let mut tree = AugmentedTree::new(Counted(&combines));
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let folder_id = tree.insert(Node::new(0), UnderNode(&root_id)).unwrap();
+
+        // inserting takes a logarithmic number of combines per level, plus the occasional
+        // recombination of all children once the balanced tree is full
+        let mut child_ids = Vec::new();
+        for data in 1..1001 {
+            child_ids.push(tree.insert(Node::new(data), UnderNode(&folder_id)).unwrap());
+        }
+        assert!(combines.get() < 20_000, "{} combines", combines.get());
+        assert_eq!(tree.total(), 500_500);
+
+        // 10 levels of the balanced tree over 1000 children, and the folder's own data, and then
+        // the root with its single child
+        combines.set(0);
+        *tree.get_mut(&child_ids[500]).unwrap() += 1000;
+        assert_eq!(combines.get(), 12);
+        assert_eq!(tree.aggregate(&folder_id), Ok(&501_500));
+        assert_eq!(tree.total(), 501_500);
+
+        tree.remove_node(child_ids[0].clone(), DropChildren)
+            .unwrap();
+        tree.make_nth_sibling(&child_ids[999], 0).unwrap();
+        tree.move_node(&child_ids[998], ToParent(&root_id)).unwrap();
+        assert_eq!(tree.total(), 501_499);
+        assert_consistent(&tree);
+    }
+
+    #[test]
+    fn test_from_tree() {
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+        let child_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&child_id)).unwrap();
+        tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+        let orphan_id = tree.insert(Node::new(5), UnderNode(&child_id)).unwrap();
+        tree.insert(Node::new(6), UnderNode(&orphan_id)).unwrap();
+        tree.remove_node(child_id, OrphanChildren).unwrap();
+
+        let tree = AugmentedTree::from_tree(tree, Sum);
+        assert_eq!(tree.total(), 5);
+        assert_eq!(tree.aggregate(&orphan_id), Ok(&11));
+        assert_consistent(&tree);
+    }
+}
@@ -0,0 +1,74 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use ParseError;
+use ParseErrorKind;
+
+///
+/// Walks through the characters of a text input, keeping track of the line and column for
+/// `ParseError`s.
+///
+pub(crate) struct Cursor<'a> {
+    input: &'a str,
+    chars: Peekable<Chars<'a>>,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(input: &'a str) -> Cursor<'a> {
+        Cursor {
+            input,
+            chars: input.chars().peekable(),
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub(crate) fn peek(&mut self) -> Option<char> {
+        self.chars.peek().cloned()
+    }
+
+    pub(crate) fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    pub(crate) fn position(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+
+    pub(crate) fn error<E>(&self, kind: ParseErrorKind<E>) -> ParseError<E> {
+        ParseError::new(self.line, self.column, kind)
+    }
+
+    ///
+    /// Consumes a (possibly empty) run of characters matching the predicate.
+    ///
+    pub(crate) fn take_while<F>(&mut self, predicate: F) -> &'a str
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.offset;
+        while matches!(self.peek(), Some(c) if predicate(c)) {
+            self.next();
+        }
+        &self.input[start..self.offset]
+    }
+
+    ///
+    /// Skips whitespace.
+    ///
+    pub(crate) fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+}
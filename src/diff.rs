@@ -0,0 +1,857 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use NodeId;
+use Placement;
+use Tree;
+
+///
+/// A single step of the edit script produced by `Tree::diff_by_key` and
+/// `Tree::diff_by_edit_distance`.
+///
+/// `old_id`s refer to `Node`s of the `Tree` the diff was called on, `new_id`s (and the `NodeId`s
+/// inside of `Placement`s that describe where a `Node` ends up) refer to `Node`s of the other
+/// `Tree`.  `TreeDiff::matches` tells which `Node`s of the two `Tree`s were considered the same.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeEdit {
+    ///
+    /// A `Node` that only exists in the new `Tree` was inserted at the given `placement`.
+    ///
+    Insert {
+        new_id: NodeId,
+        placement: Placement,
+    },
+
+    ///
+    /// A `Node` that only exists in the old `Tree` was removed from the given `placement`, along
+    /// with whatever is still left of its subtree.  Descendants that are kept are reported as
+    /// `Move`s, and descendants that are removed as well get no `Delete` of their own.
+    ///
+    Delete {
+        old_id: NodeId,
+        placement: Placement,
+    },
+
+    ///
+    /// A `Node` that exists in both `Tree`s ended up under a different parent.
+    ///
+    Move {
+        old_id: NodeId,
+        new_id: NodeId,
+        from: Placement,
+        to: Placement,
+    },
+
+    ///
+    /// The children that a `Node` kept from the old `Tree` changed their order.  `order` lists
+    /// all of its children in the new `Tree`, including the ones that were inserted or moved in.
+    ///
+    Reorder {
+        old_id: NodeId,
+        new_id: NodeId,
+        order: Vec<NodeId>,
+    },
+
+    ///
+    /// A `Node` that exists in both `Tree`s has different data in the new `Tree`.
+    ///
+    Update { old_id: NodeId, new_id: NodeId },
+}
+
+///
+/// The differences between two `Tree`s, as an edit script that turns the old `Tree` into the new
+/// one.
+///
+/// The edits come in an order that can be replayed on the old `Tree`: first every `Insert`,
+/// `Move` and `Update` in the pre-order of the new `Tree` (so that a `Node`'s new parent is always
+/// in place before the `Node` itself), then every `Delete` in the pre-order of the old `Tree`, and
+/// finally every `Reorder`.  Inserted and moved `Node`s can simply be appended to their new
+/// parents while replaying; their `Placement`s tell the position they end up at.
+///
+/// Only `Node`s reachable from the root of each `Tree` are compared.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeDiff {
+    edits: Vec<TreeEdit>,
+    matches: Vec<(NodeId, NodeId)>,
+}
+
+impl TreeDiff {
+    ///
+    /// Returns the edit script.
+    ///
+    pub fn edits(&self) -> &[TreeEdit] {
+        &self.edits
+    }
+
+    ///
+    /// Returns the pairs of `(old_id, new_id)` of the `Node`s that exist in both `Tree`s, in the
+    /// pre-order of the new `Tree`.
+    ///
+    pub fn matches(&self) -> &[(NodeId, NodeId)] {
+        &self.matches
+    }
+
+    ///
+    /// Returns `true` if the two `Tree`s have the same shape and data.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    ///
+    /// Consumes the `TreeDiff`, returning the edit script.
+    ///
+    pub fn into_edits(self) -> Vec<TreeEdit> {
+        self.edits
+    }
+}
+
+impl<T> Tree<T>
+where
+    T: PartialEq,
+{
+    ///
+    /// Compares this `Tree` to `other`, matching up `Node`s by the key the given closure computes
+    /// from their data.  `Node`s with the same key are the same `Node` (even if they moved or
+    /// their data changed), so this finds inserts, deletes, moves, reorders and updates in
+    /// O(n) time.
+    ///
+    /// Keys are expected to be unique within each `Tree`.  If they are not, only the first `Node`
+    /// with a given key (in pre-order) is matched, and the others count as inserted or deleted.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut old: Tree<(char, i32)> = Tree::new();
+    /// let old_root_id = old.insert(Node::new(('r', 0)), AsRoot).unwrap();
+    /// let old_a_id = old.insert(Node::new(('a', 1)), UnderNode(&old_root_id)).unwrap();
+    /// old.insert(Node::new(('b', 2)), UnderNode(&old_root_id)).unwrap();
+    ///
+    /// let mut new: Tree<(char, i32)> = Tree::new();
+    /// let new_root_id = new.insert(Node::new(('r', 0)), AsRoot).unwrap();
+    /// let new_a_id = new.insert(Node::new(('a', 10)), UnderNode(&new_root_id)).unwrap();
+    /// let new_c_id = new.insert(Node::new(('c', 3)), UnderNode(&new_a_id)).unwrap();
+    ///
+    /// let diff = old.diff_by_key(&new, |&(key, _)| key);
+    /// assert_eq!(diff.edits().len(), 3);
+    /// assert_eq!(
+    ///     diff.edits()[0],
+    ///     TreeEdit::Update { old_id: old_a_id, new_id: new_a_id.clone() }
+    /// );
+    /// assert_eq!(
+    ///     diff.edits()[1],
+    ///     TreeEdit::Insert {
+    ///         new_id: new_c_id,
+    ///         placement: Placement { parent_id: Some(new_a_id), position: 0 },
+    ///     }
+    /// );
+    /// match diff.edits()[2] {
+    ///     TreeEdit::Delete { ref old_id, .. } => assert_eq!(old.get(old_id).unwrap().data().0, 'b'),
+    ///     ref edit => panic!("unexpected edit: {:?}", edit),
+    /// }
+    /// ```
+    ///
+    pub fn diff_by_key<K, F>(&self, other: &Tree<T>, mut key: F) -> TreeDiff
+    where
+        K: Hash + Eq,
+        F: FnMut(&T) -> K,
+    {
+        let mut old_ids_by_key = HashMap::new();
+        for old_id in self.reachable_ids() {
+            old_ids_by_key
+                .entry(key(self.get_unsafe(&old_id).data()))
+                .or_insert(old_id);
+        }
+
+        let mut old_to_new = HashMap::new();
+        for new_id in other.reachable_ids() {
+            if let Some(old_id) = old_ids_by_key.remove(&key(other.get_unsafe(&new_id).data())) {
+                old_to_new.insert(old_id, new_id);
+            }
+        }
+
+        self.edit_script(other, old_to_new)
+    }
+
+    ///
+    /// Compares this `Tree` to `other` without any notion of identity, using the Zhang-Shasha
+    /// algorithm to find a smallest set of `Node` inserts, deletes and data updates that turns
+    /// this `Tree` into `other`.
+    ///
+    /// A `Node` deleted this way leaves its children to its parent, so they are reported as
+    /// `Move`s.  Since this takes O(n²) memory and up to O(n⁴) time in the number of `Node`s, it is
+    /// meant for small `Tree`s; prefer `Tree::diff_by_key` when `Node`s can be told apart by
+    /// their data.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut old: Tree<&str> = Tree::new();
+    /// let old_root_id = old.insert(Node::new("f"), AsRoot).unwrap();
+    /// old.insert(Node::new("a"), UnderNode(&old_root_id)).unwrap();
+    /// old.insert(Node::new("c"), UnderNode(&old_root_id)).unwrap();
+    ///
+    /// let mut new: Tree<&str> = Tree::new();
+    /// let new_root_id = new.insert(Node::new("f"), AsRoot).unwrap();
+    /// new.insert(Node::new("a"), UnderNode(&new_root_id)).unwrap();
+    /// let new_d_id = new.insert(Node::new("d"), UnderNode(&new_root_id)).unwrap();
+    ///
+    /// let diff = old.diff_by_edit_distance(&new);
+    /// assert_eq!(diff.matches().len(), 3);
+    /// match diff.edits() {
+    ///     [TreeEdit::Update { new_id, .. }] => assert_eq!(new_id, &new_d_id),
+    ///     edits => panic!("unexpected edits: {:?}", edits),
+    /// }
+    /// ```
+    ///
+    pub fn diff_by_edit_distance(&self, other: &Tree<T>) -> TreeDiff {
+        let old = PostOrderIndex::new(self);
+        let new = PostOrderIndex::new(other);
+
+        let mut old_to_new = HashMap::new();
+        if !old.ids.is_empty() && !new.ids.is_empty() {
+            let mut distances = TreeDistances {
+                old: &old,
+                new: &new,
+                tree_distances: vec![vec![0; new.ids.len()]; old.ids.len()],
+            };
+            for &i in &old.key_roots {
+                for &j in &new.key_roots {
+                    distances.forest_distances(i, j);
+                }
+            }
+
+            let mut pending = vec![(old.ids.len() - 1, new.ids.len() - 1)];
+            while let Some((i, j)) = pending.pop() {
+                distances.map_subtrees(i, j, &mut old_to_new, &mut pending);
+            }
+        }
+
+        self.edit_script(other, old_to_new)
+    }
+
+    ///
+    /// Builds the edit script for the given matching of `Node`s of this `Tree` to `Node`s of
+    /// `other`.
+    ///
+    fn edit_script(&self, other: &Tree<T>, old_to_new: HashMap<NodeId, NodeId>) -> TreeDiff {
+        let new_to_old: HashMap<&NodeId, &NodeId> = old_to_new
+            .iter()
+            .map(|(old_id, new_id)| (new_id, old_id))
+            .collect();
+
+        let mut edits = Vec::new();
+        let mut matches = Vec::new();
+        let new_ids = other.reachable_ids();
+        for new_id in &new_ids {
+            let old_id = match new_to_old.get(new_id) {
+                Some(&old_id) => old_id,
+                None => {
+                    edits.push(TreeEdit::Insert {
+                        new_id: new_id.clone(),
+                        placement: other.placement(new_id),
+                    });
+                    continue;
+                }
+            };
+            matches.push((old_id.clone(), new_id.clone()));
+
+            let old_node = self.get_unsafe(old_id);
+            let new_node = other.get_unsafe(new_id);
+            let same_parent = match (old_node.parent(), new_node.parent()) {
+                (None, None) => true,
+                (Some(old_parent_id), Some(new_parent_id)) => {
+                    old_to_new.get(old_parent_id) == Some(new_parent_id)
+                }
+                _ => false,
+            };
+            if !same_parent {
+                edits.push(TreeEdit::Move {
+                    old_id: old_id.clone(),
+                    new_id: new_id.clone(),
+                    from: self.placement(old_id),
+                    to: other.placement(new_id),
+                });
+            }
+            if old_node.data() != new_node.data() {
+                edits.push(TreeEdit::Update {
+                    old_id: old_id.clone(),
+                    new_id: new_id.clone(),
+                });
+            }
+        }
+
+        for old_id in self.reachable_ids() {
+            // deleting the topmost deleted Node takes care of the rest of its subtree
+            let parent_kept = match self.get_unsafe(&old_id).parent() {
+                Some(parent_id) => old_to_new.contains_key(parent_id),
+                None => true,
+            };
+            if parent_kept && !old_to_new.contains_key(&old_id) {
+                let placement = self.placement(&old_id);
+                edits.push(TreeEdit::Delete { old_id, placement });
+            }
+        }
+
+        for (old_id, new_id) in &matches {
+            let new_children = other.get_unsafe(new_id).children();
+            let new_children_set: HashSet<&NodeId> = new_children.iter().collect();
+            // the children that stay under this Node, in their old order
+            let kept: Vec<&NodeId> = self
+                .get_unsafe(old_id)
+                .children()
+                .iter()
+                .filter_map(|old_child_id| old_to_new.get(old_child_id))
+                .filter(|&new_child_id| new_children_set.contains(new_child_id))
+                .collect();
+            let kept_set: HashSet<&NodeId> = kept.iter().cloned().collect();
+            let reordered = new_children
+                .iter()
+                .filter(|new_child_id| kept_set.contains(new_child_id))
+                .ne(kept.iter().cloned());
+            if reordered {
+                edits.push(TreeEdit::Reorder {
+                    old_id: old_id.clone(),
+                    new_id: new_id.clone(),
+                    order: new_children.clone(),
+                });
+            }
+        }
+
+        TreeDiff { edits, matches }
+    }
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Returns the `NodeId`s of all `Node`s reachable from the root, in pre-order.
+    ///
+    fn reachable_ids(&self) -> Vec<NodeId> {
+        match self.root {
+            Some(ref root_id) => self
+                .traverse_pre_order_ids(root_id)
+                .expect("Tree::reachable_ids: The root NodeId is always valid.")
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+///
+/// The `Node`s of a `Tree` numbered in post-order, as the Zhang-Shasha algorithm needs them.
+///
+struct PostOrderIndex<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    ids: Vec<NodeId>,
+    // the post-order number of the leftmost leaf descendant of each Node
+    leftmost_leaves: Vec<usize>,
+    // the Nodes with a left sibling, plus the root, in ascending order
+    key_roots: Vec<usize>,
+}
+
+impl<'a, T> PostOrderIndex<'a, T> {
+    fn new(tree: &'a Tree<T>) -> PostOrderIndex<'a, T> {
+        let ids: Vec<NodeId> = match tree.root {
+            Some(ref root_id) => tree
+                .traverse_post_order_ids(root_id)
+                .expect("PostOrderIndex::new: The root NodeId is always valid.")
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut numbers = HashMap::with_capacity(ids.len());
+        let mut leftmost_leaves = Vec::with_capacity(ids.len());
+        for (number, node_id) in ids.iter().enumerate() {
+            // children come before their parents in post-order
+            let leftmost_leaf = match tree.get_unsafe(node_id).children().first() {
+                Some(first_child_id) => leftmost_leaves[numbers[first_child_id]],
+                None => number,
+            };
+            leftmost_leaves.push(leftmost_leaf);
+            numbers.insert(node_id, number);
+        }
+
+        let mut seen = HashSet::new();
+        let mut key_roots: Vec<usize> = (0..ids.len())
+            .rev()
+            .filter(|&number| seen.insert(leftmost_leaves[number]))
+            .collect();
+        key_roots.reverse();
+
+        PostOrderIndex {
+            tree,
+            ids,
+            leftmost_leaves,
+            key_roots,
+        }
+    }
+}
+
+struct TreeDistances<'a, 'b: 'a, T: 'b> {
+    old: &'a PostOrderIndex<'b, T>,
+    new: &'a PostOrderIndex<'b, T>,
+    tree_distances: Vec<Vec<usize>>,
+}
+
+impl<'a, 'b, T> TreeDistances<'a, 'b, T>
+where
+    T: PartialEq,
+{
+    fn update_cost(&self, i: usize, j: usize) -> usize {
+        let old_data = self.old.tree.get_unsafe(&self.old.ids[i]).data();
+        let new_data = self.new.tree.get_unsafe(&self.new.ids[j]).data();
+        if old_data == new_data {
+            0
+        } else {
+            1
+        }
+    }
+
+    ///
+    /// Computes the distances between the forests of the subtrees rooted at `i` and `j`, filling
+    /// in the tree distances of every pair of `Node`s on their leftmost paths along the way.
+    ///
+    /// Row `x` stands for the old forest of `Node`s `l(i)..l(i) + x`, column `y` for the new forest
+    /// of `Node`s `l(j)..l(j) + y`.
+    ///
+    fn forest_distances(&mut self, i: usize, j: usize) -> Vec<Vec<usize>> {
+        let old_leaf = self.old.leftmost_leaves[i];
+        let new_leaf = self.new.leftmost_leaves[j];
+        let rows = i - old_leaf + 2;
+        let columns = j - new_leaf + 2;
+
+        let mut forest = vec![vec![0; columns]; rows];
+        for (x, row) in forest.iter_mut().enumerate() {
+            row[0] = x;
+        }
+        for (y, distance) in forest[0].iter_mut().enumerate() {
+            *distance = y;
+        }
+
+        for x in 1..rows {
+            for y in 1..columns {
+                let (old_number, new_number) = (old_leaf + x - 1, new_leaf + y - 1);
+                let delete = forest[x - 1][y] + 1;
+                let insert = forest[x][y - 1] + 1;
+                let old_subtree_leaf = self.old.leftmost_leaves[old_number];
+                let new_subtree_leaf = self.new.leftmost_leaves[new_number];
+
+                if old_subtree_leaf == old_leaf && new_subtree_leaf == new_leaf {
+                    let update = forest[x - 1][y - 1] + self.update_cost(old_number, new_number);
+                    forest[x][y] = cmp::min(cmp::min(delete, insert), update);
+                    self.tree_distances[old_number][new_number] = forest[x][y];
+                } else {
+                    let subtrees = forest[old_subtree_leaf - old_leaf][new_subtree_leaf - new_leaf]
+                        + self.tree_distances[old_number][new_number];
+                    forest[x][y] = cmp::min(cmp::min(delete, insert), subtrees);
+                }
+            }
+        }
+
+        forest
+    }
+
+    ///
+    /// Walks back through the forest distances of the subtrees rooted at `i` and `j`, recording
+    /// the `Node`s that are mapped onto each other and queueing up the pairs of subtrees that
+    /// still need to be walked through.
+    ///
+    fn map_subtrees(
+        &mut self,
+        i: usize,
+        j: usize,
+        old_to_new: &mut HashMap<NodeId, NodeId>,
+        pending: &mut Vec<(usize, usize)>,
+    ) {
+        let forest = self.forest_distances(i, j);
+        let old_leaf = self.old.leftmost_leaves[i];
+        let new_leaf = self.new.leftmost_leaves[j];
+
+        let (mut x, mut y) = (i - old_leaf + 1, j - new_leaf + 1);
+        while x > 0 || y > 0 {
+            if x > 0 && y > 0 {
+                let (old_number, new_number) = (old_leaf + x - 1, new_leaf + y - 1);
+                let old_subtree_leaf = self.old.leftmost_leaves[old_number];
+                let new_subtree_leaf = self.new.leftmost_leaves[new_number];
+
+                if old_subtree_leaf == old_leaf && new_subtree_leaf == new_leaf {
+                    let update = self.update_cost(old_number, new_number);
+                    if forest[x][y] == forest[x - 1][y - 1] + update {
+                        old_to_new.insert(
+                            self.old.ids[old_number].clone(),
+                            self.new.ids[new_number].clone(),
+                        );
+                        x -= 1;
+                        y -= 1;
+                        continue;
+                    }
+                } else {
+                    let before = forest[old_subtree_leaf - old_leaf][new_subtree_leaf - new_leaf];
+                    if forest[x][y] == before + self.tree_distances[old_number][new_number] {
+                        pending.push((old_number, new_number));
+                        x = old_subtree_leaf - old_leaf;
+                        y = new_subtree_leaf - new_leaf;
+                        continue;
+                    }
+                }
+            }
+
+            if x > 0 && forest[x][y] == forest[x - 1][y] + 1 {
+                x -= 1;
+            } else {
+                y -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::super::*;
+
+    use std::collections::HashMap;
+
+    use test_fixtures::*;
+    use InsertBehavior::*;
+    use MoveBehavior::*;
+    use RemoveBehavior::*;
+
+    // the depth and data of every Node in pre-order, which pins down the whole Tree
+    fn shape(tree: &Tree<i32>) -> Vec<(usize, i32)> {
+        let mut shape = Vec::new();
+        if let Some(root_id) = tree.root_node_id() {
+            for node_id in tree.traverse_pre_order_ids(root_id).unwrap() {
+                let depth = tree.ancestor_ids(&node_id).unwrap().count();
+                shape.push((depth, *tree.get(&node_id).unwrap().data()));
+            }
+        }
+        shape
+    }
+
+    ///
+    /// Replays the edit script on the old `Tree`, appending inserted and moved `Node`s and
+    /// putting them in place afterwards.
+    ///
+    fn replay(mut tree: Tree<i32>, new: &Tree<i32>, diff: &TreeDiff) -> Tree<i32> {
+        let mut ids: HashMap<NodeId, NodeId> = diff
+            .matches()
+            .iter()
+            .map(|(old_id, new_id)| (new_id.clone(), old_id.clone()))
+            .collect();
+        let mut arrivals = Vec::new();
+        let mut reorders = Vec::new();
+
+        for edit in diff.edits() {
+            match *edit {
+                TreeEdit::Insert {
+                    ref new_id,
+                    ref placement,
+                } => {
+                    let data = *new.get(new_id).unwrap().data();
+                    let node_id = match placement.parent_id {
+                        Some(ref parent_id) => {
+                            tree.insert(Node::new(data), UnderNode(&ids[parent_id]))
+                        }
+                        None => tree.insert(Node::new(data), AsRoot),
+                    }
+                    .unwrap();
+                    ids.insert(new_id.clone(), node_id.clone());
+                    arrivals.push((node_id, placement.clone()));
+                }
+                TreeEdit::Move {
+                    ref old_id, ref to, ..
+                } => {
+                    match to.parent_id {
+                        Some(ref parent_id) => tree.move_node(old_id, ToParent(&ids[parent_id])),
+                        None => tree.move_node(old_id, ToRoot),
+                    }
+                    .unwrap();
+                    arrivals.push((old_id.clone(), to.clone()));
+                }
+                TreeEdit::Update {
+                    ref old_id,
+                    ref new_id,
+                } => {
+                    tree.replace_data(old_id, *new.get(new_id).unwrap().data())
+                        .unwrap();
+                }
+                TreeEdit::Delete { ref old_id, .. } => {
+                    tree.remove_node(old_id.clone(), DropChildren).unwrap();
+                }
+                TreeEdit::Reorder { ref order, .. } => reorders.push(order.clone()),
+            }
+        }
+
+        arrivals.sort_by_key(|(_, placement)| placement.position);
+        for (node_id, placement) in arrivals {
+            if placement.parent_id.is_some() {
+                tree.make_nth_sibling(&node_id, placement.position).unwrap();
+            }
+        }
+        for order in reorders {
+            for (position, new_id) in order.iter().enumerate() {
+                tree.make_nth_sibling(&ids[new_id], position).unwrap();
+            }
+        }
+
+        tree
+    }
+
+    fn check_replay(old: Tree<i32>, new: &Tree<i32>, diff: &TreeDiff) {
+        assert_eq!(shape(&replay(old, new, diff)), shape(new));
+    }
+
+    #[test]
+    fn test_identical() {
+        let nodes = [(None, 0), (Some(0), 1), (Some(0), 2), (Some(1), 3)];
+        let (old, _) = from_parents(&nodes);
+        let (new, _) = from_parents(&nodes);
+
+        let diff = old.diff_by_key(&new, |&data| data);
+        assert!(diff.is_empty());
+        assert_eq!(diff.matches().len(), 4);
+        assert!(old.diff_by_edit_distance(&new).is_empty());
+
+        let empty: Tree<i32> = Tree::new();
+        assert!(empty.diff_by_key(&Tree::new(), |&data| data).is_empty());
+        assert!(empty.diff_by_edit_distance(&Tree::new()).is_empty());
+    }
+
+    #[test]
+    fn test_keyed_edits() {
+        //      0              0
+        //     / \            / \
+        //    1   2    =>    2   1
+        //   / \            / \
+        //  3   4          4   5
+        let (old, old_ids) = from_parents(&[
+            (None, 0),
+            (Some(0), 1),
+            (Some(0), 2),
+            (Some(1), 3),
+            (Some(1), 4),
+        ]);
+        let (new, new_ids) = from_parents(&[
+            (None, 0),
+            (Some(0), 2),
+            (Some(0), 1),
+            (Some(1), 4),
+            (Some(1), 5),
+        ]);
+
+        let diff = old.diff_by_key(&new, |&data| data);
+        assert_eq!(
+            diff.edits(),
+            &[
+                TreeEdit::Move {
+                    old_id: old_ids[4].clone(),
+                    new_id: new_ids[3].clone(),
+                    from: Placement {
+                        parent_id: Some(old_ids[1].clone()),
+                        position: 1,
+                    },
+                    to: Placement {
+                        parent_id: Some(new_ids[1].clone()),
+                        position: 0,
+                    },
+                },
+                TreeEdit::Insert {
+                    new_id: new_ids[4].clone(),
+                    placement: Placement {
+                        parent_id: Some(new_ids[1].clone()),
+                        position: 1,
+                    },
+                },
+                TreeEdit::Delete {
+                    old_id: old_ids[3].clone(),
+                    placement: Placement {
+                        parent_id: Some(old_ids[1].clone()),
+                        position: 0,
+                    },
+                },
+                TreeEdit::Reorder {
+                    old_id: old_ids[0].clone(),
+                    new_id: new_ids[0].clone(),
+                    order: vec![new_ids[1].clone(), new_ids[2].clone()],
+                },
+            ][..]
+        );
+        check_replay(old, &new, &diff);
+    }
+
+    #[test]
+    fn test_keyed_updates_and_roots() {
+        // keys are the data divided by 10, so 11 -> 12 is an update of the same Node
+        let (old, old_ids) = from_parents(&[(None, 0), (Some(0), 11), (Some(1), 20)]);
+        let (new, new_ids) = from_parents(&[(None, 12), (Some(0), 30), (Some(1), 0)]);
+
+        let diff = old.diff_by_key(&new, |&data| data / 10);
+        assert_eq!(
+            diff.matches(),
+            &[
+                (old_ids[1].clone(), new_ids[0].clone()),
+                (old_ids[0].clone(), new_ids[2].clone()),
+            ][..]
+        );
+        assert!(diff.edits().contains(&TreeEdit::Update {
+            old_id: old_ids[1].clone(),
+            new_id: new_ids[0].clone(),
+        }));
+        check_replay(old, &new, &diff);
+    }
+
+    #[test]
+    fn test_keyed_replay() {
+        let old_nodes = [
+            (None, 0),
+            (Some(0), 1),
+            (Some(0), 2),
+            (Some(1), 3),
+            (Some(1), 4),
+            (Some(2), 5),
+            (Some(5), 6),
+            (Some(5), 7),
+            (Some(0), 8),
+        ];
+        let cases: Vec<Vec<(Option<usize>, i32)>> = vec![
+            // a subtree moves deeper and its old siblings swap places
+            vec![
+                (None, 0),
+                (Some(0), 8),
+                (Some(0), 2),
+                (Some(2), 1),
+                (Some(3), 4),
+                (Some(3), 3),
+                (Some(2), 5),
+                (Some(6), 7),
+                (Some(6), 6),
+            ],
+            // the root is replaced and most of the old Tree is dropped
+            vec![(None, 9), (Some(0), 5), (Some(1), 10), (Some(0), 1)],
+            // a Node moves above its old parent
+            vec![
+                (None, 0),
+                (Some(0), 6),
+                (Some(1), 5),
+                (Some(2), 7),
+                (Some(1), 2),
+                (Some(0), 1),
+            ],
+            vec![(None, 4)],
+        ];
+
+        for new_nodes in cases {
+            let (old, _) = from_parents(&old_nodes);
+            let (new, _) = from_parents(&new_nodes);
+            let diff = old.diff_by_key(&new, |&data| data);
+            check_replay(old, &new, &diff);
+        }
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        // the example from the Zhang-Shasha paper, with a distance of 2
+        //        f              f
+        //       / \            / \
+        //      d   e   =>     c   e
+        //     / \             |
+        //    a   c            d
+        //        |           / \
+        //        b          a   b
+        let (old, old_ids) = from_parents(&[
+            (None, 6),
+            (Some(0), 4),
+            (Some(1), 1),
+            (Some(1), 3),
+            (Some(3), 2),
+            (Some(0), 5),
+        ]);
+        let (new, new_ids) = from_parents(&[
+            (None, 6),
+            (Some(0), 3),
+            (Some(1), 4),
+            (Some(2), 1),
+            (Some(2), 2),
+            (Some(0), 5),
+        ]);
+
+        let diff = old.diff_by_edit_distance(&new);
+        assert_eq!(diff.matches().len(), 5);
+        let inserted: Vec<&NodeId> = diff
+            .edits()
+            .iter()
+            .filter_map(|edit| match *edit {
+                TreeEdit::Insert { ref new_id, .. } => Some(new_id),
+                _ => None,
+            })
+            .collect();
+        let deleted: Vec<&NodeId> = diff
+            .edits()
+            .iter()
+            .filter_map(|edit| match *edit {
+                TreeEdit::Delete { ref old_id, .. } => Some(old_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(inserted, vec![&new_ids[1]]);
+        assert_eq!(deleted, vec![&old_ids[3]]);
+        check_replay(old, &new, &diff);
+    }
+
+    #[test]
+    fn test_edit_distance_replay() {
+        let old_nodes = [
+            (None, 1),
+            (Some(0), 2),
+            (Some(1), 3),
+            (Some(1), 4),
+            (Some(0), 5),
+            (Some(4), 6),
+        ];
+        let cases: Vec<Vec<(Option<usize>, i32)>> = vec![
+            // relabels only
+            vec![
+                (None, 1),
+                (Some(0), 7),
+                (Some(1), 3),
+                (Some(1), 8),
+                (Some(0), 5),
+                (Some(4), 6),
+            ],
+            // a Node in the middle is dropped, lifting its children
+            vec![
+                (None, 1),
+                (Some(0), 3),
+                (Some(0), 4),
+                (Some(0), 5),
+                (Some(3), 6),
+            ],
+            // a new root on top of the old one
+            vec![
+                (None, 9),
+                (Some(0), 1),
+                (Some(1), 2),
+                (Some(2), 3),
+                (Some(2), 4),
+                (Some(1), 5),
+                (Some(5), 6),
+            ],
+            // the root is dropped
+            vec![(None, 2), (Some(0), 3), (Some(0), 4)],
+            vec![(None, 8)],
+        ];
+
+        for new_nodes in cases {
+            let (old, _) = from_parents(&old_nodes);
+            let (new, _) = from_parents(&new_nodes);
+            let diff = old.diff_by_edit_distance(&new);
+            check_replay(old, &new, &diff);
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use std::fmt;
+
+use Node;
+use NodeId;
+use Tree;
+use WriteError;
+
+type LabelFn<'a, T> = Box<dyn Fn(&Node<T>) -> String + 'a>;
+type AttributesFn<'a, T> = Box<dyn Fn(&Node<T>) -> Vec<(&'static str, String)> + 'a>;
+
+///
+/// Settings for `Tree::write_dot`, built up from `DotOptions::new()`.
+///
+/// By default the whole `Tree` is written, `Node`s have no labels (Graphviz shows their names
+/// instead) and orphaned `Node`s are left out.
+///
+pub struct DotOptions<'a, T: 'a> {
+    name: String,
+    label: Option<LabelFn<'a, T>>,
+    attributes: Option<AttributesFn<'a, T>>,
+    show_node_ids: bool,
+    subtree: Option<NodeId>,
+    show_orphans: bool,
+}
+
+impl<'a, T> DotOptions<'a, T> {
+    ///
+    /// Creates a new `DotOptions` with the default settings.
+    ///
+    /// ```
+    /// use id_tree::DotOptions;
+    ///
+    /// let _options: DotOptions<i32> = DotOptions::new();
+    /// ```
+    ///
+    pub fn new() -> DotOptions<'a, T> {
+        DotOptions {
+            name: "tree".to_string(),
+            label: None,
+            attributes: None,
+            show_node_ids: false,
+            subtree: None,
+            show_orphans: false,
+        }
+    }
+
+    ///
+    /// Sets the name of the graph.  Defaults to `tree`.
+    ///
+    pub fn with_name(mut self, name: &str) -> DotOptions<'a, T> {
+        self.name = name.to_string();
+        self
+    }
+
+    ///
+    /// Sets the closure that produces the label of each `Node`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let _options = DotOptions::<i32>::new().with_label(|node| node.data().to_string());
+    /// ```
+    ///
+    pub fn with_label<F>(mut self, label: F) -> DotOptions<'a, T>
+    where
+        F: Fn(&Node<T>) -> String + 'a,
+    {
+        self.label = Some(Box::new(label));
+        self
+    }
+
+    ///
+    /// Sets the closure that produces extra Graphviz attributes (such as `color` or `shape`) for
+    /// each `Node`, as pairs of attribute names and values.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let _options = DotOptions::<i32>::new().with_attributes(|node| {
+    ///     if *node.data() < 0 {
+    ///         vec![("color", "red".to_string()), ("shape", "box".to_string())]
+    ///     } else {
+    ///         vec![]
+    ///     }
+    /// });
+    /// ```
+    ///
+    pub fn with_attributes<F>(mut self, attributes: F) -> DotOptions<'a, T>
+    where
+        F: Fn(&Node<T>) -> Vec<(&'static str, String)> + 'a,
+    {
+        self.attributes = Some(Box::new(attributes));
+        self
+    }
+
+    ///
+    /// Sets whether each `Node` is annotated with the index of its `NodeId` (as an `xlabel`).
+    ///
+    pub fn with_node_ids(mut self, show_node_ids: bool) -> DotOptions<'a, T> {
+        self.show_node_ids = show_node_ids;
+        self
+    }
+
+    ///
+    /// Only writes the subtree below (and including) the given `Node`.
+    ///
+    pub fn with_subtree(mut self, node_id: &NodeId) -> DotOptions<'a, T> {
+        self.subtree = Some(node_id.clone());
+        self
+    }
+
+    ///
+    /// Sets whether orphaned `Node`s (and everything below them) are written as well, each one
+    /// in a separate dashed cluster.  Has no effect when writing a single subtree.
+    ///
+    pub fn with_orphans(mut self, show_orphans: bool) -> DotOptions<'a, T> {
+        self.show_orphans = show_orphans;
+        self
+    }
+}
+
+impl<'a, T> Default for DotOptions<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Writes the `Tree` as a Graphviz DOT `digraph`, with an edge from every `Node` to each of
+    /// its children.
+    ///
+    /// Returns a `WriteError` if the `NodeId` given to `DotOptions::with_subtree` is invalid or if
+    /// the writer fails.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(-2), UnderNode(&root_id)).unwrap();
+    ///
+    /// let options = DotOptions::<i32>::new()
+    ///     .with_label(|node| node.data().to_string())
+    ///     .with_attributes(|node| {
+    ///         if *node.data() < 0 {
+    ///             vec![("color", "red".to_string())]
+    ///         } else {
+    ///             vec![]
+    ///         }
+    ///     });
+    ///
+    /// let mut s = String::new();
+    /// tree.write_dot(&mut s, &options).unwrap();
+    /// assert_eq!(&s, "\
+    /// digraph \"tree\" {
+    ///     n0 [label=\"0\"];
+    ///     n0 -> n1;
+    ///     n0 -> n2;
+    ///     n1 [label=\"1\"];
+    ///     n2 [label=\"-2\", color=\"red\"];
+    /// }
+    /// ");
+    /// ```
+    ///
+    pub fn write_dot<W: fmt::Write>(
+        &self,
+        w: &mut W,
+        options: &DotOptions<T>,
+    ) -> Result<(), WriteError> {
+        if let Some(ref node_id) = options.subtree {
+            self.get(node_id)?;
+        }
+
+        w.write_str("digraph ")?;
+        write_quoted(w, &options.name)?;
+        w.write_str(" {\n")?;
+
+        match options.subtree {
+            Some(ref node_id) => self.write_dot_subtree(w, options, node_id, "    ")?,
+            None => {
+                if let Some(root_id) = self.root_node_id() {
+                    self.write_dot_subtree(w, options, root_id, "    ")?;
+                }
+
+                if options.show_orphans {
+                    for (index, node) in self.nodes.iter().enumerate() {
+                        let is_orphan = matches!(*node, Some(ref node) if node.parent().is_none());
+                        let node_id = self.new_node_id(index);
+                        if !is_orphan || self.root_node_id() == Some(&node_id) {
+                            continue;
+                        }
+
+                        writeln!(w, "    subgraph cluster_orphan_{} {{", index)?;
+                        w.write_str("        style=\"dashed\";\n")?;
+                        self.write_dot_subtree(w, options, &node_id, "        ")?;
+                        w.write_str("    }\n")?;
+                    }
+                }
+            }
+        }
+
+        w.write_str("}\n")?;
+        Ok(())
+    }
+
+    fn write_dot_subtree<W: fmt::Write>(
+        &self,
+        w: &mut W,
+        options: &DotOptions<T>,
+        node_id: &NodeId,
+        indent: &str,
+    ) -> fmt::Result {
+        for node_id in self.traverse_pre_order_ids(node_id).unwrap() {
+            let node = self.get_unsafe(&node_id);
+
+            let mut attributes = Vec::new();
+            if let Some(ref label) = options.label {
+                attributes.push(("label", label(node)));
+            }
+            if options.show_node_ids {
+                attributes.push(("xlabel", node_id.index.to_string()));
+            }
+            if let Some(ref extra) = options.attributes {
+                attributes.extend(extra(node));
+            }
+
+            write!(w, "{}n{}", indent, node_id.index)?;
+            if !attributes.is_empty() {
+                w.write_str(" [")?;
+                for (i, &(name, ref value)) in attributes.iter().enumerate() {
+                    if i > 0 {
+                        w.write_str(", ")?;
+                    }
+                    write!(w, "{}=", name)?;
+                    write_quoted(w, value)?;
+                }
+                w.write_str("]")?;
+            }
+            w.write_str(";\n")?;
+
+            for child_id in node.children() {
+                writeln!(w, "{}n{} -> n{};", indent, node_id.index, child_id.index)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// Writes a DOT string literal.
+///
+fn write_quoted<W: fmt::Write>(w: &mut W, value: &str) -> fmt::Result {
+    w.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => w.write_str("\\\"")?,
+            '\\' => w.write_str("\\\\")?,
+            '\n' => w.write_str("\\n")?,
+            c => w.write_char(c)?,
+        }
+    }
+    w.write_char('"')
+}
+
+#[cfg(test)]
+mod dot_tests {
+    use super::super::*;
+    use test_fixtures::*;
+    use RemoveBehavior::*;
+
+    fn write(tree: &Tree<&'static str>, options: &DotOptions<&'static str>) -> String {
+        let mut s = String::new();
+        tree.write_dot(&mut s, options).unwrap();
+        s
+    }
+
+    #[test]
+    fn test_plain() {
+        let (tree, _) = with_orphans();
+
+        assert_eq!(
+            write(&tree, &DotOptions::new()),
+            "\
+digraph \"tree\" {
+    n0;
+    n0 -> n1;
+    n0 -> n2;
+    n1;
+    n1 -> n3;
+    n3;
+    n2;
+}
+"
+        );
+
+        let mut s = String::new();
+        Tree::<i32>::new()
+            .write_dot(&mut s, &DotOptions::new().with_name("empty"))
+            .unwrap();
+        assert_eq!(s, "digraph \"empty\" {\n}\n");
+    }
+
+    #[test]
+    fn test_labels_attributes_and_ids() {
+        let (tree, ids) = with_orphans();
+
+        let options = DotOptions::<&str>::new()
+            .with_label(|node| node.data().to_string())
+            .with_attributes(|node| {
+                if node.children().is_empty() {
+                    vec![("shape", "box".to_string())]
+                } else {
+                    vec![]
+                }
+            })
+            .with_node_ids(true)
+            .with_subtree(&ids[1]);
+
+        assert_eq!(
+            write(&tree, &options),
+            "\
+digraph \"tree\" {
+    n1 [label=\"one\", xlabel=\"1\"];
+    n1 -> n3;
+    n3 [label=\"three\", xlabel=\"3\", shape=\"box\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_orphans() {
+        let (tree, _) = with_orphans();
+
+        let options = DotOptions::<&str>::new()
+            .with_label(|node| node.data().to_string())
+            .with_orphans(true);
+
+        assert_eq!(
+            write(&tree, &options),
+            "\
+digraph \"tree\" {
+    n0 [label=\"zero\"];
+    n0 -> n1;
+    n0 -> n2;
+    n1 [label=\"one\"];
+    n1 -> n3;
+    n3 [label=\"three\"];
+    n2 [label=\"two\"];
+    subgraph cluster_orphan_5 {
+        style=\"dashed\";
+        n5 [label=\"five\"];
+        n5 -> n6;
+        n6 [label=\"\\\"six\\\"\"];
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_invalid_subtree() {
+        let (tree, ids) = with_orphans();
+        let (mut other, _) = with_orphans();
+        other
+            .remove_node(other.root_node_id().unwrap().clone(), DropChildren)
+            .unwrap();
+
+        let mut s = String::new();
+        let result = other.write_dot(&mut s, &DotOptions::new().with_subtree(&ids[5]));
+        assert_eq!(
+            result,
+            Err(WriteError::InvalidNodeId(NodeIdError::InvalidNodeIdForTree))
+        );
+        assert!(tree
+            .write_dot(&mut s, &DotOptions::new().with_subtree(&ids[5]))
+            .is_ok());
+    }
+}
@@ -1,15 +1,37 @@
 use std::error::Error;
 use std::fmt;
+#[cfg(feature = "fs")]
+use std::io;
+
+use NodeId;
 
 ///
 /// Enum for all of the possible `NodeId` errors that could occur.
 ///
+/// Besides invalid `NodeId`s, this also covers requests that would break the structure of a
+/// `Tree` if they were carried out.  More variants may be added in the future, so `match`es on it
+/// need a wildcard arm.
+///
 #[derive(Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum NodeIdError {
     /// Occurs when a `NodeId` is used on a `Tree` from which it did not originate.
     InvalidNodeIdForTree,
     /// Occurs when a `NodeId` is used on a `Tree` after the corresponding `Node` has been removed.
     NodeIdNoLongerValid,
+    /// Occurs when a `NodeId` points past the end of the `Tree`'s storage.  This can only happen
+    /// with `NodeId`s that were not handed out by the `Tree` they are used on (for example ones
+    /// that were deserialized).
+    IndexOutOfBounds,
+    /// Occurs when an operation would make a `Node` its own ancestor.
+    WouldCreateCycle,
+    /// Occurs when an operation that needs two different `Node`s is given the same `NodeId`
+    /// twice.
+    SameNode,
+    /// Occurs when an operation on a `Node`'s siblings is given a `Node` that has no parent.
+    NotASibling,
+    /// Occurs when a `Node` is to be moved to a position past the last of its siblings.
+    PositionOutOfRange,
 }
 
 impl NodeIdError {
@@ -20,6 +42,15 @@ impl NodeIdError {
                 "The given NodeId is no longer valid. The Node in question has been \
                  removed."
             }
+            NodeIdError::IndexOutOfBounds => {
+                "The given NodeId points outside of the Tree. It was not created by this Tree."
+            }
+            NodeIdError::WouldCreateCycle => "The operation would make a Node its own ancestor.",
+            NodeIdError::SameNode => "The operation requires two different Nodes.",
+            NodeIdError::NotASibling => "The given Node has no parent and therefore no siblings.",
+            NodeIdError::PositionOutOfRange => {
+                "The given position is past the last of the Node's siblings."
+            }
         }
     }
 }
@@ -35,3 +66,405 @@ impl Error for NodeIdError {
         self.to_string()
     }
 }
+
+///
+/// Enum for all of the possible errors that could occur when modifying a `KeyedTree`.
+///
+#[derive(Debug, Eq, PartialEq)]
+pub enum KeyedTreeError {
+    /// Occurs when the underlying `Tree` rejects a `NodeId` or an operation.  Contains the
+    /// `NodeIdError` it returned.
+    InvalidNodeId(NodeIdError),
+    /// Occurs when an operation would leave two sibling `Node`s with the same key.
+    DuplicateSiblingKey,
+}
+
+impl KeyedTreeError {
+    fn to_string(&self) -> &str {
+        match *self {
+            KeyedTreeError::InvalidNodeId(ref error) => error.to_string(),
+            KeyedTreeError::DuplicateSiblingKey => {
+                "Another child of the same parent Node already uses this key."
+            }
+        }
+    }
+}
+
+impl From<NodeIdError> for KeyedTreeError {
+    fn from(error: NodeIdError) -> KeyedTreeError {
+        KeyedTreeError::InvalidNodeId(error)
+    }
+}
+
+impl fmt::Display for KeyedTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "KeyedTreeError: {}", self.to_string())
+    }
+}
+
+impl Error for KeyedTreeError {
+    fn description(&self) -> &str {
+        self.to_string()
+    }
+}
+
+///
+/// Enum for all of the ways the internal structure of a `Tree` can be inconsistent.
+///
+/// These are reported by `Tree::validate` and should never occur unless there is a bug in
+/// `id_tree`.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InvariantViolation {
+    /// A `NodeId` stored in the `Tree` carries the id of a different `Tree`.
+    ForeignNodeId(NodeId),
+    /// A `NodeId` stored in the `Tree` points outside of the `Tree` or to a removed `Node`.
+    DanglingNodeId(NodeId),
+    /// The root `Node` has a parent.
+    RootHasParent(NodeId),
+    /// A `Node` names a parent that does not list it as a child.
+    ParentMismatch {
+        /// The `Node` naming the parent.
+        node: NodeId,
+        /// The parent it names.
+        parent: NodeId,
+    },
+    /// A `Node` lists a child that does not name it as its parent.
+    ChildMismatch {
+        /// The `Node` listing the child.
+        node: NodeId,
+        /// The child it lists.
+        child: NodeId,
+    },
+    /// A `Node` lists the same child more than once.
+    DuplicateChild {
+        /// The `Node` listing the child.
+        node: NodeId,
+        /// The child listed more than once.
+        child: NodeId,
+    },
+    /// A `Node` is its own ancestor.  Contains one of the `Node`s on the cycle.
+    Cycle(NodeId),
+    /// A `NodeId` that is up for reuse still points to a `Node`.
+    OccupiedFreeSlot(NodeId),
+    /// A `NodeId` is up for reuse more than once.
+    DuplicateFreeId(NodeId),
+}
+
+impl InvariantViolation {
+    fn to_string(&self) -> &str {
+        match *self {
+            InvariantViolation::ForeignNodeId(_) => "A stored NodeId belongs to a different Tree.",
+            InvariantViolation::DanglingNodeId(_) => "A stored NodeId does not point to a Node.",
+            InvariantViolation::RootHasParent(_) => "The root Node has a parent.",
+            InvariantViolation::ParentMismatch { .. } => {
+                "A Node's parent does not list it as a child."
+            }
+            InvariantViolation::ChildMismatch { .. } => {
+                "A Node's child does not name it as its parent."
+            }
+            InvariantViolation::DuplicateChild { .. } => "A Node lists the same child twice.",
+            InvariantViolation::Cycle(_) => "A Node is its own ancestor.",
+            InvariantViolation::OccupiedFreeSlot(_) => "A free NodeId still points to a Node.",
+            InvariantViolation::DuplicateFreeId(_) => "A NodeId is free more than once.",
+        }
+    }
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "InvariantViolation: {}", self.to_string())?;
+        match *self {
+            InvariantViolation::ForeignNodeId(ref id)
+            | InvariantViolation::DanglingNodeId(ref id)
+            | InvariantViolation::RootHasParent(ref id)
+            | InvariantViolation::Cycle(ref id)
+            | InvariantViolation::OccupiedFreeSlot(ref id)
+            | InvariantViolation::DuplicateFreeId(ref id) => write!(f, " ({:?})", id),
+            InvariantViolation::ParentMismatch {
+                ref node,
+                ref parent,
+            } => write!(f, " (node: {:?}, parent: {:?})", node, parent),
+            InvariantViolation::ChildMismatch {
+                ref node,
+                ref child,
+            }
+            | InvariantViolation::DuplicateChild {
+                ref node,
+                ref child,
+            } => write!(f, " (node: {:?}, child: {:?})", node, child),
+        }
+    }
+}
+
+impl Error for InvariantViolation {
+    fn description(&self) -> &str {
+        self.to_string()
+    }
+}
+
+///
+/// Enum for all of the possible errors that could occur when writing a `Tree` out in some format.
+///
+#[derive(Debug, Eq, PartialEq)]
+pub enum WriteError {
+    /// Occurs when the `NodeId` to start writing from is invalid.  Contains the `NodeIdError`.
+    InvalidNodeId(NodeIdError),
+    /// Occurs when the underlying writer fails.
+    Fmt(fmt::Error),
+}
+
+impl WriteError {
+    fn to_string(&self) -> &str {
+        match *self {
+            WriteError::InvalidNodeId(ref error) => error.to_string(),
+            WriteError::Fmt(_) => "The underlying writer failed.",
+        }
+    }
+}
+
+impl From<NodeIdError> for WriteError {
+    fn from(error: NodeIdError) -> WriteError {
+        WriteError::InvalidNodeId(error)
+    }
+}
+
+impl From<fmt::Error> for WriteError {
+    fn from(error: fmt::Error) -> WriteError {
+        WriteError::Fmt(error)
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WriteError: {}", self.to_string())
+    }
+}
+
+impl Error for WriteError {
+    fn description(&self) -> &str {
+        self.to_string()
+    }
+}
+
+///
+/// Error for parsing a `Tree` from text, along with the position in the input where it occurred.
+///
+/// `E` is the error type of the closure that turns each label into the data of a `Node`.
+///
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseError<E> {
+    line: usize,
+    column: usize,
+    kind: ParseErrorKind<E>,
+}
+
+impl<E> ParseError<E> {
+    pub(crate) fn new(line: usize, column: usize, kind: ParseErrorKind<E>) -> ParseError<E> {
+        ParseError { line, column, kind }
+    }
+
+    ///
+    /// Returns the line of the input the error occurred on, starting at 1.
+    ///
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    ///
+    /// Returns the column (in characters) of the line the error occurred at, starting at 1.
+    ///
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    ///
+    /// Returns what went wrong.
+    ///
+    pub fn kind(&self) -> &ParseErrorKind<E> {
+        &self.kind
+    }
+
+    ///
+    /// Returns what went wrong, consuming the error.
+    ///
+    pub fn into_kind(self) -> ParseErrorKind<E> {
+        self.kind
+    }
+}
+
+///
+/// Enum for all of the ways parsing a `Tree` from text can fail.
+///
+#[derive(Debug, Eq, PartialEq)]
+pub enum ParseErrorKind<E> {
+    /// Occurs when the input contains more than one top-level `Node`.
+    MultipleRoots,
+    /// Occurs when a line is indented more than one level deeper than the line before it, or back
+    /// to a level that no line above it uses.
+    InvalidIndentation,
+    /// Occurs when the guide characters in front of a `Node` are not the ones expected there.
+    InvalidGuide,
+    /// Occurs when a `Node` follows a sibling that was drawn as the last child.
+    AfterLastChild,
+    /// Occurs when a character appears where the format does not allow it.
+    UnexpectedCharacter,
+    /// Occurs when the input ends in the middle of a `Tree`, a quoted label or a comment.
+    UnexpectedEnd,
+    /// Occurs when a branch length is not a number.
+    InvalidLength,
+    /// Occurs when the label closure fails.  Contains its error.
+    Label(E),
+}
+
+impl<E> ParseErrorKind<E> {
+    fn to_string(&self) -> &str {
+        match *self {
+            ParseErrorKind::MultipleRoots => "The input contains more than one root Node.",
+            ParseErrorKind::InvalidIndentation => "The line is indented to an unexpected level.",
+            ParseErrorKind::InvalidGuide => "The guide characters do not match the lines above.",
+            ParseErrorKind::AfterLastChild => "The Node follows a sibling drawn as the last child.",
+            ParseErrorKind::UnexpectedCharacter => "The character is not allowed here.",
+            ParseErrorKind::UnexpectedEnd => "The input ended unexpectedly.",
+            ParseErrorKind::InvalidLength => "The branch length is not a number.",
+            ParseErrorKind::Label(_) => "The label could not be parsed.",
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ParseError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ParseError: {}", self.kind.to_string())?;
+        if let ParseErrorKind::Label(ref error) = self.kind {
+            write!(f, " ({})", error)?;
+        }
+        write!(f, " (line: {}, column: {})", self.line, self.column)
+    }
+}
+
+impl<E: Error + 'static> Error for ParseError<E> {
+    fn description(&self) -> &str {
+        self.kind.to_string()
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            ParseErrorKind::Label(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+///
+/// Enum for all of the ways building a `Tree` out of keyed rows can fail.  Each variant contains
+/// the key of the offending row.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BuildError<K> {
+    /// Occurs when two rows have the same key.  Contains the key.
+    DuplicateKey(K),
+    /// Occurs when a row names a parent key that no row has.
+    MissingParent {
+        /// The key of the row.
+        key: K,
+        /// The parent key that no row has.
+        parent: K,
+    },
+    /// Occurs when more than one row has no parent.  Contains the keys of the first two.
+    MultipleRoots(K, K),
+    /// Occurs when following the parents of some rows leads in a circle instead of to the root.
+    /// Contains the key of a row on that circle.
+    Cycle(K),
+}
+
+impl<K> BuildError<K> {
+    fn to_string(&self) -> &str {
+        match *self {
+            BuildError::DuplicateKey(_) => "More than one row has the same key.",
+            BuildError::MissingParent { .. } => "A row names a parent that does not exist.",
+            BuildError::MultipleRoots(_, _) => "More than one row has no parent.",
+            BuildError::Cycle(_) => "The parents of some rows form a cycle.",
+        }
+    }
+}
+
+impl<K: fmt::Debug> fmt::Display for BuildError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BuildError: {}", self.to_string())?;
+        match *self {
+            BuildError::DuplicateKey(ref key) | BuildError::Cycle(ref key) => {
+                write!(f, " (key: {:?})", key)
+            }
+            BuildError::MissingParent {
+                ref key,
+                ref parent,
+            } => write!(f, " (key: {:?}, parent: {:?})", key, parent),
+            BuildError::MultipleRoots(ref first, ref second) => {
+                write!(f, " (keys: {:?}, {:?})", first, second)
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug> Error for BuildError<K> {
+    fn description(&self) -> &str {
+        self.to_string()
+    }
+}
+
+///
+/// Enum for the problems with a single entry while building a `Tree` out of a directory.  These
+/// are recorded on the entry instead of stopping the walk.
+///
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+pub enum EntryError {
+    /// Occurs when a followed symlink leads back to a directory above it, which would otherwise
+    /// be walked forever.
+    SymlinkLoop,
+    /// Occurs when the entry or its contents cannot be read, such as when permission is denied or
+    /// a followed symlink dangles.  Contains the `io::Error`.
+    Io(io::Error),
+}
+
+#[cfg(feature = "fs")]
+impl EntryError {
+    fn to_string(&self) -> &str {
+        match *self {
+            EntryError::SymlinkLoop => "The symlink leads back to a directory above it.",
+            EntryError::Io(_) => "The entry could not be read.",
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EntryError: {}", self.to_string())?;
+        if let EntryError::Io(ref error) = *self {
+            write!(f, " ({})", error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Error for EntryError {
+    fn description(&self) -> &str {
+        self.to_string()
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            EntryError::Io(ref error) => Some(error),
+            EntryError::SymlinkLoop => None,
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl From<io::Error> for EntryError {
+    fn from(error: io::Error) -> EntryError {
+        EntryError::Io(error)
+    }
+}
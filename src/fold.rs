@@ -0,0 +1,213 @@
+use NodeId;
+use NodeIdError;
+use NodeMap;
+use Tree;
+
+impl<T> Tree<T> {
+    ///
+    /// Computes a value for the `Node` specified by the given `NodeId` from the bottom up: the
+    /// closure gets the data of each `Node` in the subtree along with the values already computed
+    /// for its children (in order), and the value of the given `Node` is returned.
+    ///
+    /// This works without recursion, so it is safe to use on arbitrarily deep `Tree`s.
+    ///
+    /// Returns a `NodeIdError` if the given `NodeId` is invalid.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(3), UnderNode(&child_id)).unwrap();
+    /// tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+    ///
+    /// let size = tree.fold_subtree(&root_id, |_, sizes: &[usize]| 1 + sizes.iter().sum::<usize>());
+    /// assert_eq!(size, Ok(4));
+    ///
+    /// let sum = tree.fold_subtree(&child_id, |data, sums: &[i32]| data + sums.iter().sum::<i32>());
+    /// assert_eq!(sum, Ok(5));
+    /// ```
+    ///
+    pub fn fold_subtree<A, F>(&self, node_id: &NodeId, mut f: F) -> Result<A, NodeIdError>
+    where
+        F: FnMut(&T, &[A]) -> A,
+    {
+        self.get(node_id)?;
+
+        // the values of finished Nodes whose parents are not finished yet, in post-order
+        let mut values: Vec<A> = Vec::new();
+        // each Node along with whether its children have been pushed already
+        let mut stack = vec![(node_id, false)];
+        while let Some((current_id, expanded)) = stack.pop() {
+            let node = self.get_unsafe(current_id);
+            if expanded {
+                let first_child = values.len() - node.children().len();
+                let value = f(node.data(), &values[first_child..]);
+                values.truncate(first_child);
+                values.push(value);
+            } else {
+                stack.push((current_id, true));
+                stack.extend(
+                    node.children()
+                        .iter()
+                        .rev()
+                        .map(|child_id| (child_id, false)),
+                );
+            }
+        }
+
+        Ok(values
+            .pop()
+            .expect("Tree::fold_subtree: the given Node always gets a value."))
+    }
+
+    ///
+    /// Computes a value for every `Node` in the subtree of the `Node` specified by the given
+    /// `NodeId` from the top down: the closure gets the value of the parent of each `Node` along
+    /// with the data of the `Node` itself.  The given `Node` gets its value from `init` instead of
+    /// from a parent.
+    ///
+    /// This works without recursion, so it is safe to use on arbitrarily deep `Tree`s.
+    ///
+    /// Returns a `Result` containing a `NodeMap` with the value of every `Node` in the subtree, or
+    /// a `NodeIdError` if the given `NodeId` is invalid.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<&str> = Tree::new();
+    /// let root_id = tree.insert(Node::new("usr"), AsRoot).unwrap();
+    /// let local_id = tree.insert(Node::new("local"), UnderNode(&root_id)).unwrap();
+    /// let bin_id = tree.insert(Node::new("bin"), UnderNode(&local_id)).unwrap();
+    ///
+    /// let paths = tree
+    ///     .scan_down(&root_id, String::new(), |parent_path, name| format!("{}/{}", parent_path, name))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(paths[(&tree, &root_id)], "/usr");
+    /// assert_eq!(paths[(&tree, &bin_id)], "/usr/local/bin");
+    /// ```
+    ///
+    pub fn scan_down<A, F>(
+        &self,
+        node_id: &NodeId,
+        init: A,
+        mut f: F,
+    ) -> Result<NodeMap<A>, NodeIdError>
+    where
+        F: FnMut(&A, &T) -> A,
+    {
+        let node = self.get(node_id)?;
+
+        let mut values = NodeMap::new(self);
+        values.insert(self, node_id, f(&init, node.data()))?;
+
+        let mut stack: Vec<&NodeId> = node.children().iter().rev().collect();
+        while let Some(current_id) = stack.pop() {
+            let node = self.get_unsafe(current_id);
+            let parent_id = node
+                .parent()
+                .expect("Tree::scan_down: every Node below the given one has a parent.");
+            let value = f(&values[(self, parent_id)], node.data());
+            values.insert(self, current_id, value)?;
+            stack.extend(node.children().iter().rev());
+        }
+
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod fold_tests {
+    use super::super::*;
+
+    use test_fixtures::*;
+    use InsertBehavior::*;
+
+    #[test]
+    fn test_fold_subtree() {
+        let (tree, ids) = small_tree();
+
+        // the subtree written out in post-order, with the children of each Node in brackets
+        let describe = |node_id: &NodeId| {
+            tree.fold_subtree(node_id, |data, children: &[String]| {
+                if children.is_empty() {
+                    data.to_string()
+                } else {
+                    format!("[{}]{}", children.join(" "), data)
+                }
+            })
+        };
+        assert_eq!(describe(&ids[0]), Ok("[[3 4]1 2]0".to_string()));
+        assert_eq!(describe(&ids[1]), Ok("[3 4]1".to_string()));
+        assert_eq!(describe(&ids[4]), Ok("4".to_string()));
+
+        let mut calls = Vec::new();
+        tree.fold_subtree(&ids[0], |&data, _: &[()]| calls.push(data))
+            .unwrap();
+        assert_eq!(calls, vec![3, 4, 1, 2, 0]);
+
+        let other_id = Tree::new().insert(Node::new(0), AsRoot).unwrap();
+        assert_eq!(
+            tree.fold_subtree(&other_id, |_, _: &[()]| ()),
+            Err(NodeIdError::InvalidNodeIdForTree)
+        );
+    }
+
+    #[test]
+    fn test_scan_down() {
+        let (tree, ids) = small_tree();
+
+        let depths = tree.scan_down(&ids[0], None, |parent: &Option<usize>, _| {
+            parent.map_or(Some(0), |depth| Some(depth + 1))
+        });
+        let depths = depths.unwrap();
+        assert_eq!(depths.len(), 5);
+        let expected = [0, 1, 1, 2, 2];
+        for (node_id, &depth) in ids.iter().zip(expected.iter()) {
+            assert_eq!(depths[(&tree, node_id)], Some(depth));
+        }
+
+        let sums = tree
+            .scan_down(&ids[1], 100, |sum, data| sum + data)
+            .unwrap();
+        assert_eq!(sums.len(), 3);
+        assert_eq!(sums[(&tree, &ids[1])], 101);
+        assert_eq!(sums[(&tree, &ids[3])], 104);
+        assert_eq!(sums[(&tree, &ids[4])], 105);
+
+        let other_id = Tree::new().insert(Node::new(0), AsRoot).unwrap();
+        assert_eq!(
+            tree.scan_down(&other_id, (), |_, _| ()).map(|_| ()),
+            Err(NodeIdError::InvalidNodeIdForTree)
+        );
+    }
+
+    #[test]
+    fn test_deep_trees() {
+        let depth: usize = if cfg!(feature = "validate_mutations") {
+            2_000
+        } else {
+            100_000
+        };
+        let tree: Tree<usize> = (0..depth)
+            .map(|index| (index.checked_sub(1), index))
+            .collect();
+        let root_id = tree.root_node_id().unwrap();
+
+        let height = tree.fold_subtree(root_id, |_, heights: &[usize]| {
+            heights.iter().max().map_or(1, |height| height + 1)
+        });
+        assert_eq!(height, Ok(depth));
+
+        let depths = tree.scan_down(root_id, 0, |depth, _| depth + 1).unwrap();
+        assert_eq!(depths.len(), depth);
+        assert!(tree
+            .traverse_pre_order_ids(root_id)
+            .unwrap()
+            .all(|node_id| depths[(&tree, &node_id)] == *tree.get(&node_id).unwrap().data() + 1));
+    }
+}
@@ -0,0 +1,662 @@
+use std::fmt;
+
+use InsertBehavior::*;
+use Node;
+use NodeId;
+use NodeIdError;
+use ParseError;
+use ParseErrorKind;
+use Tree;
+
+type LabelFn<'a, T> = Box<dyn Fn(&T) -> String + 'a>;
+
+///
+/// The characters `TreeFormatter` uses to draw the lines between `Node`s.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GuideStyle {
+    ///
+    /// Box-drawing characters, as used by `Tree::write_formatted`.
+    ///
+    /// ```text
+    /// 0
+    /// ├── 1
+    /// │   └── 2
+    /// └── 3
+    /// ```
+    ///
+    Unicode,
+    ///
+    /// Plain ASCII characters.
+    ///
+    /// ```text
+    /// 0
+    /// |-- 1
+    /// |   `-- 2
+    /// `-- 3
+    /// ```
+    ///
+    Ascii,
+}
+
+impl GuideStyle {
+    fn guides(self) -> Guides {
+        match self {
+            GuideStyle::Unicode => Guides {
+                branch: "├── ",
+                last_branch: "└── ",
+                line: "│   ",
+                blank: "    ",
+                ellipsis: "…",
+            },
+            GuideStyle::Ascii => Guides {
+                branch: "|-- ",
+                last_branch: "`-- ",
+                line: "|   ",
+                blank: "    ",
+                ellipsis: "...",
+            },
+        }
+    }
+}
+
+struct Guides {
+    branch: &'static str,
+    last_branch: &'static str,
+    line: &'static str,
+    blank: &'static str,
+    ellipsis: &'static str,
+}
+
+///
+/// A configurable, `Display`-able view of a `Tree` that draws one `Node` per line.
+///
+/// By default the whole `Tree` is drawn from its root with `GuideStyle::Unicode`.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut tree: Tree<i32> = Tree::new();
+/// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+/// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+/// tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+/// tree.insert(Node::new(3), UnderNode(&child_id)).unwrap();
+/// tree.insert(Node::new(4), UnderNode(&child_id)).unwrap();
+/// tree.insert(Node::new(5), UnderNode(&root_id)).unwrap();
+///
+/// let formatter = TreeFormatter::new(&tree, |data| format!("node {}", data))
+///     .with_guides(GuideStyle::Ascii)
+///     .with_max_children(2)
+///     .with_sibling_indices(true);
+///
+/// assert_eq!(formatter.to_string(), "\
+/// node 0
+/// |-- [0] node 1
+/// |   |-- [0] node 2
+/// |   |-- [1] node 3
+/// |   `-- ... 1 more
+/// `-- [1] node 5
+/// ");
+/// ```
+///
+pub struct TreeFormatter<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    label: LabelFn<'a, T>,
+    start: Option<NodeId>,
+    max_depth: Option<usize>,
+    max_children: Option<usize>,
+    guides: GuideStyle,
+    sibling_indices: bool,
+}
+
+impl<'a, T> TreeFormatter<'a, T> {
+    ///
+    /// Creates a new `TreeFormatter` for the given `Tree` that labels each `Node` with the result
+    /// of the given closure.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: Tree<i32> = Tree::new();
+    /// let _formatter = TreeFormatter::new(&tree, |data| data.to_string());
+    /// ```
+    ///
+    pub fn new<F>(tree: &'a Tree<T>, label: F) -> TreeFormatter<'a, T>
+    where
+        F: Fn(&T) -> String + 'a,
+    {
+        TreeFormatter {
+            tree,
+            label: Box::new(label),
+            start: None,
+            max_depth: None,
+            max_children: None,
+            guides: GuideStyle::Unicode,
+            sibling_indices: false,
+        }
+    }
+
+    ///
+    /// Starts drawing at the given `Node` instead of at the root.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is invalid for the `Tree`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    ///
+    /// let formatter = TreeFormatter::new(&tree, |data| data.to_string())
+    ///     .with_start(&child_id)
+    ///     .unwrap();
+    /// assert_eq!(formatter.to_string(), "1\n└── 2\n");
+    /// ```
+    ///
+    pub fn with_start(mut self, node_id: &NodeId) -> Result<TreeFormatter<'a, T>, NodeIdError> {
+        self.tree.get(node_id)?;
+        self.start = Some(node_id.clone());
+        Ok(self)
+    }
+
+    ///
+    /// Limits how many levels below the starting `Node` are drawn.  A depth of `0` only draws the
+    /// starting `Node` itself.
+    ///
+    pub fn with_max_depth(mut self, max_depth: usize) -> TreeFormatter<'a, T> {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    ///
+    /// Limits how many children are drawn for each `Node`.  The rest are summarized with a single
+    /// "… N more" line.
+    ///
+    pub fn with_max_children(mut self, max_children: usize) -> TreeFormatter<'a, T> {
+        self.max_children = Some(max_children);
+        self
+    }
+
+    ///
+    /// Sets the characters used to draw the lines between `Node`s.
+    ///
+    pub fn with_guides(mut self, guides: GuideStyle) -> TreeFormatter<'a, T> {
+        self.guides = guides;
+        self
+    }
+
+    ///
+    /// Sets whether every `Node` below the starting one is prefixed with its position among its
+    /// siblings.
+    ///
+    pub fn with_sibling_indices(mut self, sibling_indices: bool) -> TreeFormatter<'a, T> {
+        self.sibling_indices = sibling_indices;
+        self
+    }
+
+    ///
+    /// Writes the formatted `Tree` to the given writer.  Writes nothing if there is no starting
+    /// `Node` because the `Tree` is empty.
+    ///
+    pub fn write<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        let start_id = match self.start.as_ref().or_else(|| self.tree.root_node_id()) {
+            Some(start_id) => start_id,
+            None => return Ok(()),
+        };
+        let guides = self.guides.guides();
+
+        let mut stack = vec![Line::Node {
+            node_id: start_id,
+            index: 0,
+            depth: 0,
+            is_last: true,
+            prefix: String::new(),
+        }];
+
+        while let Some(next) = stack.pop() {
+            match next {
+                Line::More { count, prefix } => {
+                    writeln!(
+                        w,
+                        "{}{}{} {} more",
+                        prefix, guides.last_branch, guides.ellipsis, count
+                    )?;
+                }
+                Line::Node {
+                    node_id,
+                    index,
+                    depth,
+                    is_last,
+                    prefix,
+                } => {
+                    let node = self.tree.get_unsafe(node_id);
+
+                    w.write_str(&prefix)?;
+                    if depth > 0 {
+                        w.write_str(if is_last {
+                            guides.last_branch
+                        } else {
+                            guides.branch
+                        })?;
+                        if self.sibling_indices {
+                            write!(w, "[{}] ", index)?;
+                        }
+                    }
+                    writeln!(w, "{}", (self.label)(node.data()))?;
+
+                    if matches!(self.max_depth, Some(max_depth) if depth >= max_depth) {
+                        continue;
+                    }
+
+                    let child_prefix = if depth == 0 {
+                        prefix
+                    } else {
+                        prefix + if is_last { guides.blank } else { guides.line }
+                    };
+
+                    let children = node.children();
+                    let shown = self
+                        .max_children
+                        .map_or(children.len(), |max| max.min(children.len()));
+                    let hidden = children.len() - shown;
+
+                    // pushed in reverse so that they come off the stack in order
+                    if hidden > 0 {
+                        stack.push(Line::More {
+                            count: hidden,
+                            prefix: child_prefix.clone(),
+                        });
+                    }
+                    for (index, child_id) in children[..shown].iter().enumerate().rev() {
+                        stack.push(Line::Node {
+                            node_id: child_id,
+                            index,
+                            depth: depth + 1,
+                            is_last: hidden == 0 && index + 1 == shown,
+                            prefix: child_prefix.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, T> fmt::Display for TreeFormatter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f)
+    }
+}
+
+enum Line<'a> {
+    Node {
+        node_id: &'a NodeId,
+        index: usize,
+        depth: usize,
+        is_last: bool,
+        prefix: String,
+    },
+    More {
+        count: usize,
+        prefix: String,
+    },
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Parses the layout written by `Tree::write_formatted` (or by a `TreeFormatter` with either
+    /// `GuideStyle`) back into a `Tree`, turning the label of each `Node` into its data with the
+    /// given closure.  Blank lines are skipped.
+    ///
+    /// Output that was cut short with `TreeFormatter::with_max_children` or
+    /// `TreeFormatter::with_max_depth` does not describe the whole `Tree`, so it is not parsed
+    /// back faithfully.
+    ///
+    /// Returns a `ParseError` with the line and column of the first problem in the input.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: Tree<i32> = Tree::parse_formatted("\
+    /// 0
+    /// ├── 1
+    /// │   └── 2
+    /// └── 3
+    /// ", |label| label.parse()).unwrap();
+    ///
+    /// let mut s = String::new();
+    /// tree.write_formatted(&mut s).unwrap();
+    /// assert_eq!(&s, "0\n├── 1\n│   └── 2\n└── 3\n");
+    ///
+    /// let error = Tree::<i32>::parse_formatted("0\n├── 1\n└── two\n", |label| label.parse())
+    ///     .unwrap_err();
+    /// assert_eq!((error.line(), error.column()), (3, 5));
+    /// ```
+    ///
+    pub fn parse_formatted<'a, E, F>(input: &'a str, mut parse: F) -> Result<Tree<T>, ParseError<E>>
+    where
+        F: FnMut(&'a str) -> Result<T, E>,
+    {
+        let mut tree = Tree::new();
+        // the latest Node at each depth, and whether it was drawn as the last child
+        let mut open: Vec<(NodeId, bool)> = Vec::new();
+
+        for (line_index, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let error = |column, kind| ParseError::new(line_index + 1, column, kind);
+
+            let mut rest = line;
+            let mut guides = Vec::new();
+            let mut is_last = None;
+            while is_last.is_none() {
+                let end = rest.char_indices().nth(4).map_or(rest.len(), |(i, _)| i);
+                match &rest[..end] {
+                    "│   " | "|   " => guides.push(true),
+                    "    " => guides.push(false),
+                    "├── " | "|-- " => is_last = Some(false),
+                    "└── " | "`-- " => is_last = Some(true),
+                    _ => break,
+                }
+                rest = &rest[end..];
+            }
+
+            let depth = match is_last {
+                Some(_) => guides.len() + 1,
+                None if guides.is_empty() => 0,
+                None => return Err(error(guides.len() * 4 + 1, ParseErrorKind::InvalidGuide)),
+            };
+            let branch_column = guides.len() * 4 + 1;
+
+            if depth == 0 && !open.is_empty() {
+                return Err(error(1, ParseErrorKind::MultipleRoots));
+            }
+            if depth > open.len() {
+                return Err(error(branch_column, ParseErrorKind::InvalidIndentation));
+            }
+            for (level, &continues) in guides.iter().enumerate() {
+                if continues == open[level + 1].1 {
+                    return Err(error(level * 4 + 1, ParseErrorKind::InvalidGuide));
+                }
+            }
+            if depth > 0 && open.len() > depth && open[depth].1 {
+                return Err(error(branch_column, ParseErrorKind::AfterLastChild));
+            }
+
+            let label_column = if depth == 0 { 1 } else { branch_column + 4 };
+            let data = parse(rest).map_err(|e| error(label_column, ParseErrorKind::Label(e)))?;
+
+            open.truncate(depth);
+            let node_id = tree.insert_parsed(data, open.last().map(|(parent_id, _)| parent_id));
+            open.push((node_id, is_last.unwrap_or(true)));
+        }
+
+        Ok(tree)
+    }
+
+    ///
+    /// Parses a plain outline, where each line holds the label of one `Node` and is indented
+    /// deeper than the line of its parent, back into a `Tree`.  Blank lines are skipped.
+    ///
+    /// Spaces and tabs both count as one column of indentation, so mixing them is not
+    /// recommended.  Returns a `ParseError` with the line and column of the first problem in the
+    /// input.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: Tree<String> = Tree::parse_outline("\
+    /// src
+    ///   lib.rs
+    ///   tree
+    ///     mod.rs
+    /// ", |label| Ok::<_, ()>(label.to_string())).unwrap();
+    ///
+    /// let mut s = String::new();
+    /// tree.write_formatted(&mut s).unwrap();
+    /// assert_eq!(&s, "\"src\"\n├── \"lib.rs\"\n└── \"tree\"\n    └── \"mod.rs\"\n");
+    ///
+    /// let error = Tree::parse_outline("src\n    lib.rs\n  tree\n", |label| Ok::<_, ()>(label))
+    ///     .unwrap_err();
+    /// assert_eq!(error.kind(), &ParseErrorKind::InvalidIndentation);
+    /// assert_eq!((error.line(), error.column()), (3, 3));
+    /// ```
+    ///
+    pub fn parse_outline<'a, E, F>(input: &'a str, mut parse: F) -> Result<Tree<T>, ParseError<E>>
+    where
+        F: FnMut(&'a str) -> Result<T, E>,
+    {
+        let mut tree = Tree::new();
+        // the indentation and NodeId of the latest Node at each depth
+        let mut open: Vec<(usize, NodeId)> = Vec::new();
+
+        for (line_index, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let error = |column, kind| ParseError::new(line_index + 1, column, kind);
+
+            let label = line.trim_start_matches([' ', '\t']);
+            let indent = line[..line.len() - label.len()].chars().count();
+
+            let depth = open
+                .iter()
+                .position(|&(open_indent, _)| open_indent >= indent)
+                .unwrap_or(open.len());
+            if depth < open.len() && open[depth].0 != indent {
+                return Err(error(indent + 1, ParseErrorKind::InvalidIndentation));
+            }
+            if depth == 0 && !open.is_empty() {
+                return Err(error(indent + 1, ParseErrorKind::MultipleRoots));
+            }
+
+            let data = parse(label).map_err(|e| error(indent + 1, ParseErrorKind::Label(e)))?;
+
+            open.truncate(depth);
+            let node_id = tree.insert_parsed(data, open.last().map(|(_, parent_id)| parent_id));
+            open.push((indent, node_id));
+        }
+
+        Ok(tree)
+    }
+
+    fn insert_parsed(&mut self, data: T, parent_id: Option<&NodeId>) -> NodeId {
+        let behavior = match parent_id {
+            Some(parent_id) => UnderNode(parent_id),
+            None => AsRoot,
+        };
+        self.insert(Node::new(data), behavior)
+            .expect("parsed parents are always in the Tree")
+    }
+}
+
+#[cfg(test)]
+mod tree_formatter_tests {
+    use super::super::*;
+    use test_fixtures::*;
+
+    fn label(data: &i32) -> String {
+        data.to_string()
+    }
+
+    #[test]
+    fn test_defaults_match_write_formatted() {
+        let (tree, _) = wide_tree();
+
+        let mut expected = String::new();
+        tree.write_formatted(&mut expected).unwrap();
+
+        assert_eq!(TreeFormatter::new(&tree, label).to_string(), expected);
+        assert_eq!(
+            expected,
+            "\
+0
+├── 1
+│   ├── 2
+│   └── 3
+├── 4
+└── 5
+    └── 6
+        └── 7
+"
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let (tree, ids) = wide_tree();
+
+        let formatter = TreeFormatter::new(&tree, label)
+            .with_guides(GuideStyle::Ascii)
+            .with_max_depth(1);
+        assert_eq!(formatter.to_string(), "0\n|-- 1\n|-- 4\n`-- 5\n");
+
+        let formatter = TreeFormatter::new(&tree, label)
+            .with_start(&ids[5])
+            .unwrap()
+            .with_max_depth(0);
+        assert_eq!(formatter.to_string(), "5\n");
+    }
+
+    #[test]
+    fn test_max_children() {
+        let (tree, _) = wide_tree();
+
+        let formatter = TreeFormatter::new(&tree, label).with_max_children(1);
+        assert_eq!(
+            formatter.to_string(),
+            "\
+0
+├── 1
+│   ├── 2
+│   └── … 1 more
+└── … 2 more
+"
+        );
+
+        let formatter = TreeFormatter::new(&tree, label).with_max_children(0);
+        assert_eq!(formatter.to_string(), "0\n└── … 3 more\n");
+    }
+
+    #[test]
+    fn test_start_and_indices() {
+        let (tree, ids) = wide_tree();
+
+        let formatter = TreeFormatter::new(&tree, |data| format!("<{}>", data))
+            .with_start(&ids[1])
+            .unwrap()
+            .with_sibling_indices(true);
+        assert_eq!(formatter.to_string(), "<1>\n├── [0] <2>\n└── [1] <3>\n");
+
+        let (other, other_ids) = wide_tree();
+        assert!(TreeFormatter::new(&other, label)
+            .with_start(&other_ids[1])
+            .is_ok());
+        assert_eq!(
+            TreeFormatter::new(&tree, label)
+                .with_start(&other_ids[1])
+                .err(),
+            Some(NodeIdError::InvalidNodeIdForTree)
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        let tree: Tree<i32> = Tree::new();
+        assert_eq!(TreeFormatter::new(&tree, label).to_string(), "");
+    }
+
+    fn parse_i32(label: &str) -> Result<i32, ::std::num::ParseIntError> {
+        label.parse()
+    }
+
+    fn assert_same_shape(a: &Tree<i32>, b: &Tree<i32>) {
+        let mut a_formatted = String::new();
+        let mut b_formatted = String::new();
+        a.write_formatted(&mut a_formatted).unwrap();
+        b.write_formatted(&mut b_formatted).unwrap();
+        assert_eq!(a_formatted, b_formatted);
+    }
+
+    #[test]
+    fn test_parse_formatted_round_trip() {
+        let (tree, _) = wide_tree();
+
+        let mut formatted = String::new();
+        tree.write_formatted(&mut formatted).unwrap();
+        let parsed = Tree::parse_formatted(&formatted, parse_i32).unwrap();
+        assert_same_shape(&tree, &parsed);
+        assert!(parsed.validate().is_ok());
+
+        let ascii = TreeFormatter::new(&tree, label)
+            .with_guides(GuideStyle::Ascii)
+            .to_string();
+        assert_same_shape(&tree, &Tree::parse_formatted(&ascii, parse_i32).unwrap());
+
+        let empty: Tree<i32> = Tree::parse_formatted("\n\n", parse_i32).unwrap();
+        assert!(empty.root_node_id().is_none());
+    }
+
+    #[test]
+    fn test_parse_formatted_errors() {
+        let check = |input: &str, line, column, kind: ParseErrorKind<()>| {
+            let error = Tree::parse_formatted(input, |label| label.parse::<i32>().map_err(|_| ()))
+                .unwrap_err();
+            assert_eq!(
+                (error.line(), error.column(), error.kind()),
+                (line, column, &kind)
+            );
+        };
+
+        check("0\n1\n", 2, 1, ParseErrorKind::MultipleRoots);
+        check("0\n│   └── 1\n", 2, 5, ParseErrorKind::InvalidIndentation);
+        check("0\n    1\n", 2, 5, ParseErrorKind::InvalidGuide);
+        check("0\n└── 1\n└── 2\n", 3, 1, ParseErrorKind::AfterLastChild);
+        check(
+            "0\n├── 1\n    └── 2\n└── 3\n",
+            3,
+            1,
+            ParseErrorKind::InvalidGuide,
+        );
+        check("0\n└── 1\n│   └── 2\n", 3, 1, ParseErrorKind::InvalidGuide);
+        check("0\n├── 1\n│   └── x\n", 3, 9, ParseErrorKind::Label(()));
+        check("\n0\n\n├── z\n", 4, 5, ParseErrorKind::Label(()));
+    }
+
+    #[test]
+    fn test_parse_outline() {
+        let (tree, _) = wide_tree();
+
+        let outline = "0\n  1\n    2\n    3\n  4\n  5\n    6\n      7\n";
+        assert_same_shape(&tree, &Tree::parse_outline(outline, parse_i32).unwrap());
+
+        let tabs = "0\n\t1\n\t\t2\n\n\t\t3\n\t4\n\t5\n\t\t6\n\t\t\t7";
+        assert_same_shape(&tree, &Tree::parse_outline(tabs, parse_i32).unwrap());
+
+        let error = Tree::parse_outline("0\n  1\n3\n", parse_i32).unwrap_err();
+        assert_eq!(
+            (error.line(), error.column(), error.kind()),
+            (3, 1, &ParseErrorKind::MultipleRoots)
+        );
+
+        let error = Tree::parse_outline("  0\n1\n", parse_i32).unwrap_err();
+        assert_eq!(
+            (error.line(), error.column(), error.kind()),
+            (2, 1, &ParseErrorKind::InvalidIndentation)
+        );
+
+        let error = Tree::parse_outline("0\n    1\n    x\n", parse_i32).unwrap_err();
+        assert_eq!((error.line(), error.column()), (3, 5));
+        assert!(error
+            .to_string()
+            .starts_with("ParseError: The label could not be parsed. ("));
+        assert!(error.to_string().ends_with("(line: 3, column: 5)"));
+    }
+}
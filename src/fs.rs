@@ -0,0 +1,561 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use EntryError;
+use InsertBehavior::*;
+use Node;
+use NodeId;
+use Tree;
+use TreeBuilder;
+
+type FilterFn<'a> = Box<dyn Fn(&Path) -> bool + 'a>;
+
+///
+/// The kind of an entry in a directory hierarchy.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EntryKind {
+    /// A regular file.
+    File,
+    /// A directory.
+    Directory,
+    /// A symlink that was not followed (or whose target could not be read).
+    Symlink,
+    /// Anything else, such as a socket or a device.
+    Other,
+}
+
+impl EntryKind {
+    fn of(file_type: fs::FileType) -> EntryKind {
+        if file_type.is_dir() {
+            EntryKind::Directory
+        } else if file_type.is_file() {
+            EntryKind::File
+        } else if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else {
+            EntryKind::Other
+        }
+    }
+}
+
+///
+/// The data of each `Node` in a `Tree` built by `Tree::from_directory`.
+///
+#[derive(Debug)]
+pub struct PathEntry {
+    /// The path of the entry, starting with the path the `Tree` was built from.
+    pub path: PathBuf,
+    /// What kind of entry this is.  Followed symlinks take on the kind of their target.
+    pub kind: EntryKind,
+    /// The metadata of the entry (of the target, for followed symlinks), if it could be read.
+    pub metadata: Option<fs::Metadata>,
+    /// What went wrong with this entry, if anything.  Directories with an error have no children.
+    pub error: Option<EntryError>,
+}
+
+impl PathEntry {
+    ///
+    /// Returns the last component of the path of the entry, if there is one.
+    ///
+    pub fn file_name(&self) -> Option<&OsStr> {
+        self.path.file_name()
+    }
+
+    fn read(path: PathBuf, follow_symlinks: bool) -> PathEntry {
+        let metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(error) => return PathEntry::failed(path, EntryKind::Other, None, error),
+        };
+
+        if follow_symlinks && metadata.file_type().is_symlink() {
+            return match fs::metadata(&path) {
+                Ok(target) => PathEntry::new(path, target),
+                Err(error) => PathEntry::failed(path, EntryKind::Symlink, Some(metadata), error),
+            };
+        }
+        PathEntry::new(path, metadata)
+    }
+
+    fn new(path: PathBuf, metadata: fs::Metadata) -> PathEntry {
+        PathEntry {
+            path,
+            kind: EntryKind::of(metadata.file_type()),
+            metadata: Some(metadata),
+            error: None,
+        }
+    }
+
+    fn failed(
+        path: PathBuf,
+        kind: EntryKind,
+        metadata: Option<fs::Metadata>,
+        error: io::Error,
+    ) -> PathEntry {
+        PathEntry {
+            path,
+            kind,
+            metadata,
+            error: Some(EntryError::Io(error)),
+        }
+    }
+
+    fn can_descend(&self) -> bool {
+        self.kind == EntryKind::Directory && self.error.is_none()
+    }
+}
+
+///
+/// Settings for `Tree::from_directory`, built up from `DirectoryOptions::new()`.
+///
+/// By default symlinks are not followed, the whole hierarchy is walked, every entry is kept and
+/// children are in the order the operating system lists them.
+///
+pub struct DirectoryOptions<'a> {
+    follow_symlinks: bool,
+    max_depth: Option<usize>,
+    include: Option<FilterFn<'a>>,
+    exclude: Option<FilterFn<'a>>,
+    sorted: bool,
+}
+
+impl<'a> DirectoryOptions<'a> {
+    ///
+    /// Creates a new `DirectoryOptions` with the default settings.
+    ///
+    /// ```
+    /// use id_tree::DirectoryOptions;
+    ///
+    /// let _options = DirectoryOptions::new();
+    /// ```
+    ///
+    pub fn new() -> DirectoryOptions<'a> {
+        DirectoryOptions {
+            follow_symlinks: false,
+            max_depth: None,
+            include: None,
+            exclude: None,
+            sorted: false,
+        }
+    }
+
+    ///
+    /// Sets whether symlinks are followed.  A followed symlink becomes an entry of the kind of its
+    /// target, and a symlink to a directory is walked like one unless it leads back to a directory
+    /// above it.
+    ///
+    pub fn with_follow_symlinks(mut self, follow_symlinks: bool) -> DirectoryOptions<'a> {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    ///
+    /// Leaves out every entry more than `max_depth` levels below the starting directory, so `0`
+    /// gives just the starting directory.
+    ///
+    pub fn with_max_depth(mut self, max_depth: usize) -> DirectoryOptions<'a> {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    ///
+    /// Sets the closure that decides which files (and other non-directories) are kept, given
+    /// their path.  Directories are always kept and walked, so this can pick files out of a whole
+    /// hierarchy.
+    ///
+    /// ```
+    /// use id_tree::DirectoryOptions;
+    ///
+    /// let _options = DirectoryOptions::new()
+    ///     .with_include(|path| matches!(path.extension(), Some(extension) if extension == "rs"));
+    /// ```
+    ///
+    pub fn with_include<F>(mut self, include: F) -> DirectoryOptions<'a>
+    where
+        F: Fn(&Path) -> bool + 'a,
+    {
+        self.include = Some(Box::new(include));
+        self
+    }
+
+    ///
+    /// Sets the closure that decides which entries are left out, given their path.  Excluded
+    /// directories are not walked at all.
+    ///
+    /// ```
+    /// use id_tree::DirectoryOptions;
+    ///
+    /// let _options = DirectoryOptions::new()
+    ///     .with_exclude(|path| path.ends_with("target"));
+    /// ```
+    ///
+    pub fn with_exclude<F>(mut self, exclude: F) -> DirectoryOptions<'a>
+    where
+        F: Fn(&Path) -> bool + 'a,
+    {
+        self.exclude = Some(Box::new(exclude));
+        self
+    }
+
+    ///
+    /// Sets whether the children of each directory are sorted by name.
+    ///
+    pub fn with_sorted(mut self, sorted: bool) -> DirectoryOptions<'a> {
+        self.sorted = sorted;
+        self
+    }
+
+    fn keeps(&self, entry: &PathEntry) -> bool {
+        match self.include {
+            Some(ref include) if entry.kind != EntryKind::Directory => include(&entry.path),
+            _ => true,
+        }
+    }
+
+    fn excludes(&self, path: &Path) -> bool {
+        match self.exclude {
+            Some(ref exclude) => exclude(path),
+            None => false,
+        }
+    }
+}
+
+impl<'a> Default for DirectoryOptions<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tree<PathEntry> {
+    ///
+    /// Builds a `Tree` out of the directory hierarchy starting at the given path, with one `Node`
+    /// per entry.  The starting path becomes the root (following it if it is a symlink).
+    ///
+    /// Only a starting path that cannot be read gives an `io::Error`.  Problems with any other
+    /// entry, such as denied permissions or symlink loops, are recorded in its `PathEntry::error`
+    /// and the walk carries on without going below it.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let options = DirectoryOptions::new()
+    ///     .with_max_depth(1)
+    ///     .with_sorted(true);
+    /// let tree = Tree::from_directory("src", &options).unwrap();
+    ///
+    /// let root_id = tree.root_node_id().unwrap();
+    /// assert_eq!(tree.get(root_id).unwrap().data().kind, EntryKind::Directory);
+    /// assert!(tree
+    ///     .children(root_id)
+    ///     .unwrap()
+    ///     .any(|node| node.data().file_name().unwrap() == "lib.rs"));
+    /// ```
+    ///
+    pub fn from_directory<P: AsRef<Path>>(
+        path: P,
+        options: &DirectoryOptions,
+    ) -> io::Result<Tree<PathEntry>> {
+        let root = PathEntry::read(path.as_ref().to_path_buf(), true);
+        if let Some(EntryError::Io(error)) = root.error {
+            return Err(error);
+        }
+
+        // every entry along with the index of its parent, with parents before their children and
+        // the children of each directory next to each other
+        let mut entries: Vec<(PathEntry, Option<usize>)> = vec![(root, None)];
+        // the canonical path of every directory, for finding symlink loops
+        let mut canonical_paths: Vec<Option<PathBuf>> = vec![None];
+        if options.follow_symlinks {
+            canonical_paths[0] = fs::canonicalize(&entries[0].0.path).ok();
+        }
+
+        let mut stack = vec![(0, 0)];
+        while let Some((index, depth)) = stack.pop() {
+            if !entries[index].0.can_descend()
+                || matches!(options.max_depth, Some(max) if depth >= max)
+            {
+                continue;
+            }
+
+            let mut paths = Vec::new();
+            match fs::read_dir(&entries[index].0.path) {
+                Ok(dir_entries) => {
+                    for dir_entry in dir_entries {
+                        match dir_entry {
+                            Ok(dir_entry) => paths.push(dir_entry.path()),
+                            Err(error) => {
+                                entries[index].0.error.get_or_insert(EntryError::Io(error));
+                            }
+                        }
+                    }
+                }
+                Err(error) => {
+                    entries[index].0.error = Some(EntryError::Io(error));
+                    continue;
+                }
+            }
+            if options.sorted {
+                paths.sort();
+            }
+
+            let first_child = entries.len();
+            for path in paths {
+                if options.excludes(&path) {
+                    continue;
+                }
+                let mut entry = PathEntry::read(path, options.follow_symlinks);
+                if !options.keeps(&entry) {
+                    continue;
+                }
+
+                let mut canonical_path = None;
+                if options.follow_symlinks && entry.can_descend() {
+                    match fs::canonicalize(&entry.path) {
+                        Ok(path) => {
+                            if leads_to_ancestor(&entries, &canonical_paths, index, &path) {
+                                entry.error = Some(EntryError::SymlinkLoop);
+                            }
+                            canonical_path = Some(path);
+                        }
+                        Err(error) => entry.error = Some(EntryError::Io(error)),
+                    }
+                }
+
+                entries.push((entry, Some(index)));
+                canonical_paths.push(canonical_path);
+            }
+            stack.extend(
+                (first_child..entries.len())
+                    .rev()
+                    .map(|child| (child, depth + 1)),
+            );
+        }
+
+        let mut tree = TreeBuilder::new().with_node_capacity(entries.len()).build();
+        let mut ids: Vec<NodeId> = Vec::with_capacity(entries.len());
+        for (entry, parent) in entries {
+            let node_id = match parent {
+                Some(parent) => tree.insert(Node::new(entry), UnderNode(&ids[parent])),
+                None => tree.insert(Node::new(entry), AsRoot),
+            };
+            ids.push(node_id.expect("parents are inserted before their children"));
+        }
+        Ok(tree)
+    }
+}
+
+fn leads_to_ancestor(
+    entries: &[(PathEntry, Option<usize>)],
+    canonical_paths: &[Option<PathBuf>],
+    parent: usize,
+    canonical_path: &Path,
+) -> bool {
+    let mut current = Some(parent);
+    while let Some(index) = current {
+        if canonical_paths[index].as_deref() == Some(canonical_path) {
+            return true;
+        }
+        current = entries[index].1;
+    }
+    false
+}
+
+#[cfg(test)]
+mod fs_tests {
+    use super::super::*;
+
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    // a fresh directory for each test, so that tests can run in parallel
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("id_tree_fs_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn create_files(dir: &Path) {
+        fs::create_dir_all(dir.join("b/d")).unwrap();
+        fs::create_dir_all(dir.join("c")).unwrap();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        fs::write(dir.join("b/e.rs"), "e").unwrap();
+        fs::write(dir.join("b/f.txt"), "f").unwrap();
+        fs::write(dir.join("b/d/g.rs"), "g").unwrap();
+    }
+
+    // the path of every entry below the root in pre-order, with a trailing `/` for directories
+    fn describe(tree: &Tree<PathEntry>) -> Vec<String> {
+        let root_id = tree.root_node_id().unwrap();
+        let root_path = &tree.get(root_id).unwrap().data().path;
+        tree.traverse_pre_order(root_id)
+            .unwrap()
+            .skip(1)
+            .map(|node| {
+                let entry = node.data();
+                let path = entry.path.strip_prefix(root_path).unwrap();
+                let mut description = path.to_string_lossy().replace('\\', "/");
+                if entry.kind == EntryKind::Directory {
+                    description.push('/');
+                }
+                description
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_directory() {
+        let dir = scratch_dir("from_directory");
+        create_files(&dir);
+
+        let sorted = DirectoryOptions::new().with_sorted(true);
+        let tree = Tree::from_directory(&dir, &sorted).unwrap();
+        assert!(tree.validate().is_ok());
+        assert_eq!(
+            describe(&tree),
+            vec!["a.txt", "b/", "b/d/", "b/d/g.rs", "b/e.rs", "b/f.txt", "c/"]
+        );
+
+        let root_id = tree.root_node_id().unwrap();
+        let root = tree.get(root_id).unwrap().data();
+        assert_eq!(root.path, dir);
+        assert_eq!(root.kind, EntryKind::Directory);
+        assert!(root.error.is_none());
+        let a = tree.children(root_id).unwrap().next().unwrap().data();
+        assert_eq!(a.file_name().unwrap(), "a.txt");
+        assert_eq!(a.kind, EntryKind::File);
+        assert_eq!(a.metadata.as_ref().unwrap().len(), 1);
+
+        let unsorted = Tree::from_directory(&dir, &DirectoryOptions::new()).unwrap();
+        let mut descriptions = describe(&unsorted);
+        descriptions.sort();
+        assert_eq!(descriptions, describe(&tree));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_from_directory_options() {
+        let dir = scratch_dir("options");
+        create_files(&dir);
+
+        let build =
+            |options: DirectoryOptions| describe(&Tree::from_directory(&dir, &options).unwrap());
+        let sorted = || DirectoryOptions::new().with_sorted(true);
+
+        assert!(build(sorted().with_max_depth(0)).is_empty());
+        assert_eq!(build(sorted().with_max_depth(1)), vec!["a.txt", "b/", "c/"]);
+        assert_eq!(
+            build(sorted().with_max_depth(2)),
+            vec!["a.txt", "b/", "b/d/", "b/e.rs", "b/f.txt", "c/"]
+        );
+
+        let is_rust =
+            |path: &Path| matches!(path.extension(), Some(extension) if extension == "rs");
+        assert_eq!(
+            build(sorted().with_include(is_rust)),
+            vec!["b/", "b/d/", "b/d/g.rs", "b/e.rs", "c/"]
+        );
+        assert_eq!(
+            build(sorted().with_exclude(|path| path.ends_with("b") || path.ends_with("a.txt"))),
+            vec!["c/"]
+        );
+        assert_eq!(
+            build(
+                sorted()
+                    .with_include(is_rust)
+                    .with_exclude(|path| path.ends_with("d"))
+            ),
+            vec!["b/", "b/e.rs", "c/"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_from_directory_missing_root() {
+        let dir = scratch_dir("missing_root");
+        let error =
+            Tree::from_directory(dir.join("missing"), &DirectoryOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+
+        // a file is a valid (if small) hierarchy
+        fs::write(dir.join("file"), "").unwrap();
+        let tree = Tree::from_directory(dir.join("file"), &DirectoryOptions::new()).unwrap();
+        let root_id = tree.root_node_id().unwrap();
+        assert_eq!(tree.get(root_id).unwrap().data().kind, EntryKind::File);
+        assert!(describe(&tree).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_from_directory_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = scratch_dir("symlinks");
+        create_files(&dir);
+        symlink(&dir, dir.join("b/loop")).unwrap();
+        symlink(dir.join("b/d"), dir.join("c/d")).unwrap();
+        symlink(dir.join("missing"), dir.join("dangling")).unwrap();
+
+        let kept = Tree::from_directory(&dir, &DirectoryOptions::new().with_sorted(true)).unwrap();
+        assert_eq!(
+            describe(&kept),
+            vec![
+                "a.txt", "b/", "b/d/", "b/d/g.rs", "b/e.rs", "b/f.txt", "b/loop", "c/", "c/d",
+                "dangling"
+            ]
+        );
+        assert!(kept
+            .traverse_pre_order(kept.root_node_id().unwrap())
+            .unwrap()
+            .all(|node| node.data().error.is_none()));
+
+        let options = DirectoryOptions::new()
+            .with_sorted(true)
+            .with_follow_symlinks(true);
+        let followed = Tree::from_directory(&dir, &options).unwrap();
+        assert_eq!(
+            describe(&followed),
+            vec![
+                "a.txt", "b/", "b/d/", "b/d/g.rs", "b/e.rs", "b/f.txt", "b/loop/", "c/", "c/d/",
+                "c/d/g.rs", "dangling"
+            ]
+        );
+
+        let root_id = followed.root_node_id().unwrap();
+        let errors: Vec<(String, &EntryError)> = followed
+            .traverse_pre_order(root_id)
+            .unwrap()
+            .filter_map(|node| {
+                let entry = node.data();
+                let name = entry.file_name().unwrap().to_string_lossy().into_owned();
+                entry.error.as_ref().map(|error| (name, error))
+            })
+            .collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].0, "loop");
+        match *errors[0].1 {
+            EntryError::SymlinkLoop => {}
+            ref error => panic!("unexpected error: {}", error),
+        }
+        assert_eq!(errors[1].0, "dangling");
+        match *errors[1].1 {
+            EntryError::Io(ref error) => assert_eq!(error.kind(), std::io::ErrorKind::NotFound),
+            ref error => panic!("unexpected error: {}", error),
+        }
+        assert_eq!(
+            errors[0].1.to_string(),
+            "EntryError: The symlink leads back to a directory above it."
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
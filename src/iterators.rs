@@ -1,6 +1,10 @@
+// `Option::inspect` needs Rust 1.76, so the iterators below stick to `map`
+#![allow(clippy::manual_inspect)]
+
 use std::collections::VecDeque;
-use std::slice::Iter;
-use std::vec::IntoIter;
+use std::mem;
+use std::slice;
+use std::vec;
 
 use Node;
 use NodeId;
@@ -26,7 +30,7 @@ pub struct Ancestors<'a, T: 'a> {
 impl<'a, T> Ancestors<'a, T> {
     pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> Ancestors<'a, T> {
         Ancestors {
-            tree: tree,
+            tree,
             node_id: Some(node_id),
         }
     }
@@ -51,7 +55,7 @@ impl<'a, T> Iterator for Ancestors<'a, T> {
 impl<'a, T> Clone for Ancestors<'a, T> {
     fn clone(&self) -> Self {
         Ancestors {
-            tree: &self.tree,
+            tree: self.tree,
             node_id: self.node_id.clone(),
         }
     }
@@ -70,7 +74,7 @@ pub struct AncestorIds<'a, T: 'a> {
 impl<'a, T> AncestorIds<'a, T> {
     pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> AncestorIds<'a, T> {
         AncestorIds {
-            tree: tree,
+            tree,
             node_id: Some(node_id),
         }
     }
@@ -84,10 +88,10 @@ impl<'a, T> Iterator for AncestorIds<'a, T> {
             .take()
             .and_then(|current_id| self.tree.get(&current_id).ok())
             .and_then(|node_ref| node_ref.parent())
-            .and_then(|parent_id| {
+            .map(|parent_id| {
                 self.node_id = Some(parent_id.clone());
 
-                Some(parent_id)
+                parent_id
             })
     }
 }
@@ -95,7 +99,7 @@ impl<'a, T> Iterator for AncestorIds<'a, T> {
 impl<'a, T> Clone for AncestorIds<'a, T> {
     fn clone(&self) -> Self {
         AncestorIds {
-            tree: &self.tree,
+            tree: self.tree,
             node_id: self.node_id.clone(),
         }
     }
@@ -109,13 +113,13 @@ impl<'a, T> Clone for AncestorIds<'a, T> {
 ///
 pub struct Children<'a, T: 'a> {
     tree: &'a Tree<T>,
-    child_ids: Iter<'a, NodeId>,
+    child_ids: slice::Iter<'a, NodeId>,
 }
 
 impl<'a, T> Children<'a, T> {
     pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> Children<'a, T> {
         Children {
-            tree: tree,
+            tree,
             child_ids: tree.get_unsafe(&node_id).children().as_slice().iter(),
         }
     }
@@ -134,7 +138,7 @@ impl<'a, T> Iterator for Children<'a, T> {
 impl<'a, T> Clone for Children<'a, T> {
     fn clone(&self) -> Self {
         Children {
-            tree: &self.tree,
+            tree: self.tree,
             child_ids: self.child_ids.clone(),
         }
     }
@@ -147,7 +151,7 @@ impl<'a, T> Clone for Children<'a, T> {
 ///
 #[derive(Clone)]
 pub struct ChildrenIds<'a> {
-    child_ids: Iter<'a, NodeId>,
+    child_ids: slice::Iter<'a, NodeId>,
 }
 
 impl<'a> ChildrenIds<'a> {
@@ -178,16 +182,13 @@ pub struct PreOrderTraversal<'a, T: 'a> {
 }
 
 impl<'a, T> PreOrderTraversal<'a, T> {
-    pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> PreOrderTraversal<T> {
+    pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> PreOrderTraversal<'a, T> {
         // over allocating, but all at once instead of re-sizing and re-allocating as we go
         let mut data = VecDeque::with_capacity(tree.capacity());
 
         data.push_front(node_id);
 
-        PreOrderTraversal {
-            tree: tree,
-            data: data,
-        }
+        PreOrderTraversal { tree, data }
     }
 }
 
@@ -198,13 +199,13 @@ impl<'a, T> Iterator for PreOrderTraversal<'a, T> {
         self.data
             .pop_front()
             .and_then(|node_id| self.tree.get(&node_id).ok())
-            .and_then(|node_ref| {
+            .map(|node_ref| {
                 // prepend child_ids
                 for child_id in node_ref.children().iter().rev() {
                     self.data.push_front(child_id.clone());
                 }
 
-                Some(node_ref)
+                node_ref
             })
     }
 }
@@ -212,7 +213,7 @@ impl<'a, T> Iterator for PreOrderTraversal<'a, T> {
 impl<'a, T> Clone for PreOrderTraversal<'a, T> {
     fn clone(&self) -> Self {
         PreOrderTraversal {
-            tree: &self.tree,
+            tree: self.tree,
             data: self.data.clone(),
         }
     }
@@ -236,10 +237,7 @@ impl<'a, T> PreOrderTraversalIds<'a, T> {
 
         data.push_front(node_id);
 
-        PreOrderTraversalIds {
-            tree: tree,
-            data: data,
-        }
+        PreOrderTraversalIds { tree, data }
     }
 }
 
@@ -248,13 +246,13 @@ impl<'a, T> Iterator for PreOrderTraversalIds<'a, T> {
 
     fn next(&mut self) -> Option<NodeId> {
         self.data.pop_front().and_then(|node_id| {
-            self.tree.get(&node_id).ok().and_then(|node_ref| {
+            self.tree.get(&node_id).ok().map(|node_ref| {
                 // prepend child_ids
                 for child_id in node_ref.children().iter().rev() {
                     self.data.push_front(child_id.clone());
                 }
 
-                Some(node_id)
+                node_id
             })
         })
     }
@@ -263,7 +261,7 @@ impl<'a, T> Iterator for PreOrderTraversalIds<'a, T> {
 impl<'a, T> Clone for PreOrderTraversalIds<'a, T> {
     fn clone(&self) -> Self {
         PreOrderTraversalIds {
-            tree: &self.tree,
+            tree: self.tree,
             data: self.data.clone(),
         }
     }
@@ -277,18 +275,18 @@ impl<'a, T> Clone for PreOrderTraversalIds<'a, T> {
 ///
 pub struct PostOrderTraversal<'a, T: 'a> {
     tree: &'a Tree<T>,
-    ids: IntoIter<NodeId>,
+    ids: vec::IntoIter<NodeId>,
 }
 
 impl<'a, T> PostOrderTraversal<'a, T> {
-    pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> PostOrderTraversal<T> {
+    pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> PostOrderTraversal<'a, T> {
         // over allocating, but all at once instead of re-sizing and re-allocating as we go
         let mut ids = Vec::with_capacity(tree.capacity());
 
         PostOrderTraversal::process_nodes(node_id, tree, &mut ids);
 
         PostOrderTraversal {
-            tree: tree,
+            tree,
             ids: ids.into_iter(),
         }
     }
@@ -317,7 +315,7 @@ impl<'a, T> Iterator for PostOrderTraversal<'a, T> {
 impl<'a, T> Clone for PostOrderTraversal<'a, T> {
     fn clone(&self) -> Self {
         PostOrderTraversal {
-            tree: &self.tree,
+            tree: self.tree,
             ids: self.ids.clone(),
         }
     }
@@ -331,7 +329,7 @@ impl<'a, T> Clone for PostOrderTraversal<'a, T> {
 ///
 #[derive(Clone)]
 pub struct PostOrderTraversalIds {
-    ids: IntoIter<NodeId>,
+    ids: vec::IntoIter<NodeId>,
 }
 
 impl PostOrderTraversalIds {
@@ -377,16 +375,13 @@ pub struct LevelOrderTraversal<'a, T: 'a> {
 }
 
 impl<'a, T> LevelOrderTraversal<'a, T> {
-    pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> LevelOrderTraversal<T> {
+    pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> LevelOrderTraversal<'a, T> {
         // over allocating, but all at once instead of re-sizing and re-allocating as we go
         let mut data = VecDeque::with_capacity(tree.capacity());
 
         data.push_back(node_id);
 
-        LevelOrderTraversal {
-            tree: tree,
-            data: data,
-        }
+        LevelOrderTraversal { tree, data }
     }
 }
 
@@ -397,12 +392,12 @@ impl<'a, T> Iterator for LevelOrderTraversal<'a, T> {
         self.data
             .pop_front()
             .and_then(|node_id| self.tree.get(&node_id).ok())
-            .and_then(|node_ref| {
+            .map(|node_ref| {
                 for child_id in node_ref.children() {
                     self.data.push_back(child_id.clone());
                 }
 
-                Some(node_ref)
+                node_ref
             })
     }
 }
@@ -410,7 +405,7 @@ impl<'a, T> Iterator for LevelOrderTraversal<'a, T> {
 impl<'a, T> Clone for LevelOrderTraversal<'a, T> {
     fn clone(&self) -> Self {
         LevelOrderTraversal {
-            tree: &self.tree,
+            tree: self.tree,
             data: self.data.clone(),
         }
     }
@@ -428,16 +423,13 @@ pub struct LevelOrderTraversalIds<'a, T: 'a> {
 }
 
 impl<'a, T> LevelOrderTraversalIds<'a, T> {
-    pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> LevelOrderTraversalIds<T> {
+    pub(crate) fn new(tree: &'a Tree<T>, node_id: NodeId) -> LevelOrderTraversalIds<'a, T> {
         // over allocating, but all at once instead of re-sizing and re-allocating as we go
         let mut data = VecDeque::with_capacity(tree.capacity());
 
         data.push_back(node_id);
 
-        LevelOrderTraversalIds {
-            tree: tree,
-            data: data,
-        }
+        LevelOrderTraversalIds { tree, data }
     }
 }
 
@@ -446,12 +438,12 @@ impl<'a, T> Iterator for LevelOrderTraversalIds<'a, T> {
 
     fn next(&mut self) -> Option<NodeId> {
         self.data.pop_front().and_then(|node_id| {
-            self.tree.get(&node_id).ok().and_then(|node_ref| {
+            self.tree.get(&node_id).ok().map(|node_ref| {
                 for child_id in node_ref.children() {
                     self.data.push_back(child_id.clone());
                 }
 
-                Some(node_id)
+                node_id
             })
         })
     }
@@ -460,12 +452,299 @@ impl<'a, T> Iterator for LevelOrderTraversalIds<'a, T> {
 impl<'a, T> Clone for LevelOrderTraversalIds<'a, T> {
     fn clone(&self) -> Self {
         LevelOrderTraversalIds {
-            tree: &self.tree,
+            tree: self.tree,
             data: self.data.clone(),
         }
     }
 }
 
+///
+/// An Iterator over every `Node` below the root of a `Tree` together with its parent.
+///
+/// Each call to `next` will return the `NodeId` of the next `Node` in Pre-Order Traversal order,
+/// the `NodeId` of its parent (`None` for the root) and an immutable reference to its data.
+///
+pub struct ParentList<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    stack: Vec<&'a NodeId>,
+}
+
+impl<'a, T> ParentList<'a, T> {
+    pub(crate) fn new(tree: &'a Tree<T>) -> ParentList<'a, T> {
+        ParentList {
+            tree,
+            stack: tree.root_node_id().into_iter().collect(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for ParentList<'a, T> {
+    type Item = (NodeId, Option<NodeId>, &'a T);
+
+    fn next(&mut self) -> Option<(NodeId, Option<NodeId>, &'a T)> {
+        self.stack.pop().map(|node_id| {
+            let node = self.tree.get_unsafe(node_id);
+            self.stack.extend(node.children().iter().rev());
+
+            (node_id.clone(), node.parent().cloned(), node.data())
+        })
+    }
+}
+
+impl<'a, T> Clone for ParentList<'a, T> {
+    fn clone(&self) -> Self {
+        ParentList {
+            tree: self.tree,
+            stack: self.stack.clone(),
+        }
+    }
+}
+
+///
+/// An Iterator over the data of every `Node` below the root of a `Tree`.
+///
+/// Each call to `next` will return an immutable reference to the data of the next `Node` in
+/// Pre-Order Traversal order.  Orphaned `Node`s are left out.
+///
+pub struct Iter<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    stack: Vec<&'a NodeId>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    pub(crate) fn new(tree: &'a Tree<T>) -> Iter<'a, T> {
+        Iter {
+            tree,
+            stack: tree.root_node_id().into_iter().collect(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.stack.pop().map(|node_id| {
+            let node = self.tree.get_unsafe(node_id);
+            self.stack.extend(node.children().iter().rev());
+
+            node.data()
+        })
+    }
+}
+
+impl<'a, T> Clone for Iter<'a, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            tree: self.tree,
+            stack: self.stack.clone(),
+        }
+    }
+}
+
+///
+/// An Iterator that moves the data out of every `Node` below the root of a `Tree`.
+///
+/// Each call to `next` will return the data of the next `Node` in Pre-Order Traversal order.
+/// Orphaned `Node`s are dropped along with the `Tree`.
+///
+pub struct IntoIter<T> {
+    pre_order: IntoPreOrder<T>,
+}
+
+impl<T> IntoIter<T> {
+    pub(crate) fn new(nodes: Vec<Option<Node<T>>>, root: Option<NodeId>) -> IntoIter<T> {
+        IntoIter {
+            pre_order: IntoPreOrder::new(nodes, root),
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.pre_order.next().map(|(data, _)| data)
+    }
+}
+
+// takes the Node at the given index out of the nodes of a consumed Tree
+fn take_owned<T>(nodes: &mut [Option<Node<T>>], index: usize) -> Node<T> {
+    nodes[index]
+        .take()
+        .expect("every Node below the root is visited once")
+}
+
+///
+/// An Iterator that moves the data out of every `Node` below the root of a `Tree`.
+///
+/// Each call to `next` will return the data of the next `Node` in Pre-Order Traversal order
+/// along with its depth (0 for the root).  Orphaned `Node`s are dropped along with the `Tree`.
+///
+pub struct IntoPreOrder<T> {
+    nodes: Vec<Option<Node<T>>>,
+    stack: Vec<(usize, usize)>,
+}
+
+impl<T> IntoPreOrder<T> {
+    pub(crate) fn new(nodes: Vec<Option<Node<T>>>, root: Option<NodeId>) -> IntoPreOrder<T> {
+        IntoPreOrder {
+            nodes,
+            stack: root.into_iter().map(|root_id| (root_id.index, 0)).collect(),
+        }
+    }
+}
+
+impl<T> Iterator for IntoPreOrder<T> {
+    type Item = (T, usize);
+
+    fn next(&mut self) -> Option<(T, usize)> {
+        self.stack.pop().map(|(index, depth)| {
+            let node = take_owned(&mut self.nodes, index);
+            self.stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|child_id| (child_id.index, depth + 1)),
+            );
+
+            (node.data, depth)
+        })
+    }
+}
+
+///
+/// An Iterator that moves the data out of every `Node` below the root of a `Tree`.
+///
+/// Each call to `next` will return the data of the next `Node` in Post-Order Traversal order
+/// along with its depth (0 for the root), so every `Node` comes after all of its children.
+/// Orphaned `Node`s are dropped along with the `Tree`.
+///
+pub struct IntoPostOrder<T> {
+    nodes: Vec<Option<Node<T>>>,
+    stack: Vec<(usize, usize)>,
+}
+
+impl<T> IntoPostOrder<T> {
+    pub(crate) fn new(nodes: Vec<Option<Node<T>>>, root: Option<NodeId>) -> IntoPostOrder<T> {
+        IntoPostOrder {
+            nodes,
+            stack: root.into_iter().map(|root_id| (root_id.index, 0)).collect(),
+        }
+    }
+}
+
+impl<T> Iterator for IntoPostOrder<T> {
+    type Item = (T, usize);
+
+    fn next(&mut self) -> Option<(T, usize)> {
+        while let Some(&(index, depth)) = self.stack.last() {
+            let children = match self.nodes[index] {
+                Some(ref mut node) => mem::take(&mut node.children),
+                None => unreachable!("every Node below the root is visited once"),
+            };
+
+            // a Node whose children have been taken is left on the stack below them, so it comes
+            // back up once they are all done
+            if children.is_empty() {
+                self.stack.pop();
+                return Some((take_owned(&mut self.nodes, index).data, depth));
+            }
+            self.stack.extend(
+                children
+                    .iter()
+                    .rev()
+                    .map(|child_id| (child_id.index, depth + 1)),
+            );
+        }
+        None
+    }
+}
+
+///
+/// An Iterator that moves the data out of every `Node` below the root of a `Tree`.
+///
+/// Each call to `next` will return the data of the next `Node` in Level-Order Traversal order
+/// along with its depth (0 for the root).  Orphaned `Node`s are dropped along with the `Tree`.
+///
+pub struct IntoLevelOrder<T> {
+    nodes: Vec<Option<Node<T>>>,
+    queue: VecDeque<(usize, usize)>,
+}
+
+impl<T> IntoLevelOrder<T> {
+    pub(crate) fn new(nodes: Vec<Option<Node<T>>>, root: Option<NodeId>) -> IntoLevelOrder<T> {
+        IntoLevelOrder {
+            nodes,
+            queue: root.into_iter().map(|root_id| (root_id.index, 0)).collect(),
+        }
+    }
+}
+
+impl<T> Iterator for IntoLevelOrder<T> {
+    type Item = (T, usize);
+
+    fn next(&mut self) -> Option<(T, usize)> {
+        self.queue.pop_front().map(|(index, depth)| {
+            let node = take_owned(&mut self.nodes, index);
+            self.queue.extend(
+                node.children
+                    .iter()
+                    .map(|child_id| (child_id.index, depth + 1)),
+            );
+
+            (node.data, depth)
+        })
+    }
+}
+
+///
+/// An Iterator that removes a branch from a `Tree`.
+///
+/// The branch is cut off from the rest of the `Tree` as soon as the iterator is created.  Each
+/// call to `next` will remove the next `Node` of the branch in Pre-Order Traversal order and
+/// return its data along with its depth below the top of the branch.  Whatever is left of the
+/// branch is removed when the iterator is dropped.
+///
+pub struct DrainSubtree<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    stack: Vec<(NodeId, usize)>,
+}
+
+impl<'a, T> DrainSubtree<'a, T> {
+    pub(crate) fn new(tree: &'a mut Tree<T>, node_id: NodeId) -> DrainSubtree<'a, T> {
+        DrainSubtree {
+            tree,
+            stack: vec![(node_id, 0)],
+        }
+    }
+}
+
+impl<'a, T> Iterator for DrainSubtree<'a, T> {
+    type Item = (T, usize);
+
+    fn next(&mut self) -> Option<(T, usize)> {
+        self.stack.pop().map(|(node_id, depth)| {
+            let node = self.tree.take_node(node_id);
+            self.stack.extend(
+                node.children
+                    .into_iter()
+                    .rev()
+                    .map(|child_id| (child_id, depth + 1)),
+            );
+
+            (node.data, depth)
+        })
+    }
+}
+
+impl<'a, T> Drop for DrainSubtree<'a, T> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+        self.tree.validate_mutation();
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
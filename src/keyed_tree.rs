@@ -0,0 +1,717 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::slice;
+
+use super::*;
+
+///
+/// A `Tree` whose `Node`s each carry a key that is unique among their siblings.
+///
+/// Every `Node` keeps a `HashMap` from its children's keys to their `NodeId`s, so looking up a
+/// child by key is O(1) instead of a linear scan over `Node::children`.  The index is kept in sync
+/// through every operation that can change a `Node`'s parent.  Operations that would leave two
+/// siblings with the same key are rejected with `KeyedTreeError::DuplicateSiblingKey` before the
+/// `Tree` is touched.
+///
+/// `Node`s without a parent (the root and any orphans) are not constrained by their keys.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut tree: KeyedTree<&str, i32> = KeyedTree::new();
+///
+/// let root_id = tree.insert("root", Node::new(0), AsRoot).unwrap();
+/// let usr_id = tree.insert("usr", Node::new(1), UnderNode(&root_id)).unwrap();
+/// tree.insert("etc", Node::new(2), UnderNode(&root_id)).unwrap();
+///
+/// assert_eq!(tree.child_by_key(&root_id, "usr").unwrap(), Some(&usr_id));
+/// assert_eq!(tree.child_by_key(&root_id, "var").unwrap(), None);
+///
+/// let duplicate = tree.insert("usr", Node::new(3), UnderNode(&root_id));
+/// assert_eq!(duplicate, Err(KeyedTreeError::DuplicateSiblingKey));
+/// ```
+///
+#[derive(Debug)]
+pub struct KeyedTree<K, T> {
+    tree: Tree<T>,
+    keys: Vec<Option<K>>,
+    child_index: Vec<HashMap<K, NodeId>>,
+}
+
+impl<K, T> KeyedTree<K, T>
+where
+    K: Hash + Eq + Clone,
+{
+    ///
+    /// Creates a new, empty `KeyedTree`.
+    ///
+    /// ```
+    /// use id_tree::KeyedTree;
+    ///
+    /// let _tree: KeyedTree<String, i32> = KeyedTree::new();
+    /// ```
+    ///
+    pub fn new() -> KeyedTree<K, T> {
+        KeyedTree {
+            tree: Tree::new(),
+            keys: Vec::new(),
+            child_index: Vec::new(),
+        }
+    }
+
+    ///
+    /// Returns an immutable reference to the underlying `Tree`.
+    ///
+    /// All of the read-only `Tree` methods (traversals, `ancestors`, `height`, etc.) are available
+    /// through this reference.
+    ///
+    pub fn tree(&self) -> &Tree<T> {
+        &self.tree
+    }
+
+    ///
+    /// Consumes the `KeyedTree`, dropping the keys and returning the underlying `Tree`.
+    ///
+    pub fn into_tree(self) -> Tree<T> {
+        self.tree
+    }
+
+    ///
+    /// Returns a `Some` value containing the `NodeId` of the root `Node` if it exists.  Otherwise a
+    /// `None` value is returned.
+    ///
+    pub fn root_node_id(&self) -> Option<&NodeId> {
+        self.tree.root_node_id()
+    }
+
+    ///
+    /// Get an immutable reference to a `Node`.
+    ///
+    /// Returns a `Result` containing the immutable reference or a `NodeIdError` if one occurred.
+    ///
+    pub fn get(&self, node_id: &NodeId) -> Result<&Node<T>, NodeIdError> {
+        self.tree.get(node_id)
+    }
+
+    ///
+    /// Get a mutable reference to a `Node`.
+    ///
+    /// Keys are stored separately from the data, so changing the data never invalidates the index.
+    ///
+    /// Returns a `Result` containing the mutable reference or a `NodeIdError` if one occurred.
+    ///
+    pub fn get_mut(&mut self, node_id: &NodeId) -> Result<&mut Node<T>, NodeIdError> {
+        self.tree.get_mut(node_id)
+    }
+
+    ///
+    /// Returns the key of the `Node` specified by the `NodeId` given (or a `NodeIdError` if one
+    /// occurred).
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: KeyedTree<&str, i32> = KeyedTree::new();
+    /// let root_id = tree.insert("root", Node::new(0), AsRoot).unwrap();
+    ///
+    /// assert_eq!(tree.key(&root_id).unwrap(), &"root");
+    /// ```
+    ///
+    pub fn key(&self, node_id: &NodeId) -> Result<&K, NodeIdError> {
+        self.tree.get(node_id)?;
+        Ok(self.key_unsafe(node_id))
+    }
+
+    ///
+    /// Looks up the child of a `Node` by its key in O(1).
+    ///
+    /// Returns a `Result` containing `Some(&NodeId)` of the matching child, `None` if no child
+    /// uses that key, or a `NodeIdError` if one occurred.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: KeyedTree<String, i32> = KeyedTree::new();
+    /// let root_id = tree.insert("root".to_string(), Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert("a".to_string(), Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// assert_eq!(tree.child_by_key(&root_id, "a").unwrap(), Some(&child_id));
+    /// ```
+    ///
+    pub fn child_by_key<Q>(
+        &self,
+        parent_id: &NodeId,
+        key: &Q,
+    ) -> Result<Option<&NodeId>, NodeIdError>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.tree.get(parent_id)?;
+        Ok(self.child_index[parent_id.index].get(key))
+    }
+
+    ///
+    /// Inserts a new `Node` with the given key into the `KeyedTree`.  The `InsertBehavior`
+    /// provided will determine where the `Node` is inserted.
+    ///
+    /// Returns a `Result` containing the `NodeId` of the `Node` that was inserted or a
+    /// `KeyedTreeError` if one occurred.  Inserting under a parent that already has a child with
+    /// the same key returns `KeyedTreeError::DuplicateSiblingKey`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: KeyedTree<u32, &str> = KeyedTree::new();
+    /// let root_id = tree.insert(0, Node::new("root"), AsRoot).unwrap();
+    ///
+    /// assert!(tree.insert(1, Node::new("one"), UnderNode(&root_id)).is_ok());
+    /// assert!(tree.insert(1, Node::new("uno"), UnderNode(&root_id)).is_err());
+    /// ```
+    ///
+    pub fn insert(
+        &mut self,
+        key: K,
+        node: Node<T>,
+        behavior: InsertBehavior,
+    ) -> Result<NodeId, KeyedTreeError> {
+        match behavior {
+            InsertBehavior::UnderNode(parent_id) => {
+                self.tree.get(parent_id)?;
+                if self.child_index[parent_id.index].contains_key(&key) {
+                    return Err(KeyedTreeError::DuplicateSiblingKey);
+                }
+
+                let new_id = self
+                    .tree
+                    .insert(node, InsertBehavior::UnderNode(parent_id))?;
+                self.set_key(&new_id, key.clone());
+                self.child_index[parent_id.index].insert(key, new_id.clone());
+                Ok(new_id)
+            }
+            InsertBehavior::AsRoot => {
+                // the old root becomes the only child of the new root, so no conflict is possible
+                let new_id = self.tree.insert(node, InsertBehavior::AsRoot)?;
+                self.set_key(&new_id, key);
+                self.reindex(&new_id);
+                Ok(new_id)
+            }
+        }
+    }
+
+    ///
+    /// Remove a `Node` from the `KeyedTree`.  The `RemoveBehavior` provided determines what
+    /// happens to the removed `Node`'s children.
+    ///
+    /// Returns a `Result` containing the removed `Node` and its key or a `KeyedTreeError` if one
+    /// occurred.  `RemoveBehavior::LiftChildren` fails with `KeyedTreeError::DuplicateSiblingKey`
+    /// if one of the lifted children collides with a sibling of the removed `Node`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::RemoveBehavior::*;
+    ///
+    /// let mut tree: KeyedTree<&str, i32> = KeyedTree::new();
+    /// let root_id = tree.insert("root", Node::new(0), AsRoot).unwrap();
+    /// let a_id = tree.insert("a", Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert("b", Node::new(2), UnderNode(&a_id)).unwrap();
+    /// tree.insert("b", Node::new(3), UnderNode(&root_id)).unwrap();
+    ///
+    /// // lifting "b" up next to the other "b" is not allowed
+    /// assert!(tree.remove_node(a_id.clone(), LiftChildren).is_err());
+    ///
+    /// let (key, node) = tree.remove_node(a_id, DropChildren).unwrap();
+    /// assert_eq!(key, "a");
+    /// assert_eq!(node.data(), &1);
+    /// assert!(tree.child_by_key(&root_id, "a").unwrap().is_none());
+    /// ```
+    ///
+    pub fn remove_node(
+        &mut self,
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+    ) -> Result<(K, Node<T>), KeyedTreeError> {
+        self.tree.get(&node_id)?;
+
+        let parent_id = self.tree.get_unsafe(&node_id).parent().cloned();
+        let (removed_ids, lift) = match behavior {
+            RemoveBehavior::DropChildren => {
+                (self.tree.traverse_pre_order_ids(&node_id)?.collect(), false)
+            }
+            RemoveBehavior::LiftChildren => (vec![node_id.clone()], true),
+            RemoveBehavior::OrphanChildren => (vec![node_id.clone()], false),
+        };
+
+        if let (true, Some(ref parent_id)) = (lift, &parent_id) {
+            let children = self.tree.get_unsafe(&node_id).children().clone();
+            self.check_siblings(parent_id, &children, slice::from_ref(&node_id))?;
+        }
+
+        let index = node_id.index;
+        let node = self.tree.remove_node(node_id, behavior)?;
+
+        let key = self
+            .keys
+            .get_mut(index)
+            .and_then(|key| key.take())
+            .expect("KeyedTree::remove_node: Missing a key for a valid NodeId.");
+        for removed_id in &removed_ids {
+            self.clear_slot(removed_id.index);
+        }
+
+        if let Some(parent_id) = parent_id {
+            if lift {
+                self.reindex(&parent_id);
+            } else {
+                self.child_index[parent_id.index].remove(&key);
+            }
+        }
+
+        Ok((key, node))
+    }
+
+    ///
+    /// Moves a `Node` in the `KeyedTree` to a new location based upon the `MoveBehavior` provided.
+    ///
+    /// Returns an empty `Result` or a `KeyedTreeError` if one occurred.  Moves that would give a
+    /// `Node` a sibling with the same key fail with `KeyedTreeError::DuplicateSiblingKey`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::MoveBehavior::*;
+    ///
+    /// let mut tree: KeyedTree<&str, i32> = KeyedTree::new();
+    /// let root_id = tree.insert("root", Node::new(0), AsRoot).unwrap();
+    /// let a_id = tree.insert("a", Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let b_id = tree.insert("b", Node::new(2), UnderNode(&root_id)).unwrap();
+    ///
+    /// tree.move_node(&b_id, ToParent(&a_id)).unwrap();
+    ///
+    /// assert_eq!(tree.child_by_key(&a_id, "b").unwrap(), Some(&b_id));
+    /// assert!(tree.child_by_key(&root_id, "b").unwrap().is_none());
+    /// ```
+    ///
+    pub fn move_node(
+        &mut self,
+        node_id: &NodeId,
+        behavior: MoveBehavior,
+    ) -> Result<(), KeyedTreeError> {
+        self.tree.get(node_id)?;
+
+        let mut touched = vec![node_id.clone()];
+        touched.extend(self.tree.get_unsafe(node_id).parent().cloned());
+
+        match behavior {
+            MoveBehavior::ToRoot => {
+                if let Some(root_id) = self.tree.root_node_id().cloned() {
+                    if &root_id != node_id {
+                        // the old root will become a child of node_id
+                        self.check_siblings(node_id, slice::from_ref(&root_id), &[])?;
+                        touched.push(root_id);
+                    }
+                }
+
+                self.tree.move_node(node_id, MoveBehavior::ToRoot)?;
+            }
+            MoveBehavior::ToParent(parent_id) => {
+                self.tree.get(parent_id)?;
+
+                // when moving "down" the tree, the child of node_id on the path to parent_id takes
+                // node_id's place under its old parent
+                let shifted_id = self
+                    .tree
+                    .find_subtree_root_between_ids(parent_id, node_id)
+                    .cloned();
+                if let (Some(ref shifted_id), Some(old_parent_id)) = (&shifted_id, touched.get(1)) {
+                    self.check_siblings(
+                        old_parent_id,
+                        slice::from_ref(shifted_id),
+                        slice::from_ref(node_id),
+                    )?;
+                }
+                self.check_siblings(parent_id, slice::from_ref(node_id), &[])?;
+
+                touched.push(parent_id.clone());
+                self.tree
+                    .move_node(node_id, MoveBehavior::ToParent(parent_id))?;
+            }
+        }
+
+        self.reindex_all(&touched);
+        Ok(())
+    }
+
+    ///
+    /// Swap `Node`s in the `KeyedTree` based upon the `SwapBehavior` provided.
+    ///
+    /// Returns an empty `Result` or a `KeyedTreeError` if one occurred.  Swaps that would give a
+    /// `Node` a sibling with the same key fail with `KeyedTreeError::DuplicateSiblingKey`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::SwapBehavior::*;
+    ///
+    /// let mut tree: KeyedTree<&str, i32> = KeyedTree::new();
+    /// let root_id = tree.insert("root", Node::new(0), AsRoot).unwrap();
+    /// let a_id = tree.insert("a", Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let b_id = tree.insert("b", Node::new(2), UnderNode(&root_id)).unwrap();
+    /// let ax_id = tree.insert("x", Node::new(3), UnderNode(&a_id)).unwrap();
+    /// let bx_id = tree.insert("x", Node::new(4), UnderNode(&b_id)).unwrap();
+    /// let by_id = tree.insert("y", Node::new(5), UnderNode(&b_id)).unwrap();
+    ///
+    /// // "b" already has a child called "x"
+    /// assert!(tree.swap_nodes(&ax_id, &by_id, TakeChildren).is_err());
+    /// // but trading one "x" for another is fine
+    /// assert!(tree.swap_nodes(&ax_id, &bx_id, TakeChildren).is_ok());
+    /// ```
+    ///
+    pub fn swap_nodes(
+        &mut self,
+        first_id: &NodeId,
+        second_id: &NodeId,
+        behavior: SwapBehavior,
+    ) -> Result<(), KeyedTreeError> {
+        self.tree.get(first_id)?;
+        self.tree.get(second_id)?;
+
+        let first_parent_id = self.tree.get_unsafe(first_id).parent().cloned();
+        let second_parent_id = self.tree.get_unsafe(second_id).parent().cloned();
+
+        let lower_upper = self
+            .tree
+            .find_subtree_root_between_ids(first_id, second_id)
+            .map(|_| (first_id, second_id))
+            .or_else(|| {
+                self.tree
+                    .find_subtree_root_between_ids(second_id, first_id)
+                    .map(|_| (second_id, first_id))
+            });
+
+        match (&behavior, lower_upper) {
+            (&SwapBehavior::TakeChildren, Some((lower_id, upper_id))) => {
+                // lower takes upper's place and upper is attached as the last child of lower
+                if let Some(upper_parent_id) = self.tree.get_unsafe(upper_id).parent() {
+                    self.check_siblings(
+                        upper_parent_id,
+                        slice::from_ref(lower_id),
+                        slice::from_ref(upper_id),
+                    )?;
+                }
+                self.check_siblings(lower_id, slice::from_ref(upper_id), &[])?;
+            }
+            (&SwapBehavior::ChildrenOnly, Some((lower_id, _))) => {
+                // upper ends up with lower's children plus lower itself
+                self.check_siblings(lower_id, slice::from_ref(lower_id), &[])?;
+            }
+            (&SwapBehavior::ChildrenOnly, None) => {
+                // each child set is moved as a whole, so the keys stay unique
+            }
+            _ => {
+                if first_parent_id != second_parent_id {
+                    if let Some(ref first_parent_id) = first_parent_id {
+                        self.check_siblings(
+                            first_parent_id,
+                            slice::from_ref(second_id),
+                            slice::from_ref(first_id),
+                        )?;
+                    }
+                    if let Some(ref second_parent_id) = second_parent_id {
+                        self.check_siblings(
+                            second_parent_id,
+                            slice::from_ref(first_id),
+                            slice::from_ref(second_id),
+                        )?;
+                    }
+                }
+            }
+        }
+
+        self.tree.swap_nodes(first_id, second_id, behavior)?;
+
+        let mut touched = vec![first_id.clone(), second_id.clone()];
+        touched.extend(first_parent_id);
+        touched.extend(second_parent_id);
+        self.reindex_all(&touched);
+
+        Ok(())
+    }
+
+    ///
+    /// Sorts the children of one node, in-place, using compare to compare the nodes.
+    ///
+    /// This does not change any parent/child relationships, so the key index is left as-is.
+    ///
+    /// Returns an empty `Result` containing a `NodeIdError` if one occurred.
+    ///
+    pub fn sort_children_by<F>(&mut self, node_id: &NodeId, compare: F) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&Node<T>, &Node<T>) -> Ordering,
+    {
+        self.tree.sort_children_by(node_id, compare)
+    }
+
+    ///
+    /// Moves the node to a position amongst sibling nodes.  See `Tree::make_nth_sibling` for more
+    /// information.
+    ///
+    /// This does not change any parent/child relationships, so the key index is left as-is.
+    ///
+    pub fn make_nth_sibling(&mut self, node_id: &NodeId, pos: usize) -> Result<(), NodeIdError> {
+        self.tree.make_nth_sibling(node_id, pos)
+    }
+
+    ///
+    /// Makes sure none of the `incoming` `Node`s share a key with a child of `parent_id` that will
+    /// still be there afterwards (i.e. one that is not listed in `outgoing`).
+    ///
+    fn check_siblings(
+        &self,
+        parent_id: &NodeId,
+        incoming: &[NodeId],
+        outgoing: &[NodeId],
+    ) -> Result<(), KeyedTreeError> {
+        let index = &self.child_index[parent_id.index];
+        for incoming_id in incoming {
+            if let Some(holder_id) = index.get(self.key_unsafe(incoming_id)) {
+                if holder_id != incoming_id && !outgoing.contains(holder_id) {
+                    return Err(KeyedTreeError::DuplicateSiblingKey);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn reindex_all(&mut self, node_ids: &[NodeId]) {
+        for node_id in node_ids {
+            if self.tree.get(node_id).is_ok() {
+                self.reindex(node_id);
+            }
+        }
+    }
+
+    ///
+    /// Rebuilds the child index of a `Node` from its current children.
+    ///
+    fn reindex(&mut self, node_id: &NodeId) {
+        let children = self.tree.get_unsafe(node_id).children();
+        let mut index = HashMap::with_capacity(children.len());
+        for child_id in children {
+            let previous = index.insert(self.key_unsafe(child_id).clone(), child_id.clone());
+            debug_assert!(
+                previous.is_none(),
+                "KeyedTree::reindex: Found duplicate sibling keys.  Please report this issue!"
+            );
+        }
+        self.child_index[node_id.index] = index;
+    }
+
+    fn set_key(&mut self, node_id: &NodeId, key: K) {
+        if self.keys.len() <= node_id.index {
+            let len = node_id.index + 1;
+            self.keys.resize_with(len, || None);
+            self.child_index.resize_with(len, HashMap::new);
+        }
+        self.keys[node_id.index] = Some(key);
+        self.child_index[node_id.index] = HashMap::new();
+    }
+
+    fn clear_slot(&mut self, index: usize) {
+        self.keys[index] = None;
+        self.child_index[index] = HashMap::new();
+    }
+
+    fn key_unsafe(&self, node_id: &NodeId) -> &K {
+        self.keys[node_id.index].as_ref().expect(
+            "KeyedTree::key_unsafe: Missing a key for a valid NodeId.  Please report this issue!",
+        )
+    }
+}
+
+impl<K, T> Default for KeyedTree<K, T>
+where
+    K: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod keyed_tree_tests {
+    use super::KeyedTree;
+    use InsertBehavior::*;
+    use KeyedTreeError;
+    use MoveBehavior::*;
+    use Node;
+    use NodeId;
+    use RemoveBehavior::*;
+    use SwapBehavior::*;
+
+    fn assert_index_matches<T>(tree: &KeyedTree<&'static str, T>, parent_id: &NodeId) {
+        let children = tree.get(parent_id).unwrap().children();
+        assert_eq!(tree.child_index[parent_id.index].len(), children.len());
+        for child_id in children {
+            let key = tree.key(child_id).unwrap();
+            assert_eq!(tree.child_by_key(parent_id, key).unwrap(), Some(child_id));
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut tree = KeyedTree::new();
+
+        let root_id = tree.insert("root", Node::new(0), AsRoot).unwrap();
+        let a_id = tree.insert("a", Node::new(1), UnderNode(&root_id)).unwrap();
+        let b_id = tree.insert("b", Node::new(2), UnderNode(&root_id)).unwrap();
+        // same key under a different parent is fine
+        let aa_id = tree.insert("a", Node::new(3), UnderNode(&a_id)).unwrap();
+
+        assert_eq!(tree.child_by_key(&root_id, "a").unwrap(), Some(&a_id));
+        assert_eq!(tree.child_by_key(&root_id, "b").unwrap(), Some(&b_id));
+        assert_eq!(tree.child_by_key(&a_id, "a").unwrap(), Some(&aa_id));
+
+        let result = tree.insert("b", Node::new(4), UnderNode(&root_id));
+        assert_eq!(result, Err(KeyedTreeError::DuplicateSiblingKey));
+        assert_eq!(tree.get(&root_id).unwrap().children().len(), 2);
+
+        let new_root_id = tree.insert("new", Node::new(5), AsRoot).unwrap();
+        assert_eq!(
+            tree.child_by_key(&new_root_id, "root").unwrap(),
+            Some(&root_id)
+        );
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let mut tree = KeyedTree::new();
+
+        let root_id = tree.insert("root", Node::new(0), AsRoot).unwrap();
+        let a_id = tree.insert("a", Node::new(1), UnderNode(&root_id)).unwrap();
+        let b_id = tree.insert("b", Node::new(2), UnderNode(&a_id)).unwrap();
+        let c_id = tree.insert("c", Node::new(3), UnderNode(&b_id)).unwrap();
+        tree.insert("c", Node::new(4), UnderNode(&root_id)).unwrap();
+
+        let (key, node) = tree.remove_node(b_id.clone(), OrphanChildren).unwrap();
+        assert_eq!(key, "b");
+        assert_eq!(node.data(), &2);
+        assert!(tree.child_by_key(&a_id, "b").unwrap().is_none());
+        assert_eq!(tree.key(&c_id).unwrap(), &"c");
+
+        // the freed slot is reused with a fresh index
+        let d_id = tree.insert("d", Node::new(5), UnderNode(&a_id)).unwrap();
+        assert_eq!(tree.child_by_key(&d_id, "c").unwrap(), None);
+        tree.move_node(&c_id, ToParent(&d_id)).unwrap();
+
+        let result = tree.remove_node(a_id.clone(), LiftChildren);
+        assert!(result.is_ok());
+        assert_eq!(tree.child_by_key(&root_id, "d").unwrap(), Some(&d_id));
+        assert_index_matches(&tree, &root_id);
+
+        // "d"'s child "c" would collide with the root's child "c"
+        let result = tree.remove_node(d_id.clone(), LiftChildren);
+        assert_eq!(result.err(), Some(KeyedTreeError::DuplicateSiblingKey));
+
+        tree.remove_node(d_id.clone(), DropChildren).unwrap();
+        assert!(tree.key(&c_id).is_err());
+        assert_index_matches(&tree, &root_id);
+    }
+
+    #[test]
+    fn test_move_node() {
+        let mut tree = KeyedTree::new();
+
+        let root_id = tree.insert("root", Node::new(0), AsRoot).unwrap();
+        let a_id = tree.insert("a", Node::new(1), UnderNode(&root_id)).unwrap();
+        let b_id = tree.insert("b", Node::new(2), UnderNode(&a_id)).unwrap();
+        let c_id = tree.insert("b", Node::new(3), UnderNode(&root_id)).unwrap();
+
+        // "a" would get two children called "b"
+        let result = tree.move_node(&c_id, ToParent(&a_id));
+        assert_eq!(result, Err(KeyedTreeError::DuplicateSiblingKey));
+
+        // moving "a" under its own child lifts "b" into the root, next to the other "b"
+        let result = tree.move_node(&a_id, ToParent(&b_id));
+        assert_eq!(result, Err(KeyedTreeError::DuplicateSiblingKey));
+
+        tree.move_node(&c_id, ToParent(&b_id)).unwrap();
+        tree.move_node(&a_id, ToParent(&c_id)).unwrap();
+        assert_eq!(tree.child_by_key(&root_id, "b").unwrap(), Some(&b_id));
+        assert_eq!(tree.child_by_key(&b_id, "b").unwrap(), Some(&c_id));
+        assert_eq!(tree.child_by_key(&c_id, "a").unwrap(), Some(&a_id));
+
+        tree.move_node(&a_id, ToRoot).unwrap();
+        assert_eq!(tree.child_by_key(&a_id, "root").unwrap(), Some(&root_id));
+        assert!(tree.child_by_key(&c_id, "a").unwrap().is_none());
+        assert_index_matches(&tree, &a_id);
+        assert_index_matches(&tree, &root_id);
+        assert_index_matches(&tree, &b_id);
+        assert_index_matches(&tree, &c_id);
+    }
+
+    #[test]
+    fn test_swap_nodes() {
+        let mut tree = KeyedTree::new();
+
+        let root_id = tree.insert("root", Node::new(0), AsRoot).unwrap();
+        let a_id = tree.insert("a", Node::new(1), UnderNode(&root_id)).unwrap();
+        let b_id = tree.insert("b", Node::new(2), UnderNode(&root_id)).unwrap();
+        let x_id = tree.insert("x", Node::new(3), UnderNode(&a_id)).unwrap();
+        let bx_id = tree.insert("x", Node::new(4), UnderNode(&b_id)).unwrap();
+        let y_id = tree.insert("y", Node::new(5), UnderNode(&b_id)).unwrap();
+
+        let result = tree.swap_nodes(&x_id, &y_id, TakeChildren);
+        assert_eq!(result, Err(KeyedTreeError::DuplicateSiblingKey));
+        let result = tree.swap_nodes(&x_id, &y_id, LeaveChildren);
+        assert_eq!(result, Err(KeyedTreeError::DuplicateSiblingKey));
+
+        tree.swap_nodes(&x_id, &bx_id, TakeChildren).unwrap();
+        assert_eq!(tree.child_by_key(&a_id, "x").unwrap(), Some(&bx_id));
+        assert_eq!(tree.child_by_key(&b_id, "x").unwrap(), Some(&x_id));
+        assert_eq!(tree.child_by_key(&b_id, "y").unwrap(), Some(&y_id));
+
+        tree.swap_nodes(&a_id, &b_id, ChildrenOnly).unwrap();
+        assert_eq!(tree.child_by_key(&a_id, "x").unwrap(), Some(&x_id));
+        assert_eq!(tree.child_by_key(&a_id, "y").unwrap(), Some(&y_id));
+        assert_eq!(tree.child_by_key(&b_id, "x").unwrap(), Some(&bx_id));
+        assert_eq!(tree.child_by_key(&b_id, "y").unwrap(), None);
+
+        // "root" is moved under "a", which has no child called "root"
+        tree.swap_nodes(&root_id, &a_id, TakeChildren).unwrap();
+        assert_eq!(tree.root_node_id(), Some(&a_id));
+        assert_eq!(tree.child_by_key(&a_id, "root").unwrap(), Some(&root_id));
+        assert_index_matches(&tree, &a_id);
+        assert_index_matches(&tree, &b_id);
+        assert_index_matches(&tree, &root_id);
+    }
+
+    #[test]
+    fn test_make_nth_sibling() {
+        let mut tree = KeyedTree::new();
+
+        let root_id = tree.insert("root", Node::new(0), AsRoot).unwrap();
+        let a_id = tree.insert("a", Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.insert("b", Node::new(2), UnderNode(&root_id)).unwrap();
+        tree.insert("c", Node::new(3), UnderNode(&root_id)).unwrap();
+
+        tree.make_nth_sibling(&a_id, 2).unwrap();
+
+        let keys: Vec<&str> = tree
+            .get(&root_id)
+            .unwrap()
+            .children()
+            .iter()
+            .map(|id| *tree.key(id).unwrap())
+            .collect();
+        assert_eq!(keys, vec!["b", "c", "a"]);
+        assert_eq!(tree.child_by_key(&root_id, "a").unwrap(), Some(&a_id));
+    }
+}
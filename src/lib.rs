@@ -65,6 +65,12 @@
 //! attempts to solve them.
 //!
 
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+
+#[cfg(feature = "proptest")]
+extern crate proptest;
+
 #[cfg(feature = "serde_support")]
 extern crate serde;
 
@@ -75,29 +81,94 @@ extern crate serde_derive;
 extern crate snowflake;
 use self::snowflake::ProcessUniqueId;
 
+#[macro_use]
+mod macros;
+
+mod augmented_tree;
 mod behaviors;
+mod cursor;
+mod diff;
+mod dot;
 mod error;
+mod fold;
+mod formatter;
+#[cfg(feature = "fs")]
+mod fs;
 mod iterators;
+mod keyed_tree;
+mod newick;
 mod node;
+mod node_map;
+mod observers;
+mod parent_list;
+mod paths;
+mod recorded_tree;
+#[cfg(feature = "serde_support")]
+pub mod serde_nested;
+mod sexpr;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(test)]
+mod test_fixtures;
+mod transaction;
 mod tree;
 
+pub use augmented_tree::Aggregate;
+pub use augmented_tree::AugmentedTree;
+pub use augmented_tree::DataMut;
 pub use behaviors::InsertBehavior;
 pub use behaviors::MoveBehavior;
 pub use behaviors::RemoveBehavior;
 pub use behaviors::SwapBehavior;
+pub use diff::TreeDiff;
+pub use diff::TreeEdit;
+pub use dot::DotOptions;
+pub use error::BuildError;
+#[cfg(feature = "fs")]
+pub use error::EntryError;
+pub use error::InvariantViolation;
+pub use error::KeyedTreeError;
 pub use error::NodeIdError;
+pub use error::ParseError;
+pub use error::ParseErrorKind;
+pub use error::WriteError;
+pub use formatter::GuideStyle;
+pub use formatter::TreeFormatter;
+#[cfg(feature = "fs")]
+pub use fs::DirectoryOptions;
+#[cfg(feature = "fs")]
+pub use fs::EntryKind;
+#[cfg(feature = "fs")]
+pub use fs::PathEntry;
 pub use iterators::AncestorIds;
 pub use iterators::Ancestors;
 pub use iterators::Children;
 pub use iterators::ChildrenIds;
+pub use iterators::DrainSubtree;
+pub use iterators::IntoIter;
+pub use iterators::IntoLevelOrder;
+pub use iterators::IntoPostOrder;
+pub use iterators::IntoPreOrder;
+pub use iterators::Iter;
 pub use iterators::LevelOrderTraversal;
 pub use iterators::LevelOrderTraversalIds;
+pub use iterators::ParentList;
 pub use iterators::PostOrderTraversal;
 pub use iterators::PostOrderTraversalIds;
 pub use iterators::PreOrderTraversal;
 pub use iterators::PreOrderTraversalIds;
+pub use keyed_tree::KeyedTree;
+pub use newick::NewickNode;
 pub use node::Node;
 pub use node::NodeBuilder;
+pub use node_map::NodeMap;
+pub use node_map::NodeMapIter;
+pub use node_map::NodeMapIterMut;
+pub use observers::ObserverId;
+pub use observers::Placement;
+pub use observers::TreeEvent;
+pub use recorded_tree::RecordedTree;
+pub use transaction::Transaction;
 pub use tree::Tree;
 pub use tree::TreeBuilder;
 
@@ -117,39 +188,38 @@ pub use tree::TreeBuilder;
 /// Because `Tree`s pass out `NodeId`s as `Node`s are inserted, several issues can occur:
 ///
 /// 1. If a `Node` is removed, the `NodeId` that previously identified it now points to nothing
-/// (technically a `None` value in this case).
-/// 2. If a `Node` is removed and then another is inserted later, the "new" `NodeId` that is
-/// returned can (and will) be the same `NodeId` that was used to identify a different `Node`
-/// previously.
+///    (technically a `None` value in this case).
+/// 2. If a `Node` is removed and then another is inserted later, the "new" `Node` can (and will)
+///    take over the storage slot that the old `NodeId` points to.
 ///
 /// The above issues may seem like deal-breakers, but our situation isn't as bad as it seems:
 ///
-/// The first issue can be easily detected by the library itself.  In this situation, a
-/// `Result::Err` will be returned with the appropriate `NodeIdError`. The second issue, however, is
-/// not something that the library can detect. To mitigate this problem, this library ensures the
+/// Both issues are detected by the library itself.  Every slot in a `Tree` keeps a generation
+/// that goes up whenever its `Node` is removed (never repeating itself, even when a removal is
+/// undone), and each `NodeId` remembers the generation it was handed out with.  Using the `NodeId` of a removed `Node` (even after another `Node` has taken
+/// its place) returns a `Result::Err` with `NodeIdError::NodeIdNoLongerValid` instead of reaching
+/// the wrong `Node`.  `NodeMap`s use the same generations to tell their entries apart.
+///
+/// To keep stray `NodeId`s to a minimum in the first place, this library also ensures the
 /// following:
 ///
 /// 1. All `Node` methods that provide `NodeId`s will **return** `&NodeId`s instead of `NodeId`s.
 /// 2. All `Tree` methods that **read** or **insert** data accept `&NodeId`s instead of taking
-/// `NodeId`s.
+///    `NodeId`s.
 /// 3. All `Tree` methods that **remove** data take `NodeId`s instead of accepting `&NodeId`s.
 /// 4. All `Node`s that have been removed from a `Tree` will have their parent and child references
-/// cleared (to avoid leaking extra `NodeId` copies).
+///    cleared (to avoid leaking extra `NodeId` copies).
 /// 5. `NodeId`s themselves are `Clone`, but not `Copy`.
 ///
 /// This means that no methods will ever take ownership of a `NodeId` except for methods that remove
-/// a `Node` from a `Tree`. The resulting behavior is that unless the caller **explicitly `Clone`s a
-/// `NodeId`** they should never be in a situation where they accidentally hold onto a `NodeId` too
-/// long.  This means that we have "almost safe references" that the caller can clone if they choose
-/// to.  Doing so, however, will open up the possibility of confusing which `NodeId`s refer to which
-/// `Node`s in the calling context.
-///
-/// This _does_ transfer some of the burden to the caller, but any errors should be fairly easy to
-/// sort out because an explicit `Clone` is required for such an error to occur.
+/// a `Node` from a `Tree`, so unless the caller **explicitly `Clone`s a `NodeId`** they should
+/// never be holding onto one that has gone stale.
 ///
 #[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct NodeId {
     tree_id: ProcessUniqueId,
     index: usize,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    generation: u32,
 }
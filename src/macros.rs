@@ -0,0 +1,144 @@
+///
+/// Builds a `Tree` from a nested literal.
+///
+/// Every `Node` is written as its data, followed by `=> { ... }` with its comma-separated children
+/// if it has any.  `tree!()` gives an empty `Tree`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate id_tree;
+///
+/// use id_tree::Tree;
+///
+/// # fn main() {
+/// //      0
+/// //     / \
+/// //    1   2
+/// //   / \
+/// //  3   4
+/// let tree: Tree<i32> = tree! { 0 => { 1 => { 3, 4 }, 2 } };
+///
+/// let root_id = tree.root_node_id().unwrap();
+/// let pre_order: Vec<i32> = tree
+///     .traverse_pre_order(root_id)
+///     .unwrap()
+///     .map(|node| *node.data())
+///     .collect();
+/// assert_eq!(pre_order, vec![0, 1, 3, 4, 2]);
+/// # }
+/// ```
+///
+/// Starting with `let` (or `let mut`) turns the macro into a statement that binds the `Tree` to
+/// the given name.  Any `Node` written as `name: data` then also has its `NodeId` bound to `name`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate id_tree;
+///
+/// use id_tree::InsertBehavior::*;
+/// use id_tree::Node;
+///
+/// # fn main() {
+/// tree! {
+///     let mut tree = root: "/" => {
+///         usr: "usr" => { "bin", "lib" },
+///         "etc",
+///     }
+/// };
+///
+/// tree.insert(Node::new("share"), UnderNode(&usr)).unwrap();
+///
+/// assert_eq!(tree.children(&root).unwrap().count(), 2);
+/// assert_eq!(tree.children(&usr).unwrap().count(), 3);
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! tree {
+    () => {
+        $crate::Tree::new()
+    };
+
+    (let mut $tree:ident = $($node:tt)+) => {
+        let mut tree = $crate::Tree::new();
+        $crate::tree!(@root tree; $($node)+);
+        let mut $tree = tree;
+    };
+    (let $tree:ident = $($node:tt)+) => {
+        let mut tree = $crate::Tree::new();
+        $crate::tree!(@root tree; $($node)+);
+        let $tree = tree;
+    };
+
+    (@root $tree:ident; $name:ident : $data:expr => { $($children:tt)* }) => {
+        $crate::tree!(@insert $tree, $name, $data, $crate::InsertBehavior::AsRoot);
+        $crate::tree!(@children $tree, $name; $($children)*);
+    };
+    (@root $tree:ident; $name:ident : $data:expr) => {
+        $crate::tree!(@insert $tree, $name, $data, $crate::InsertBehavior::AsRoot);
+    };
+    (@root $tree:ident; $data:expr => { $($children:tt)* }) => {
+        $crate::tree!(@insert $tree, node_id, $data, $crate::InsertBehavior::AsRoot);
+        $crate::tree!(@children $tree, node_id; $($children)*);
+    };
+    (@root $tree:ident; $data:expr) => {
+        $crate::tree!(@insert $tree, node_id, $data, $crate::InsertBehavior::AsRoot);
+    };
+
+    // lists of only named or only unnamed children are expanded all at once, so that wide Nodes
+    // do not run into the recursion limit (each unnamed child goes through @child to get its own
+    // `node_id`)
+    (@children $tree:ident, $parent:ident;
+        $($name:ident : $data:expr $(=> { $($children:tt)* })?),* $(,)?
+    ) => {
+        $(
+            $crate::tree!(@insert $tree, $name, $data, $crate::InsertBehavior::UnderNode(&$parent));
+            $crate::tree!(@children $tree, $name; $($($children)*)?);
+        )*
+    };
+    (@children $tree:ident, $parent:ident; $($data:expr $(=> { $($children:tt)* })?),* $(,)?) => {
+        $(
+            $crate::tree!(@child $tree, $parent; $data => { $($($children)*)? });
+        )*
+    };
+    // mixed lists are taken apart one child at a time until the rest is one or the other
+    (@children $tree:ident, $parent:ident;
+        $name:ident : $data:expr => { $($children:tt)* } , $($rest:tt)+
+    ) => {
+        $crate::tree!(@insert $tree, $name, $data, $crate::InsertBehavior::UnderNode(&$parent));
+        $crate::tree!(@children $tree, $name; $($children)*);
+        $crate::tree!(@children $tree, $parent; $($rest)+);
+    };
+    (@children $tree:ident, $parent:ident; $name:ident : $data:expr , $($rest:tt)+) => {
+        $crate::tree!(@insert $tree, $name, $data, $crate::InsertBehavior::UnderNode(&$parent));
+        $crate::tree!(@children $tree, $parent; $($rest)+);
+    };
+    (@children $tree:ident, $parent:ident; $data:expr => { $($children:tt)* } , $($rest:tt)+) => {
+        $crate::tree!(@child $tree, $parent; $data => { $($children)* });
+        $crate::tree!(@children $tree, $parent; $($rest)+);
+    };
+    (@children $tree:ident, $parent:ident; $data:expr , $($rest:tt)+) => {
+        $crate::tree!(@child $tree, $parent; $data => {});
+        $crate::tree!(@children $tree, $parent; $($rest)+);
+    };
+
+    (@child $tree:ident, $parent:ident; $data:expr => { $($children:tt)* }) => {
+        $crate::tree!(@insert $tree, node_id, $data, $crate::InsertBehavior::UnderNode(&$parent));
+        $crate::tree!(@children $tree, node_id; $($children)*);
+    };
+
+    (@insert $tree:ident, $name:ident, $data:expr, $behavior:expr) => {
+        #[allow(unused_variables)]
+        let $name = $tree.insert($crate::Node::new($data), $behavior).unwrap();
+    };
+
+    (@ $($tokens:tt)*) => {
+        compile_error!("tree!: expected `data`, `data => { children }` or `name: data` for every Node")
+    };
+
+    ($($node:tt)+) => {{
+        let mut tree = $crate::Tree::new();
+        $crate::tree!(@root tree; $($node)+);
+        tree
+    }};
+}
@@ -0,0 +1,541 @@
+use std::convert::Infallible;
+use std::fmt;
+
+use cursor::Cursor;
+use InsertBehavior::*;
+use Node;
+use NodeId;
+use ParseError;
+use ParseErrorKind;
+use Tree;
+
+///
+/// The data of a `Node` in a Newick `Tree`: an optional name and an optional length of the branch
+/// leading to it.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NewickNode {
+    /// The name of the `Node`, with quotes removed and underscores in unquoted names turned into
+    /// spaces.
+    pub name: Option<String>,
+    /// The length of the branch from the parent to this `Node`.
+    pub length: Option<f64>,
+}
+
+impl NewickNode {
+    ///
+    /// Creates a new `NewickNode` with the given name and branch length.
+    ///
+    /// ```
+    /// use id_tree::NewickNode;
+    ///
+    /// let node = NewickNode::new(Some("A"), Some(0.5));
+    /// assert_eq!(node.name, Some("A".to_string()));
+    /// assert_eq!(node.length, Some(0.5));
+    /// ```
+    ///
+    pub fn new(name: Option<&str>, length: Option<f64>) -> NewickNode {
+        NewickNode {
+            name: name.map(|name| name.to_string()),
+            length,
+        }
+    }
+}
+
+impl Tree<NewickNode> {
+    ///
+    /// Parses a single `Tree` in Newick format, such as `((A:0.1,B:0.2)C:0.3,D);`.
+    ///
+    /// Names may be quoted with single quotes (with `''` standing for a quote inside them), and
+    /// comments in square brackets are skipped.  The input `;` gives an empty `Tree`.
+    ///
+    /// Parsing does not recurse, so arbitrarily deep nesting is fine.  Returns a `ParseError`
+    /// with the line and column of the first problem in the input.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree = Tree::from_newick("(A:0.1,'B c':0.2[comment])root;").unwrap();
+    ///
+    /// let root_id = tree.root_node_id().unwrap();
+    /// assert_eq!(tree.get(root_id).unwrap().data().name, Some("root".to_string()));
+    ///
+    /// let names: Vec<_> = tree
+    ///     .children(root_id)
+    ///     .unwrap()
+    ///     .map(|node| node.data().name.clone().unwrap())
+    ///     .collect();
+    /// assert_eq!(names, vec!["A", "B c"]);
+    /// ```
+    ///
+    pub fn from_newick(input: &str) -> Result<Tree<NewickNode>, ParseError<Infallible>> {
+        Tree::from_newick_with(input, Ok)
+    }
+
+    ///
+    /// Writes the `Tree` in Newick format.  See `Tree::write_newick_with`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree = Tree::from_newick("((A:0.1, B_b:0.2)C, [comment] D);").unwrap();
+    ///
+    /// let mut s = String::new();
+    /// tree.write_newick(&mut s).unwrap();
+    /// assert_eq!(&s, "((A:0.1,'B b':0.2)C,D);");
+    /// ```
+    ///
+    pub fn write_newick<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        self.write_newick_with(w, |data| data.clone())
+    }
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Parses a single `Tree` in Newick format like `Tree::from_newick`, turning the
+    /// `NewickNode` of each `Node` into its data with the given closure.
+    ///
+    /// The closure is called in pre-order.  If it fails, the returned `ParseError` points at the
+    /// name (or where the name would be) of the `Node` in question.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree = Tree::from_newick_with("(A:1,B:2.5)", |node| {
+    ///     Ok::<_, ()>(node.length.unwrap_or(0.0))
+    /// });
+    /// assert_eq!(tree.unwrap_err().kind(), &ParseErrorKind::UnexpectedEnd);
+    ///
+    /// let tree = Tree::from_newick_with("(A:1,B:2.5);", |node| {
+    ///     node.length.ok_or("every branch needs a length")
+    /// });
+    /// assert_eq!(tree.unwrap_err().kind(), &ParseErrorKind::Label("every branch needs a length"));
+    /// ```
+    ///
+    pub fn from_newick_with<E, F>(input: &str, mut f: F) -> Result<Tree<T>, ParseError<E>>
+    where
+        F: FnMut(NewickNode) -> Result<T, E>,
+    {
+        let parsed = parse(input)?;
+
+        let mut tree = Tree::new();
+        let mut nodes: Vec<Option<Parsed>> = parsed.into_iter().map(Some).collect();
+        let mut stack: Vec<(usize, Option<NodeId>)> = Vec::new();
+        if !nodes.is_empty() {
+            stack.push((0, None));
+        }
+        while let Some((index, parent_id)) = stack.pop() {
+            let parsed = nodes[index].take().unwrap();
+            let (line, column) = parsed.position;
+            let data = f(parsed.node)
+                .map_err(|e| ParseError::new(line, column, ParseErrorKind::Label(e)))?;
+
+            let behavior = match parent_id {
+                Some(ref parent_id) => UnderNode(parent_id),
+                None => AsRoot,
+            };
+            let node_id = tree
+                .insert(Node::new(data), behavior)
+                .expect("parsed parents are always in the Tree");
+            for &child in parsed.children.iter().rev() {
+                stack.push((child, Some(node_id.clone())));
+            }
+        }
+
+        Ok(tree)
+    }
+
+    ///
+    /// Writes the `Tree` in Newick format, turning the data of each `Node` into a `NewickNode`
+    /// with the given closure.  Orphaned `Node`s are left out and an empty `Tree` is written as
+    /// `;`.
+    ///
+    /// Names are quoted when they are empty or contain whitespace, underscores or any of the
+    /// characters Newick reserves.  Writing does not recurse.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<(&str, f64)> = Tree::new();
+    /// let root_id = tree.insert(Node::new(("root", 0.0)), AsRoot).unwrap();
+    /// tree.insert(Node::new(("it's", 1.5)), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(("b", 2.0)), UnderNode(&root_id)).unwrap();
+    ///
+    /// let mut s = String::new();
+    /// tree.write_newick_with(&mut s, |&(name, length)| {
+    ///     NewickNode::new(Some(name), Some(length).filter(|&length| length > 0.0))
+    /// })
+    /// .unwrap();
+    /// assert_eq!(&s, "('it''s':1.5,b:2)root;");
+    /// ```
+    ///
+    pub fn write_newick_with<W, F>(&self, w: &mut W, f: F) -> fmt::Result
+    where
+        W: fmt::Write,
+        F: Fn(&T) -> NewickNode,
+    {
+        enum Step<'a> {
+            Open(&'a NodeId),
+            Close(&'a NodeId),
+            Comma,
+        }
+
+        let mut stack = Vec::new();
+        if let Some(root_id) = self.root_node_id() {
+            stack.push(Step::Open(root_id));
+        }
+
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Open(node_id) => {
+                    let node = self.get_unsafe(node_id);
+                    if node.children().is_empty() {
+                        write_label(w, &f(node.data()))?;
+                        continue;
+                    }
+
+                    w.write_char('(')?;
+                    stack.push(Step::Close(node_id));
+                    for (i, child_id) in node.children().iter().enumerate().rev() {
+                        stack.push(Step::Open(child_id));
+                        if i > 0 {
+                            stack.push(Step::Comma);
+                        }
+                    }
+                }
+                Step::Close(node_id) => {
+                    w.write_char(')')?;
+                    write_label(w, &f(self.get_unsafe(node_id).data()))?;
+                }
+                Step::Comma => w.write_char(',')?,
+            }
+        }
+
+        w.write_char(';')
+    }
+}
+
+///
+/// A `Node` that has been parsed but not inserted into a `Tree` yet.
+///
+struct Parsed {
+    node: NewickNode,
+    children: Vec<usize>,
+    position: (usize, usize),
+}
+
+///
+/// Parses the input into a flat list of `Node`s where the first one is the root.
+///
+fn parse<E>(input: &str) -> Result<Vec<Parsed>, ParseError<E>> {
+    let mut cursor = Cursor::new(input);
+    let mut nodes: Vec<Parsed> = Vec::new();
+    // the internal nodes whose closing parenthesis has not been seen yet
+    let mut open: Vec<usize> = Vec::new();
+
+    skip_blank(&mut cursor)?;
+    if cursor.peek() == Some(';') {
+        cursor.next();
+    } else {
+        loop {
+            // a subtree starts here, either internal or a leaf
+            let current = nodes.len();
+            nodes.push(Parsed {
+                node: NewickNode::default(),
+                children: Vec::new(),
+                position: cursor.position(),
+            });
+            if let Some(&parent) = open.last() {
+                nodes[parent].children.push(current);
+            }
+
+            if cursor.peek() == Some('(') {
+                cursor.next();
+                open.push(current);
+                skip_blank(&mut cursor)?;
+                continue;
+            }
+            parse_label(&mut cursor, &mut nodes[current])?;
+
+            // after a subtree: close as many internal nodes as there are parentheses
+            loop {
+                match cursor.peek() {
+                    Some(')') if !open.is_empty() => {
+                        cursor.next();
+                        let closed = open.pop().unwrap();
+                        skip_blank(&mut cursor)?;
+                        parse_label(&mut cursor, &mut nodes[closed])?;
+                    }
+                    _ => break,
+                }
+            }
+
+            match cursor.peek() {
+                Some(',') if !open.is_empty() => {
+                    cursor.next();
+                    skip_blank(&mut cursor)?;
+                }
+                Some(';') if open.is_empty() => {
+                    cursor.next();
+                    break;
+                }
+                Some(_) => return Err(cursor.error(ParseErrorKind::UnexpectedCharacter)),
+                None => return Err(cursor.error(ParseErrorKind::UnexpectedEnd)),
+            }
+        }
+    }
+
+    skip_blank(&mut cursor)?;
+    if cursor.peek().is_some() {
+        return Err(cursor.error(ParseErrorKind::UnexpectedCharacter));
+    }
+    Ok(nodes)
+}
+
+///
+/// Parses the optional name and branch length of a `Node`, as well as the blank space after them.
+///
+fn parse_label<E>(cursor: &mut Cursor, parsed: &mut Parsed) -> Result<(), ParseError<E>> {
+    parsed.position = cursor.position();
+
+    if cursor.peek() == Some('\'') {
+        let start = cursor.error(ParseErrorKind::UnexpectedEnd);
+        cursor.next();
+        let mut name = String::new();
+        loop {
+            match cursor.next() {
+                Some('\'') if cursor.peek() == Some('\'') => {
+                    cursor.next();
+                    name.push('\'');
+                }
+                Some('\'') => break,
+                Some(c) => name.push(c),
+                None => return Err(start),
+            }
+        }
+        parsed.node.name = Some(name);
+    } else {
+        let name: String = cursor
+            .take_while(is_word_char)
+            .chars()
+            .map(|c| if c == '_' { ' ' } else { c })
+            .collect();
+        if !name.is_empty() {
+            parsed.node.name = Some(name);
+        }
+    }
+    skip_blank(cursor)?;
+
+    if cursor.peek() == Some(':') {
+        cursor.next();
+        skip_blank(cursor)?;
+        let invalid = cursor.error(ParseErrorKind::InvalidLength);
+        parsed.node.length = Some(
+            cursor
+                .take_while(is_word_char)
+                .parse()
+                .map_err(|_| invalid)?,
+        );
+        skip_blank(cursor)?;
+    }
+
+    Ok(())
+}
+
+///
+/// Whether a character can appear in an unquoted name or a branch length.
+///
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"()[]':;,".contains(c)
+}
+
+///
+/// Skips whitespace and comments.
+///
+fn skip_blank<E>(cursor: &mut Cursor) -> Result<(), ParseError<E>> {
+    loop {
+        match cursor.peek() {
+            Some(c) if c.is_whitespace() => {
+                cursor.next();
+            }
+            Some('[') => {
+                let start = cursor.error(ParseErrorKind::UnexpectedEnd);
+                while cursor.next() != Some(']') {
+                    if cursor.peek().is_none() {
+                        return Err(start);
+                    }
+                }
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+///
+/// Writes the name and branch length of a `Node`, quoting the name if needed.
+///
+fn write_label<W: fmt::Write>(w: &mut W, node: &NewickNode) -> fmt::Result {
+    if let Some(ref name) = node.name {
+        if !name.is_empty() && name.chars().all(|c| is_word_char(c) && c != '_') {
+            w.write_str(name)?;
+        } else {
+            w.write_char('\'')?;
+            w.write_str(&name.replace('\'', "''"))?;
+            w.write_char('\'')?;
+        }
+    }
+    if let Some(length) = node.length {
+        write!(w, ":{}", length)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod newick_tests {
+    use super::super::*;
+    use std::convert::Infallible;
+
+    fn names(tree: &Tree<NewickNode>) -> Vec<String> {
+        tree.traverse_pre_order(tree.root_node_id().unwrap())
+            .unwrap()
+            .map(|node| node.data().name.clone().unwrap_or_default())
+            .collect()
+    }
+
+    fn round_trip(input: &str) -> String {
+        let mut s = String::new();
+        Tree::from_newick(input)
+            .unwrap()
+            .write_newick(&mut s)
+            .unwrap();
+        s
+    }
+
+    fn error(input: &str) -> (usize, usize, ParseErrorKind<Infallible>) {
+        let error = Tree::from_newick(input).unwrap_err();
+        (error.line(), error.column(), error.into_kind())
+    }
+
+    #[test]
+    fn test_parse() {
+        let tree = Tree::from_newick("((A,B)C,(D:1,E:2.5e-1)F:3)G;").unwrap();
+        assert_eq!(names(&tree), vec!["G", "C", "A", "B", "F", "D", "E"]);
+        assert!(tree.validate().is_ok());
+
+        let lengths: Vec<_> = tree
+            .traverse_pre_order(tree.root_node_id().unwrap())
+            .unwrap()
+            .map(|node| node.data().length)
+            .collect();
+        assert_eq!(
+            lengths,
+            vec![None, None, None, None, Some(3.0), Some(1.0), Some(0.25)]
+        );
+
+        let unnamed = Tree::from_newick("(,(,));").unwrap();
+        assert_eq!(unnamed.height(), 3);
+        assert_eq!(names(&unnamed), vec![""; 5]);
+
+        let leaf = Tree::from_newick("A;").unwrap();
+        assert_eq!(names(&leaf), vec!["A"]);
+
+        assert!(Tree::from_newick(" ; ").unwrap().root_node_id().is_none());
+    }
+
+    #[test]
+    fn test_quotes_comments_and_whitespace() {
+        let input = "[&R] ( 'it''s (a)' : 1 [x], Homo_sapiens\n[multi\nline] )\t'' ;\n";
+        let tree = Tree::from_newick(input).unwrap();
+        assert_eq!(names(&tree), vec!["", "it's (a)", "Homo sapiens"]);
+        assert_eq!(
+            tree.get(tree.root_node_id().unwrap()).unwrap().data().name,
+            Some(String::new())
+        );
+
+        assert_eq!(round_trip(input), "('it''s (a)':1,'Homo sapiens')'';");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for input in &[
+            ";",
+            "A;",
+            "(A,B);",
+            "((A:0.1,B:0.2)C:0.3,(D,(E,F)G)H)I;",
+            "(,(,));",
+            "('a b':1e-7,'x_y','[c]');",
+        ] {
+            let written = round_trip(input);
+            assert_eq!(round_trip(&written), written);
+        }
+        assert_eq!(
+            round_trip("((A:0.1,B:0.2)C:0.3,D);"),
+            "((A:0.1,B:0.2)C:0.3,D);"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(error(""), (1, 1, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(error("(A,B)"), (1, 6, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(error("(A,B;"), (1, 5, ParseErrorKind::UnexpectedCharacter));
+        assert_eq!(error("A,B;"), (1, 2, ParseErrorKind::UnexpectedCharacter));
+        assert_eq!(error("(A)B);"), (1, 5, ParseErrorKind::UnexpectedCharacter));
+        assert_eq!(error("(A B);"), (1, 4, ParseErrorKind::UnexpectedCharacter));
+        assert_eq!(error("A;\nB;"), (2, 1, ParseErrorKind::UnexpectedCharacter));
+        assert_eq!(error("(A:x,B);"), (1, 4, ParseErrorKind::InvalidLength));
+        assert_eq!(error("(A:,B);"), (1, 4, ParseErrorKind::InvalidLength));
+        assert_eq!(error("(A,\n 'B);"), (2, 2, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(error("(A [B);"), (1, 4, ParseErrorKind::UnexpectedEnd));
+    }
+
+    #[test]
+    fn test_with_closures() {
+        let tree = Tree::from_newick_with("(A:1,(B:2,C:3):4);", |node| {
+            Ok::<_, ()>(node.length.unwrap_or(0.0))
+        })
+        .unwrap();
+        let total: f64 = tree
+            .traverse_pre_order(tree.root_node_id().unwrap())
+            .unwrap()
+            .map(|node| *node.data())
+            .sum();
+        assert_eq!(total, 10.0);
+
+        let mut s = String::new();
+        tree.write_newick_with(&mut s, |&length| NewickNode::new(None, Some(length)))
+            .unwrap();
+        assert_eq!(s, "(:1,(:2,:3):4):0;");
+
+        let error = Tree::from_newick_with("(A,\n  (B,bad));", |node| match node.name {
+            Some(ref name) if name == "bad" => Err(name.clone()),
+            _ => Ok(()),
+        })
+        .unwrap_err();
+        assert_eq!((error.line(), error.column()), (2, 6));
+        assert_eq!(error.kind(), &ParseErrorKind::Label("bad".to_string()));
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        // validate_mutations checks the whole Tree after every insert
+        let depth = if cfg!(feature = "validate_mutations") {
+            2_000
+        } else {
+            100_000
+        };
+        let input = format!("{}A{};", "(".repeat(depth), ")".repeat(depth));
+
+        let tree = Tree::from_newick(&input).unwrap();
+        let root_id = tree.root_node_id().unwrap();
+        assert_eq!(
+            tree.traverse_pre_order_ids(root_id).unwrap().count(),
+            depth + 1
+        );
+
+        let mut s = String::new();
+        tree.write_newick(&mut s).unwrap();
+        assert_eq!(s, input);
+    }
+}
@@ -20,7 +20,7 @@ impl<T> NodeBuilder<T> {
     ///
     pub fn new(data: T) -> NodeBuilder<T> {
         NodeBuilder {
-            data: data,
+            data,
             child_capacity: 0,
         }
     }
@@ -290,6 +290,7 @@ mod node_tests {
         let parent_id: NodeId = NodeId {
             tree_id: ProcessUniqueId::new(),
             index: 0,
+            generation: 0,
         };
 
         node.set_parent(Some(parent_id.clone()));
@@ -306,11 +307,12 @@ mod node_tests {
         let child_id: NodeId = NodeId {
             tree_id: ProcessUniqueId::new(),
             index: 0,
+            generation: 0,
         };
         node.add_child(child_id.clone());
 
         assert_eq!(node.children().len(), 1);
-        assert_eq!(node.children().get(0).unwrap(), &child_id);
+        assert_eq!(node.children().first().unwrap(), &child_id);
 
         let mut node = Node::new(5);
         assert_eq!(node.children().len(), 0);
@@ -318,11 +320,12 @@ mod node_tests {
         let child_id: NodeId = NodeId {
             tree_id: ProcessUniqueId::new(),
             index: 0,
+            generation: 0,
         };
         node.children_mut().push(child_id.clone());
 
         assert_eq!(node.children().len(), 1);
-        assert_eq!(node.children().get(0).unwrap(), &child_id);
+        assert_eq!(node.children().first().unwrap(), &child_id);
     }
 
     #[test]
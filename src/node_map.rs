@@ -0,0 +1,501 @@
+use std::iter::Enumerate;
+use std::ops::Index;
+use std::ops::IndexMut;
+use std::slice;
+
+use super::snowflake::ProcessUniqueId;
+use NodeId;
+use NodeIdError;
+use Tree;
+
+///
+/// A map from the `NodeId`s of one `Tree` to values, for attaching extra data to `Node`s without
+/// changing the `Tree` itself.
+///
+/// Values are stored by the slot each `NodeId` points to instead of being hashed.  Along with
+/// each value the map keeps the generation of its `NodeId`, so the value of a removed `Node` is
+/// never handed out for a `Node` that later takes over its slot.
+///
+/// Every method that takes a `NodeId` also takes the `Tree`, and treats the `NodeId`s of removed
+/// `Node`s (and of other `Tree`s) as if they had no value.  The value of a removed `Node` stays in
+/// the map, counted by `len` and visited by the iterators, until a newer `Node` in the same slot
+/// gets a value or `NodeMap::retain_valid` drops it.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+/// use id_tree::RemoveBehavior::*;
+///
+/// let mut tree: Tree<&str> = Tree::new();
+/// let root_id = tree.insert(Node::new("root"), AsRoot).unwrap();
+/// let child_id = tree.insert(Node::new("child"), UnderNode(&root_id)).unwrap();
+///
+/// let mut selected = NodeMap::new(&tree);
+/// selected.insert(&tree, &child_id, true).unwrap();
+/// assert_eq!(selected.get(&tree, &child_id), Some(&true));
+/// assert_eq!(selected.get(&tree, &root_id), None);
+///
+/// // neither the stale NodeId nor a new Node in the same slot get the value
+/// tree.remove_node(child_id.clone(), DropChildren).unwrap();
+/// let new_child_id = tree.insert(Node::new("new child"), UnderNode(&root_id)).unwrap();
+/// assert_eq!(selected.get(&tree, &child_id), None);
+/// assert_eq!(selected.get(&tree, &new_child_id), None);
+///
+/// // the value itself stays in the map until it is dropped
+/// assert_eq!(selected.len(), 1);
+/// selected.retain_valid(&tree);
+/// assert!(selected.is_empty());
+/// ```
+///
+#[derive(Clone, Debug)]
+pub struct NodeMap<V> {
+    tree_id: ProcessUniqueId,
+    entries: Vec<Option<(u32, V)>>,
+    len: usize,
+}
+
+impl<V> NodeMap<V> {
+    ///
+    /// Creates a new, empty `NodeMap` for the `NodeId`s of the given `Tree`.
+    ///
+    pub fn new<T>(tree: &Tree<T>) -> NodeMap<V> {
+        NodeMap {
+            tree_id: tree.id,
+            entries: Vec::new(),
+            len: 0,
+        }
+    }
+
+    ///
+    /// Returns the number of values in the `NodeMap`, including those of removed `Node`s that have
+    /// not been dropped yet.
+    ///
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    ///
+    /// Returns `true` if the `NodeMap` has no values.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    ///
+    /// Returns an immutable reference to the value for the given `NodeId`, if there is one and the
+    /// `NodeId` is valid in the given `Tree`.
+    ///
+    pub fn get<T>(&self, tree: &Tree<T>, node_id: &NodeId) -> Option<&V> {
+        let index = self.index_of(tree, node_id)?;
+        self.entries[index].as_ref().map(|(_, value)| value)
+    }
+
+    ///
+    /// Returns a mutable reference to the value for the given `NodeId`, if there is one and the
+    /// `NodeId` is valid in the given `Tree`.
+    ///
+    pub fn get_mut<T>(&mut self, tree: &Tree<T>, node_id: &NodeId) -> Option<&mut V> {
+        let index = self.index_of(tree, node_id)?;
+        self.entries[index]
+            .as_mut()
+            .map(|&mut (_, ref mut value)| value)
+    }
+
+    ///
+    /// Returns `true` if there is a value for the given `NodeId` and the `NodeId` is valid in the
+    /// given `Tree`.
+    ///
+    pub fn contains_key<T>(&self, tree: &Tree<T>, node_id: &NodeId) -> bool {
+        self.get(tree, node_id).is_some()
+    }
+
+    ///
+    /// Sets the value for the given `NodeId`, which has to be valid in the given `Tree`.
+    ///
+    /// Returns a `Result` containing the value it replaces (if any), or a `NodeIdError` if the
+    /// `Tree` is not the one the `NodeMap` was made for or the `NodeId` is not valid in it.  The
+    /// value of a removed `Node` is simply dropped when a newer `Node` in the same slot gets one.
+    ///
+    pub fn insert<T>(
+        &mut self,
+        tree: &Tree<T>,
+        node_id: &NodeId,
+        value: V,
+    ) -> Result<Option<V>, NodeIdError> {
+        if tree.id != self.tree_id {
+            return Err(NodeIdError::InvalidNodeIdForTree);
+        }
+        tree.get(node_id)?;
+
+        if self.entries.len() <= node_id.index {
+            let len = node_id.index + 1;
+            self.entries.resize_with(len, || None);
+        }
+
+        // the NodeId is valid, so any other value in its slot belongs to a removed Node
+        let slot = &mut self.entries[node_id.index];
+        match slot.replace((node_id.generation, value)) {
+            Some((generation, old_value)) if generation == node_id.generation => {
+                Ok(Some(old_value))
+            }
+            Some(_) => Ok(None),
+            None => {
+                self.len += 1;
+                Ok(None)
+            }
+        }
+    }
+
+    ///
+    /// Removes the value for the given `NodeId` and returns it, if there is one and the `NodeId`
+    /// is valid in the given `Tree`.  Use `NodeMap::retain_valid` to drop the values of removed
+    /// `Node`s.
+    ///
+    pub fn remove<T>(&mut self, tree: &Tree<T>, node_id: &NodeId) -> Option<V> {
+        let index = self.index_of(tree, node_id)?;
+        self.len -= 1;
+        self.entries[index].take().map(|(_, value)| value)
+    }
+
+    ///
+    /// Removes every value.
+    ///
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.len = 0;
+    }
+
+    ///
+    /// Removes the values of every `Node` that is no longer in the given `Tree` (orphaned `Node`s
+    /// are still in it).  Does nothing if the `Tree` is not the one the `NodeMap` was made for.
+    ///
+    pub fn retain_valid<T>(&mut self, tree: &Tree<T>) {
+        if tree.id != self.tree_id {
+            return;
+        }
+
+        for index in 0..self.entries.len() {
+            let node_id = match self.entries[index] {
+                Some((generation, _)) => self.node_id(index, generation),
+                None => continue,
+            };
+            if tree.get(&node_id).is_err() {
+                self.entries[index] = None;
+                self.len -= 1;
+            }
+        }
+    }
+
+    ///
+    /// Returns a `NodeMapIter` over the `NodeId`s and values in the `NodeMap`, in the order of
+    /// their slots in the `Tree`.  Values of removed `Node`s that have not been dropped yet are
+    /// included.
+    ///
+    pub fn iter(&self) -> NodeMapIter<'_, V> {
+        NodeMapIter {
+            tree_id: self.tree_id,
+            entries: self.entries.iter().enumerate(),
+        }
+    }
+
+    ///
+    /// Returns a `NodeMapIterMut` over the `NodeId`s and mutable references to the values in the
+    /// `NodeMap`, in the order of their slots in the `Tree`.
+    ///
+    pub fn iter_mut(&mut self) -> NodeMapIterMut<'_, V> {
+        NodeMapIterMut {
+            tree_id: self.tree_id,
+            entries: self.entries.iter_mut().enumerate(),
+        }
+    }
+
+    // the index of the entry for the given NodeId, if the NodeId is valid and the entry holds a
+    // value for that very NodeId
+    fn index_of<T>(&self, tree: &Tree<T>, node_id: &NodeId) -> Option<usize> {
+        if tree.id != self.tree_id || tree.get(node_id).is_err() {
+            return None;
+        }
+        match self.entries.get(node_id.index) {
+            Some(&Some((generation, _))) if generation == node_id.generation => Some(node_id.index),
+            _ => None,
+        }
+    }
+
+    fn node_id(&self, index: usize, generation: u32) -> NodeId {
+        NodeId {
+            tree_id: self.tree_id,
+            index,
+            generation,
+        }
+    }
+}
+
+impl<'a, 'b, T, V> Index<(&'a Tree<T>, &'b NodeId)> for NodeMap<V> {
+    type Output = V;
+
+    ///
+    /// # Panics
+    /// Panics if there is no value for the given `NodeId` or it is not valid in the given `Tree`.
+    ///
+    fn index(&self, (tree, node_id): (&'a Tree<T>, &'b NodeId)) -> &V {
+        self.get(tree, node_id)
+            .expect("NodeMap::index: there is no value for the given NodeId.")
+    }
+}
+
+impl<'a, 'b, T, V> IndexMut<(&'a Tree<T>, &'b NodeId)> for NodeMap<V> {
+    ///
+    /// # Panics
+    /// Panics if there is no value for the given `NodeId` or it is not valid in the given `Tree`.
+    ///
+    fn index_mut(&mut self, (tree, node_id): (&'a Tree<T>, &'b NodeId)) -> &mut V {
+        self.get_mut(tree, node_id)
+            .expect("NodeMap::index_mut: there is no value for the given NodeId.")
+    }
+}
+
+impl<'a, V> IntoIterator for &'a NodeMap<V> {
+    type Item = (NodeId, &'a V);
+    type IntoIter = NodeMapIter<'a, V>;
+
+    fn into_iter(self) -> NodeMapIter<'a, V> {
+        self.iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a mut NodeMap<V> {
+    type Item = (NodeId, &'a mut V);
+    type IntoIter = NodeMapIterMut<'a, V>;
+
+    fn into_iter(self) -> NodeMapIterMut<'a, V> {
+        self.iter_mut()
+    }
+}
+
+///
+/// An Iterator over the `NodeId`s and values in a `NodeMap`.
+///
+pub struct NodeMapIter<'a, V: 'a> {
+    tree_id: ProcessUniqueId,
+    entries: Enumerate<slice::Iter<'a, Option<(u32, V)>>>,
+}
+
+impl<'a, V> Iterator for NodeMapIter<'a, V> {
+    type Item = (NodeId, &'a V);
+
+    fn next(&mut self) -> Option<(NodeId, &'a V)> {
+        let tree_id = self.tree_id;
+        self.entries.find_map(|(index, entry)| {
+            entry.as_ref().map(|&(generation, ref value)| {
+                let node_id = NodeId {
+                    tree_id,
+                    index,
+                    generation,
+                };
+                (node_id, value)
+            })
+        })
+    }
+}
+
+impl<'a, V> Clone for NodeMapIter<'a, V> {
+    fn clone(&self) -> Self {
+        NodeMapIter {
+            tree_id: self.tree_id,
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+///
+/// An Iterator over the `NodeId`s and mutable references to the values in a `NodeMap`.
+///
+pub struct NodeMapIterMut<'a, V: 'a> {
+    tree_id: ProcessUniqueId,
+    entries: Enumerate<slice::IterMut<'a, Option<(u32, V)>>>,
+}
+
+impl<'a, V> Iterator for NodeMapIterMut<'a, V> {
+    type Item = (NodeId, &'a mut V);
+
+    fn next(&mut self) -> Option<(NodeId, &'a mut V)> {
+        let tree_id = self.tree_id;
+        self.entries.find_map(|(index, entry)| {
+            entry.as_mut().map(|&mut (generation, ref mut value)| {
+                let node_id = NodeId {
+                    tree_id,
+                    index,
+                    generation,
+                };
+                (node_id, value)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod node_map_tests {
+    use super::super::*;
+
+    use InsertBehavior::*;
+    use RemoveBehavior::*;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+
+        let mut map = NodeMap::new(&tree);
+        assert!(map.is_empty());
+        assert_eq!(map.insert(&tree, &child_id, "a"), Ok(None));
+        assert_eq!(map.insert(&tree, &child_id, "b"), Ok(Some("a")));
+        assert_eq!(map.len(), 1);
+        assert!(map.contains_key(&tree, &child_id));
+        assert!(!map.contains_key(&tree, &root_id));
+        assert_eq!(map.get(&tree, &root_id), None);
+
+        *map.get_mut(&tree, &child_id).unwrap() = "c";
+        assert_eq!(map[(&tree, &child_id)], "c");
+        map[(&tree, &child_id)] = "d";
+        assert_eq!(map.remove(&tree, &child_id), Some("d"));
+        assert_eq!(map.remove(&tree, &child_id), None);
+        assert!(map.is_empty());
+
+        map.insert(&tree, &root_id, "e").unwrap();
+        map.insert(&tree, &child_id, "f").unwrap();
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&tree, &root_id), None);
+    }
+
+    #[test]
+    fn test_other_trees() {
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let mut other = Tree::new();
+        let other_id = other.insert(Node::new(0), AsRoot).unwrap();
+
+        let mut map = NodeMap::new(&tree);
+        map.insert(&tree, &root_id, 1).unwrap();
+        assert_eq!(
+            map.insert(&tree, &other_id, 2),
+            Err(NodeIdError::InvalidNodeIdForTree)
+        );
+        assert_eq!(
+            map.insert(&other, &other_id, 2),
+            Err(NodeIdError::InvalidNodeIdForTree)
+        );
+        assert_eq!(map.get(&tree, &other_id), None);
+        assert_eq!(map.get(&other, &other_id), None);
+        assert_eq!(map.get(&other, &root_id), None);
+        assert_eq!(map.remove(&other, &other_id), None);
+
+        // a Tree that does not belong to the map is ignored
+        map.retain_valid(&other);
+        assert_eq!(map.len(), 1);
+
+        // clones of a Tree get a new id, and so do their NodeIds
+        let cloned = tree.clone();
+        let cloned_root_id = cloned.root_node_id().unwrap();
+        assert_eq!(map.get(&cloned, cloned_root_id), None);
+        assert_eq!(map.get(&tree, &root_id), Some(&1));
+    }
+
+    #[test]
+    fn test_removed_nodes() {
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let old_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let orphan_id = tree.insert(Node::new(2), UnderNode(&old_id)).unwrap();
+
+        let mut map = NodeMap::new(&tree);
+        map.insert(&tree, &old_id, "old").unwrap();
+        map.insert(&tree, &orphan_id, "orphan").unwrap();
+
+        tree.remove_node(old_id.clone(), OrphanChildren).unwrap();
+        let new_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+        assert_eq!(tree.get(&old_id), Err(NodeIdError::NodeIdNoLongerValid));
+        assert_eq!(tree.get(&new_id).unwrap().data(), &3);
+
+        // the new Node takes over the slot but not the value, and the stale NodeId has none
+        assert_eq!(map.get(&tree, &new_id), None);
+        assert_eq!(map.get(&tree, &old_id), None);
+        assert_eq!(map.get_mut(&tree, &old_id), None);
+        assert!(!map.contains_key(&tree, &old_id));
+        assert_eq!(map.remove(&tree, &old_id), None);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.insert(&tree, &new_id, "new"), Ok(None));
+        assert_eq!(map.len(), 2);
+        assert_eq!(
+            map.insert(&tree, &old_id, "stale"),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
+        assert_eq!(map.get(&tree, &new_id), Some(&"new"));
+
+        // the value of a removed Node is kept until it is dropped
+        tree.remove_node(new_id.clone(), DropChildren).unwrap();
+        assert_eq!(map.get(&tree, &new_id), None);
+        assert_eq!(map.len(), 2);
+        map.retain_valid(&tree);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&tree, &orphan_id), Some(&"orphan"));
+
+        // a stale NodeId is rejected even when its slot holds no value
+        assert_eq!(
+            map.insert(&tree, &new_id, "stale"),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_wrapped_generations() {
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        tree.next_generations[root_id.index] = u32::MAX;
+
+        tree.remove_node(root_id, DropChildren).unwrap();
+        let old_id = tree.insert(Node::new(1), AsRoot).unwrap();
+        let mut map = NodeMap::new(&tree);
+        map.insert(&tree, &old_id, "old").unwrap();
+
+        tree.remove_node(old_id.clone(), DropChildren).unwrap();
+        let new_id = tree.insert(Node::new(2), AsRoot).unwrap();
+        assert_eq!(new_id.index, old_id.index);
+        assert_eq!(new_id.generation, 0);
+        assert_eq!(map.insert(&tree, &new_id, "new"), Ok(None));
+        assert_eq!(map.get(&tree, &new_id), Some(&"new"));
+        assert_eq!(map.get(&tree, &old_id), None);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let child_ids = tree.extend_children(&root_id, 1..5).unwrap();
+
+        let mut map = NodeMap::new(&tree);
+        map.insert(&tree, &child_ids[2], 30).unwrap();
+        map.insert(&tree, &root_id, 0).unwrap();
+        map.insert(&tree, &child_ids[0], 10).unwrap();
+
+        let entries: Vec<(NodeId, i32)> = map.iter().map(|(id, &value)| (id, value)).collect();
+        assert_eq!(
+            entries,
+            vec![
+                (root_id.clone(), 0),
+                (child_ids[0].clone(), 10),
+                (child_ids[2].clone(), 30)
+            ]
+        );
+
+        for (node_id, value) in &mut map {
+            *value += *tree.get(&node_id).unwrap().data();
+        }
+        let values: Vec<i32> = (&map).into_iter().map(|(_, &value)| value).collect();
+        assert_eq!(values, vec![0, 11, 33]);
+        assert_eq!(map.iter().clone().count(), 3);
+    }
+}
@@ -0,0 +1,690 @@
+use std::fmt;
+use std::mem;
+
+use MoveBehavior;
+use NodeId;
+use RemoveBehavior;
+use SwapBehavior;
+use Tree;
+
+///
+/// Where a `Node` sits in a `Tree`: its parent and its position among that parent's children.
+///
+/// `Node`s without a parent (the root and any orphans) always have a position of 0.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Placement {
+    pub parent_id: Option<NodeId>,
+    pub position: usize,
+}
+
+impl Placement {
+    fn detached() -> Placement {
+        Placement {
+            parent_id: None,
+            position: 0,
+        }
+    }
+}
+
+///
+/// A change made to a `Tree`, as reported to the observers registered with `Tree::add_observer`.
+///
+/// Events are delivered after the operation that caused them has finished, so the `Tree` handed
+/// to the observers along with an event is already in its new state.  An operation that changes
+/// more than one `Node`'s place reports the secondary changes as extra `Moved` events (e.g. the old
+/// root when inserting a new root, or the children lifted by `RemoveBehavior::LiftChildren`).
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeEvent {
+    ///
+    /// A `Node` was inserted.
+    ///
+    Inserted {
+        node_id: NodeId,
+        placement: Placement,
+    },
+
+    ///
+    /// A `Node` was removed from the `placement` it had.  For `RemoveBehavior::DropChildren`,
+    /// `dropped_ids` lists the descendants that were removed along with it (in pre-order); any
+    /// children that stay in the `Tree` are reported as `Moved` first.
+    ///
+    Removed {
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+        placement: Placement,
+        dropped_ids: Vec<NodeId>,
+    },
+
+    ///
+    /// A `Node` (along with its subtree) was moved.
+    ///
+    Moved {
+        node_id: NodeId,
+        from: Placement,
+        to: Placement,
+    },
+
+    ///
+    /// Two `Node`s were swapped.  See `SwapBehavior` for what happens to their children.
+    ///
+    Swapped {
+        first_id: NodeId,
+        second_id: NodeId,
+        behavior: SwapBehavior,
+        first_from: Placement,
+        first_to: Placement,
+        second_from: Placement,
+        second_to: Placement,
+    },
+
+    ///
+    /// The children of a `Node` were put in a different order.
+    ///
+    ChildrenReordered {
+        parent_id: NodeId,
+        old_order: Vec<NodeId>,
+        new_order: Vec<NodeId>,
+    },
+
+    ///
+    /// The data of a `Node` was replaced with `Tree::replace_data`.
+    ///
+    DataChanged { node_id: NodeId },
+}
+
+///
+/// Identifies an observer registered with `Tree::add_observer`, so it can be removed again.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ObserverId(usize);
+
+type Observer<T> = Box<dyn FnMut(&Tree<T>, &TreeEvent) + Send + Sync>;
+
+///
+/// The observers of a `Tree`, along with the events held back during a transaction.
+///
+pub(crate) struct Observers<T> {
+    observers: Vec<(ObserverId, Observer<T>)>,
+    next_id: usize,
+    held_events: Option<Vec<TreeEvent>>,
+}
+
+impl<T> Default for Observers<T> {
+    fn default() -> Self {
+        Observers {
+            observers: Vec::new(),
+            next_id: 0,
+            held_events: None,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Observers<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Observers({})", self.observers.len())
+    }
+}
+
+///
+/// The state of a `Node` about to be removed, captured while it can still be looked up.
+///
+pub(crate) struct PendingRemoval {
+    node_id: NodeId,
+    behavior: RemoveBehavior,
+    placement: Placement,
+    children: Vec<NodeId>,
+    dropped_ids: Vec<NodeId>,
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Registers a closure that is called with the `Tree` and a `TreeEvent` every time the `Tree`
+    /// is changed by `insert`, `extend_children`, `remove_node`, `drain_subtree`, `move_node`,
+    /// `swap_nodes`, the `sort_children_by*` methods, the `make_*_sibling` methods or
+    /// `replace_data`.
+    ///
+    /// Edits made through `get_mut` are not reported.  Clones of the `Tree` start without any
+    /// observers.
+    ///
+    /// Returns an `ObserverId` that can be passed to `remove_observer`.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::MoveBehavior::*;
+    ///
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// let recorded = events.clone();
+    /// tree.add_observer(move |_, event| recorded.lock().unwrap().push(event.clone()));
+    ///
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.move_node(&child_id, ToRoot).unwrap();
+    ///
+    /// let events = events.lock().unwrap();
+    /// assert_eq!(
+    ///     events[0],
+    ///     TreeEvent::Inserted {
+    ///         node_id: child_id.clone(),
+    ///         placement: Placement { parent_id: Some(root_id.clone()), position: 0 },
+    ///     }
+    /// );
+    /// // the old root is moved under the new one as well
+    /// assert_eq!(events.len(), 3);
+    /// ```
+    ///
+    pub fn add_observer<F>(&mut self, observer: F) -> ObserverId
+    where
+        F: FnMut(&Tree<T>, &TreeEvent) + Send + Sync + 'static,
+    {
+        let observer_id = ObserverId(self.observers.next_id);
+        self.observers.next_id += 1;
+        self.observers
+            .observers
+            .push((observer_id, Box::new(observer)));
+        observer_id
+    }
+
+    ///
+    /// Unregisters an observer registered with `add_observer`.
+    ///
+    /// Returns `false` if the observer was not registered (anymore).
+    ///
+    pub fn remove_observer(&mut self, observer_id: ObserverId) -> bool {
+        let count = self.observers.observers.len();
+        self.observers
+            .observers
+            .retain(|&(registered_id, _)| registered_id != observer_id);
+        self.observers.observers.len() != count
+    }
+
+    pub(crate) fn is_observed(&self) -> bool {
+        !self.observers.observers.is_empty()
+    }
+
+    ///
+    /// Holds back all events until `release_events` is called.
+    ///
+    pub(crate) fn hold_events(&mut self) {
+        if self.is_observed() {
+            self.observers.held_events = Some(Vec::new());
+        }
+    }
+
+    ///
+    /// Stops holding back events, delivering the held events if `deliver` is set and dropping them
+    /// otherwise.
+    ///
+    pub(crate) fn release_events(&mut self, deliver: bool) {
+        if let Some(events) = self.observers.held_events.take() {
+            if deliver {
+                for event in events {
+                    self.notify(event);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn notify(&mut self, event: TreeEvent) {
+        if let Some(ref mut events) = self.observers.held_events {
+            events.push(event);
+            return;
+        }
+
+        // observers only get an immutable reference, so they cannot register new observers
+        let mut observers = mem::take(&mut self.observers.observers);
+        for &mut (_, ref mut observer) in observers.iter_mut() {
+            observer(self, &event);
+        }
+        self.observers.observers = observers;
+    }
+
+    pub(crate) fn placement(&self, node_id: &NodeId) -> Placement {
+        match self.get_unsafe(node_id).parent() {
+            Some(parent_id) => Placement {
+                parent_id: Some(parent_id.clone()),
+                position: self
+                    .get_unsafe(parent_id)
+                    .children()
+                    .iter()
+                    .position(|child_id| child_id == node_id)
+                    .expect("Tree::placement: A Node is missing from its parent's children."),
+            },
+            None => Placement::detached(),
+        }
+    }
+
+    ///
+    /// Captures the placements of the given `Node`s before they might be moved, if anyone is
+    /// listening.
+    ///
+    pub(crate) fn placements(&self, node_ids: &[&NodeId]) -> Vec<(NodeId, Placement)> {
+        if !self.is_observed() {
+            return Vec::new();
+        }
+        node_ids
+            .iter()
+            .map(|&node_id| (node_id.clone(), self.placement(node_id)))
+            .collect()
+    }
+
+    ///
+    /// Reports a `Moved` event for each of the given `Node`s whose placement changed.
+    ///
+    pub(crate) fn notify_moved(&mut self, old_placements: Vec<(NodeId, Placement)>) {
+        for (node_id, from) in old_placements {
+            let to = self.placement(&node_id);
+            if from != to {
+                self.notify(TreeEvent::Moved { node_id, from, to });
+            }
+        }
+    }
+
+    ///
+    /// Captures the placements of all `Node`s that a move can affect: the `Node` itself, the old
+    /// root for `MoveBehavior::ToRoot` and the child taking the `Node`'s place when it is moved
+    /// below itself.
+    ///
+    pub(crate) fn pending_moves(
+        &self,
+        node_id: &NodeId,
+        behavior: &MoveBehavior,
+    ) -> Vec<(NodeId, Placement)> {
+        let mut affected = vec![node_id];
+        match *behavior {
+            MoveBehavior::ToRoot => affected.extend(self.root_node_id()),
+            MoveBehavior::ToParent(parent_id) => {
+                if self.get(parent_id).is_ok() {
+                    affected.extend(self.find_subtree_root_between_ids(parent_id, node_id));
+                }
+            }
+        }
+        affected.dedup();
+        self.placements(&affected)
+    }
+
+    pub(crate) fn notify_inserted(&mut self, node_id: &NodeId) {
+        if self.is_observed() {
+            let placement = self.placement(node_id);
+            self.notify(TreeEvent::Inserted {
+                node_id: node_id.clone(),
+                placement,
+            });
+        }
+    }
+
+    pub(crate) fn pending_removal(
+        &self,
+        node_id: &NodeId,
+        behavior: RemoveBehavior,
+    ) -> Option<PendingRemoval> {
+        if !self.is_observed() {
+            return None;
+        }
+
+        let children = self.get_unsafe(node_id).children().clone();
+        let dropped_ids = match behavior {
+            RemoveBehavior::DropChildren => self
+                .traverse_pre_order_ids(node_id)
+                .expect("Tree::pending_removal: The NodeId was checked by the caller.")
+                .skip(1)
+                .collect(),
+            _ => Vec::new(),
+        };
+        Some(PendingRemoval {
+            node_id: node_id.clone(),
+            behavior,
+            placement: self.placement(node_id),
+            children,
+            dropped_ids,
+        })
+    }
+
+    pub(crate) fn notify_removed(&mut self, removal: PendingRemoval) {
+        if removal.behavior != RemoveBehavior::DropChildren {
+            // lifted children are appended to the old parent in order, orphans have no placement
+            let lifted = removal.behavior == RemoveBehavior::LiftChildren;
+            let lifted_into = match removal.placement.parent_id {
+                Some(ref parent_id) if lifted => Some((
+                    parent_id.clone(),
+                    self.get_unsafe(parent_id).children().len() - removal.children.len(),
+                )),
+                _ => None,
+            };
+            for (position, child_id) in removal.children.into_iter().enumerate() {
+                let to = match lifted_into {
+                    Some((ref parent_id, first_position)) => Placement {
+                        parent_id: Some(parent_id.clone()),
+                        position: first_position + position,
+                    },
+                    _ => Placement::detached(),
+                };
+                let from = Placement {
+                    parent_id: Some(removal.node_id.clone()),
+                    position,
+                };
+                self.notify(TreeEvent::Moved {
+                    node_id: child_id,
+                    from,
+                    to,
+                });
+            }
+        }
+
+        self.notify(TreeEvent::Removed {
+            node_id: removal.node_id,
+            behavior: removal.behavior,
+            placement: removal.placement,
+            dropped_ids: removal.dropped_ids,
+        });
+    }
+
+    ///
+    /// Captures the order of the children of a `Node` before they might be reordered, if anyone is
+    /// listening.
+    ///
+    pub(crate) fn children_order(&self, parent_id: &NodeId) -> Option<Vec<NodeId>> {
+        if self.is_observed() {
+            Some(self.get_unsafe(parent_id).children().clone())
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn notify_reordered(&mut self, parent_id: &NodeId, old_order: Option<Vec<NodeId>>) {
+        if let Some(old_order) = old_order {
+            let new_order = self.get_unsafe(parent_id).children().clone();
+            if old_order != new_order {
+                self.notify(TreeEvent::ChildrenReordered {
+                    parent_id: parent_id.clone(),
+                    old_order,
+                    new_order,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod observers_tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::super::*;
+
+    use test_fixtures::*;
+    use InsertBehavior::*;
+    use MoveBehavior::*;
+    use RemoveBehavior::*;
+    use SwapBehavior::*;
+
+    fn placement(parent_id: Option<&NodeId>, position: usize) -> Placement {
+        Placement {
+            parent_id: parent_id.cloned(),
+            position,
+        }
+    }
+
+    fn observe(tree: &mut Tree<i32>) -> Arc<Mutex<Vec<TreeEvent>>> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        tree.add_observer(move |_, event| recorded.lock().unwrap().push(event.clone()));
+        events
+    }
+
+    fn take(events: &Arc<Mutex<Vec<TreeEvent>>>) -> Vec<TreeEvent> {
+        events.lock().unwrap().drain(..).collect()
+    }
+
+    #[test]
+    fn test_insert() {
+        let (mut tree, ids) = small_tree();
+        let events = observe(&mut tree);
+
+        let new_id = tree.insert(Node::new(5), UnderNode(&ids[2])).unwrap();
+        let new_root_id = tree.insert(Node::new(6), AsRoot).unwrap();
+        let extended_ids = tree.extend_children(&new_id, vec![7, 8]).unwrap();
+
+        assert_eq!(
+            take(&events),
+            vec![
+                TreeEvent::Inserted {
+                    node_id: new_id.clone(),
+                    placement: placement(Some(&ids[2]), 0),
+                },
+                TreeEvent::Inserted {
+                    node_id: new_root_id.clone(),
+                    placement: placement(None, 0),
+                },
+                TreeEvent::Moved {
+                    node_id: ids[0].clone(),
+                    from: placement(None, 0),
+                    to: placement(Some(&new_root_id), 0),
+                },
+                TreeEvent::Inserted {
+                    node_id: extended_ids[0].clone(),
+                    placement: placement(Some(&new_id), 0),
+                },
+                TreeEvent::Inserted {
+                    node_id: extended_ids[1].clone(),
+                    placement: placement(Some(&new_id), 1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let (mut tree, ids) = small_tree();
+        let events = observe(&mut tree);
+
+        tree.remove_node(ids[1].clone(), LiftChildren).unwrap();
+        assert_eq!(
+            take(&events),
+            vec![
+                TreeEvent::Moved {
+                    node_id: ids[3].clone(),
+                    from: placement(Some(&ids[1]), 0),
+                    to: placement(Some(&ids[0]), 1),
+                },
+                TreeEvent::Moved {
+                    node_id: ids[4].clone(),
+                    from: placement(Some(&ids[1]), 1),
+                    to: placement(Some(&ids[0]), 2),
+                },
+                TreeEvent::Removed {
+                    node_id: ids[1].clone(),
+                    behavior: LiftChildren,
+                    placement: placement(Some(&ids[0]), 0),
+                    dropped_ids: vec![],
+                },
+            ]
+        );
+
+        tree.move_node(&ids[4], ToParent(&ids[3])).unwrap();
+        take(&events);
+        tree.remove_node(ids[0].clone(), DropChildren).unwrap();
+        assert_eq!(
+            take(&events),
+            vec![TreeEvent::Removed {
+                node_id: ids[0].clone(),
+                behavior: DropChildren,
+                placement: placement(None, 0),
+                dropped_ids: vec![ids[2].clone(), ids[3].clone(), ids[4].clone()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_orphan_and_drain() {
+        let (mut tree, ids) = small_tree();
+        let events = observe(&mut tree);
+
+        tree.remove_node(ids[1].clone(), OrphanChildren).unwrap();
+        let events_after_remove = take(&events);
+        assert_eq!(events_after_remove.len(), 3);
+        assert_eq!(
+            events_after_remove[1],
+            TreeEvent::Moved {
+                node_id: ids[4].clone(),
+                from: placement(Some(&ids[1]), 1),
+                to: placement(None, 0),
+            }
+        );
+
+        tree.drain_subtree(&ids[0]).unwrap().for_each(drop);
+        assert_eq!(
+            take(&events),
+            vec![TreeEvent::Removed {
+                node_id: ids[0].clone(),
+                behavior: DropChildren,
+                placement: placement(None, 0),
+                dropped_ids: vec![ids[2].clone()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_move_and_swap() {
+        let (mut tree, ids) = small_tree();
+        let events = observe(&mut tree);
+
+        // the child of 0 on the way to 3 takes 0's place, which is the root
+        tree.move_node(&ids[0], ToParent(&ids[3])).unwrap();
+        assert_eq!(
+            take(&events),
+            vec![
+                TreeEvent::Moved {
+                    node_id: ids[0].clone(),
+                    from: placement(None, 0),
+                    to: placement(Some(&ids[3]), 0),
+                },
+                TreeEvent::Moved {
+                    node_id: ids[1].clone(),
+                    from: placement(Some(&ids[0]), 0),
+                    to: placement(None, 0),
+                },
+            ]
+        );
+
+        tree.swap_nodes(&ids[2], &ids[4], TakeChildren).unwrap();
+        assert_eq!(
+            take(&events),
+            vec![TreeEvent::Swapped {
+                first_id: ids[2].clone(),
+                second_id: ids[4].clone(),
+                behavior: TakeChildren,
+                first_from: placement(Some(&ids[0]), 0),
+                first_to: placement(Some(&ids[1]), 1),
+                second_from: placement(Some(&ids[1]), 1),
+                second_to: placement(Some(&ids[0]), 0),
+            }]
+        );
+
+        // failed operations are not reported
+        assert!(tree.move_node(&ids[0], ToParent(&ids[0])).is_err());
+        assert!(take(&events).is_empty());
+    }
+
+    #[test]
+    fn test_reorder_and_data() {
+        let (mut tree, ids) = small_tree();
+        let events = observe(&mut tree);
+
+        tree.make_first_sibling(&ids[4]).unwrap();
+        tree.make_first_sibling(&ids[4]).unwrap();
+        tree.sort_children_by_data(&ids[1]).unwrap();
+        tree.sort_children_by_data(&ids[1]).unwrap();
+        tree.make_nth_sibling(&ids[2], 0).unwrap();
+        assert_eq!(tree.replace_data(&ids[2], 20), Ok(2));
+
+        assert_eq!(
+            take(&events),
+            vec![
+                TreeEvent::ChildrenReordered {
+                    parent_id: ids[1].clone(),
+                    old_order: vec![ids[3].clone(), ids[4].clone()],
+                    new_order: vec![ids[4].clone(), ids[3].clone()],
+                },
+                TreeEvent::ChildrenReordered {
+                    parent_id: ids[1].clone(),
+                    old_order: vec![ids[4].clone(), ids[3].clone()],
+                    new_order: vec![ids[3].clone(), ids[4].clone()],
+                },
+                TreeEvent::ChildrenReordered {
+                    parent_id: ids[0].clone(),
+                    old_order: vec![ids[1].clone(), ids[2].clone()],
+                    new_order: vec![ids[2].clone(), ids[1].clone()],
+                },
+                TreeEvent::DataChanged {
+                    node_id: ids[2].clone(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_observer() {
+        let (mut tree, ids) = small_tree();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        // observers see the Tree after the change
+        let observer_id = tree.add_observer(move |tree, event| {
+            if let TreeEvent::DataChanged { ref node_id } = *event {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(*tree.get(node_id).unwrap().data());
+            }
+        });
+
+        tree.replace_data(&ids[3], 30).unwrap();
+        assert!(tree.remove_observer(observer_id));
+        assert!(!tree.remove_observer(observer_id));
+        tree.replace_data(&ids[3], 31).unwrap();
+
+        assert_eq!(*events.lock().unwrap(), vec![30]);
+        assert!(!tree.clone().is_observed());
+    }
+
+    #[test]
+    fn test_transactions() {
+        let (mut tree, ids) = small_tree();
+        let events = observe(&mut tree);
+
+        let result = tree.transaction(|tx| {
+            tx.replace_data(&ids[3], 30)?;
+            tx.remove_node(ids[1].clone(), DropChildren)?;
+            tx.remove_node(ids[1].clone(), DropChildren)
+        });
+        assert!(result.is_err());
+        // nothing happened as far as the observers can tell
+        assert!(take(&events).is_empty());
+
+        tree.transaction(|tx| tx.replace_data(&ids[3], 30)).unwrap();
+        assert_eq!(
+            take(&events),
+            vec![TreeEvent::DataChanged {
+                node_id: ids[3].clone(),
+            }]
+        );
+        // removing a subtree reports the same event as outside of a transaction
+        tree.transaction(|tx| tx.remove_node(ids[1].clone(), DropChildren))
+            .unwrap();
+        assert_eq!(
+            take(&events),
+            vec![TreeEvent::Removed {
+                node_id: ids[1].clone(),
+                behavior: DropChildren,
+                placement: placement(Some(&ids[0]), 0),
+                dropped_ids: vec![ids[3].clone(), ids[4].clone()],
+            }]
+        );
+    }
+}
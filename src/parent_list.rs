@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use BuildError;
+use InsertBehavior::*;
+use Node;
+use NodeId;
+use ParentList;
+use Tree;
+use TreeBuilder;
+
+impl<T> Tree<T> {
+    ///
+    /// Builds a `Tree` out of `(key, parent key, data)` rows, which can come in any order.  The
+    /// one row without a parent key becomes the root, and children keep the order of their rows.
+    ///
+    /// Returns the `Tree` along with the `NodeId` of every key, or a `BuildError` if two rows have
+    /// the same key, a parent key has no row, more than one row has no parent, or the parents of
+    /// some rows form a cycle.  No rows give an empty `Tree`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let rows = vec![
+    ///     (3, Some(1), "c"),
+    ///     (1, None, "a"),
+    ///     (2, Some(1), "b"),
+    ///     (4, Some(2), "d"),
+    /// ];
+    /// let (tree, ids) = Tree::from_parent_list(rows).unwrap();
+    ///
+    /// assert_eq!(tree.root_node_id(), Some(&ids[&1]));
+    /// let children: Vec<&str> = tree.children(&ids[&1]).unwrap().map(|node| *node.data()).collect();
+    /// assert_eq!(children, vec!["c", "b"]);
+    /// assert_eq!(tree.get(&ids[&4]).unwrap().parent(), Some(&ids[&2]));
+    ///
+    /// let error = Tree::from_parent_list(vec![(1, None, "a"), (2, Some(3), "b")]).unwrap_err();
+    /// assert_eq!(error, BuildError::MissingParent { key: 2, parent: 3 });
+    /// ```
+    ///
+    #[allow(clippy::type_complexity)]
+    pub fn from_parent_list<K, I>(rows: I) -> Result<(Tree<T>, HashMap<K, NodeId>), BuildError<K>>
+    where
+        K: Hash + Eq + Clone,
+        I: IntoIterator<Item = (K, Option<K>, T)>,
+    {
+        let mut keys = Vec::new();
+        let mut parent_keys = Vec::new();
+        let mut data = Vec::new();
+        let mut index_of = HashMap::new();
+        for (key, parent_key, row_data) in rows {
+            if index_of.contains_key(&key) {
+                return Err(BuildError::DuplicateKey(key));
+            }
+            index_of.insert(key.clone(), keys.len());
+            keys.push(key);
+            parent_keys.push(parent_key);
+            data.push(Some(row_data));
+        }
+
+        let mut parents = Vec::with_capacity(keys.len());
+        let mut children = vec![Vec::new(); keys.len()];
+        let mut root: Option<usize> = None;
+        for (index, parent_key) in parent_keys.into_iter().enumerate() {
+            match parent_key {
+                Some(parent_key) => match index_of.get(&parent_key) {
+                    Some(&parent) => {
+                        children[parent].push(index);
+                        parents.push(Some(parent));
+                    }
+                    None => {
+                        return Err(BuildError::MissingParent {
+                            key: keys[index].clone(),
+                            parent: parent_key,
+                        });
+                    }
+                },
+                None => {
+                    if let Some(first) = root {
+                        return Err(BuildError::MultipleRoots(
+                            keys[first].clone(),
+                            keys[index].clone(),
+                        ));
+                    }
+                    root = Some(index);
+                    parents.push(None);
+                }
+            }
+        }
+
+        let mut tree = TreeBuilder::new().with_node_capacity(keys.len()).build();
+        let mut ids: Vec<Option<NodeId>> = vec![None; keys.len()];
+        let mut stack: Vec<usize> = root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = Node::new(data[index].take().unwrap());
+            let node_id = match parents[index] {
+                Some(parent) => tree.insert(node, UnderNode(ids[parent].as_ref().unwrap())),
+                None => tree.insert(node, AsRoot),
+            };
+            ids[index] = Some(node_id.expect("parents are inserted before their children"));
+            stack.extend(children[index].iter().rev());
+        }
+
+        // every row that the root does not lead to hangs off a cycle
+        if let Some(mut current) = ids.iter().position(Option::is_none) {
+            let mut seen = HashSet::new();
+            while seen.insert(current) {
+                current = parents[current].expect("only the root has no parent");
+            }
+            return Err(BuildError::Cycle(keys[current].clone()));
+        }
+
+        let ids = keys
+            .into_iter()
+            .zip(ids.into_iter().map(Option::unwrap))
+            .collect();
+        Ok((tree, ids))
+    }
+
+    ///
+    /// Returns a `ParentList` iterator over the `NodeId`, parent `NodeId` and data of every `Node`
+    /// below the root, in pre-order.  This is the reverse of `Tree::from_parent_list`, so parents
+    /// always come before their children.  Orphaned `Node`s are left out.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let grandchild_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    ///
+    /// let rows: Vec<_> = tree.to_parent_list().collect();
+    /// assert_eq!(
+    ///     rows,
+    ///     vec![
+    ///         (root_id.clone(), None, &0),
+    ///         (child_id.clone(), Some(root_id), &1),
+    ///         (grandchild_id, Some(child_id), &2),
+    ///     ]
+    /// );
+    /// ```
+    ///
+    pub fn to_parent_list(&self) -> ParentList<'_, T> {
+        ParentList::new(self)
+    }
+}
+
+#[cfg(test)]
+mod parent_list_tests {
+    use super::super::*;
+
+    #[test]
+    fn test_from_parent_list() {
+        let rows = vec![
+            ("d", Some("b"), 4),
+            ("c", Some("a"), 3),
+            ("e", Some("b"), 5),
+            ("a", None, 1),
+            ("b", Some("a"), 2),
+        ];
+        let (tree, ids) = Tree::from_parent_list(rows).unwrap();
+        assert!(tree.validate().is_ok());
+        assert_eq!(ids.len(), 5);
+
+        let root_id = tree.root_node_id().unwrap();
+        assert_eq!(root_id, &ids["a"]);
+        let pre_order: Vec<i32> = tree
+            .traverse_pre_order(root_id)
+            .unwrap()
+            .map(|node| *node.data())
+            .collect();
+        assert_eq!(pre_order, vec![1, 3, 2, 4, 5]);
+        for (key, node_id) in &ids {
+            let data = *tree.get(node_id).unwrap().data();
+            assert_eq!(data, " abcde".find(key).unwrap() as i32);
+        }
+
+        let (empty, ids) = Tree::<()>::from_parent_list(Vec::<(u8, _, _)>::new()).unwrap();
+        assert!(empty.root_node_id().is_none());
+        assert!(ids.is_empty());
+    }
+
+    #[test]
+    fn test_from_parent_list_errors() {
+        let build = |rows: Vec<(u8, Option<u8>)>| {
+            Tree::from_parent_list(rows.into_iter().map(|(key, parent)| (key, parent, ())))
+                .map(|_| ())
+        };
+
+        assert_eq!(
+            build(vec![(1, None), (2, Some(1)), (2, Some(1))]),
+            Err(BuildError::DuplicateKey(2))
+        );
+        assert_eq!(
+            build(vec![(1, None), (2, Some(9))]),
+            Err(BuildError::MissingParent { key: 2, parent: 9 })
+        );
+        assert_eq!(
+            build(vec![(1, None), (2, Some(1)), (3, None), (4, None)]),
+            Err(BuildError::MultipleRoots(1, 3))
+        );
+
+        // 2 -> 3 -> 4 -> 2, with 5 hanging off the cycle
+        assert_eq!(
+            build(vec![
+                (1, None),
+                (5, Some(3)),
+                (2, Some(4)),
+                (3, Some(2)),
+                (4, Some(3))
+            ]),
+            Err(BuildError::Cycle(3))
+        );
+        assert_eq!(build(vec![(1, Some(1))]), Err(BuildError::Cycle(1)));
+        assert_eq!(
+            build(vec![(1, Some(2)), (2, Some(1))]),
+            Err(BuildError::Cycle(1))
+        );
+
+        let error = build(vec![(1, None), (2, Some(9))]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "BuildError: A row names a parent that does not exist. (key: 2, parent: 9)"
+        );
+    }
+
+    #[test]
+    fn test_parent_list_round_trip() {
+        let (tree, _) = Tree::from_parent_list(vec![
+            (0, None, 'a'),
+            (1, Some(0), 'b'),
+            (2, Some(1), 'c'),
+            (3, Some(0), 'd'),
+            (4, Some(1), 'e'),
+        ])
+        .unwrap();
+
+        let rows: Vec<_> = tree
+            .to_parent_list()
+            .map(|(node_id, parent_id, &data)| (node_id, parent_id, data))
+            .collect();
+        assert_eq!(
+            rows.iter().map(|&(_, _, data)| data).collect::<String>(),
+            "abced"
+        );
+
+        let (rebuilt, ids) = Tree::from_parent_list(rows.clone()).unwrap();
+        let rebuilt_rows: Vec<_> = rebuilt
+            .to_parent_list()
+            .map(|(node_id, parent_id, &data)| (node_id, parent_id, data))
+            .collect();
+        for (&(ref old_id, ref old_parent, old_data), (new_id, new_parent, new_data)) in
+            rows.iter().zip(rebuilt_rows)
+        {
+            assert_eq!(ids[old_id], new_id);
+            assert_eq!(old_parent.as_ref().map(|id| ids[id].clone()), new_parent);
+            assert_eq!(old_data, new_data);
+        }
+
+        assert_eq!(Tree::<i32>::new().to_parent_list().count(), 0);
+    }
+}
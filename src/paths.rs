@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use InsertBehavior::*;
+use Node;
+use NodeId;
+use Tree;
+
+impl Tree<String> {
+    ///
+    /// Builds a trie-shaped `Tree` out of separated paths like `usr/local/bin`, with one `Node` per
+    /// segment.  Paths that start with the same segments share their `Node`s.
+    ///
+    /// The root is an empty `String` that stands for the start of every path, so paths with
+    /// different first segments still form a single `Tree`.  See `Tree::from_paths_with`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree = Tree::from_paths(vec!["usr/bin", "usr/lib", "etc/hosts", "usr/bin"], "/");
+    ///
+    /// let mut s = String::new();
+    /// tree.write_formatted(&mut s).unwrap();
+    /// assert_eq!(s, "\
+    /// \"\"
+    /// ├── \"usr\"
+    /// │   ├── \"bin\"
+    /// │   └── \"lib\"
+    /// └── \"etc\"
+    ///     └── \"hosts\"
+    /// ");
+    ///
+    /// assert_eq!(tree.to_paths("/"), vec!["usr/bin", "usr/lib", "etc/hosts"]);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the separator is empty.
+    ///
+    pub fn from_paths<I, S>(paths: I, separator: &str) -> Tree<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Tree::from_paths_with(paths, separator, String::new(), |segment| {
+            segment.to_string()
+        })
+    }
+
+    ///
+    /// Returns the path from below the root to every leaf, joining the segments with the
+    /// separator.  See `Tree::to_paths_with`.
+    ///
+    pub fn to_paths(&self, separator: &str) -> Vec<String> {
+        self.to_paths_with(separator, |segment| segment.clone())
+    }
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Builds a trie-shaped `Tree` out of separated paths, turning each segment into the data of
+    /// a `Node` with the given closure.  The given root data becomes the root, which stands for
+    /// the start of every path.
+    ///
+    /// Segments are compared as strings, so the closure is only called once per distinct prefix.
+    /// Children are in the order their segments first appear, and empty segments (from leading,
+    /// trailing or doubled separators) are skipped.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let metrics = vec!["http.requests.get", "http.requests.post", "http.errors", "db.queries"];
+    /// let tree = Tree::from_paths_with(metrics, ".", 0, |segment| segment.len());
+    ///
+    /// let root_id = tree.root_node_id().unwrap();
+    /// let lengths: Vec<usize> = tree
+    ///     .traverse_pre_order(root_id)
+    ///     .unwrap()
+    ///     .map(|node| *node.data())
+    ///     .collect();
+    /// assert_eq!(lengths, vec![0, 4, 8, 3, 4, 6, 2, 7]);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the separator is empty.
+    ///
+    pub fn from_paths_with<I, S, F>(paths: I, separator: &str, root: T, mut f: F) -> Tree<T>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+        F: FnMut(&str) -> T,
+    {
+        assert!(
+            !separator.is_empty(),
+            "Tree::from_paths_with: the separator cannot be empty."
+        );
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(root), AsRoot).unwrap();
+        // the NodeIds of the children of each Node, by segment
+        let mut segments: HashMap<NodeId, HashMap<String, NodeId>> = HashMap::new();
+
+        for path in paths {
+            let mut parent_id = root_id.clone();
+            for segment in path.as_ref().split(separator) {
+                if segment.is_empty() {
+                    continue;
+                }
+
+                let children = segments.entry(parent_id.clone()).or_default();
+                parent_id = match children.get(segment) {
+                    Some(child_id) => child_id.clone(),
+                    None => {
+                        let child_id = tree
+                            .insert(Node::new(f(segment)), UnderNode(&parent_id))
+                            .unwrap();
+                        children.insert(segment.to_string(), child_id.clone());
+                        child_id
+                    }
+                };
+            }
+        }
+
+        tree
+    }
+
+    ///
+    /// Returns the path from below the root to every leaf in pre-order, turning the data of each
+    /// `Node` into a segment with the given closure and joining the segments with the separator.
+    ///
+    /// The root is left out of every path, which makes this the inverse of
+    /// `Tree::from_paths_with`.  Only leaves get a path of their own, so a path that is a prefix
+    /// of another one (like `a` next to `a/b`) does not come back.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree = Tree::from_paths_with(vec!["a/b/c", "a/d", "e"], "/", 0, |segment| {
+    ///     segment.as_bytes()[0]
+    /// });
+    ///
+    /// let paths = tree.to_paths_with("::", |&byte| (byte as char).to_uppercase().to_string());
+    /// assert_eq!(paths, vec!["A::B::C", "A::D", "E"]);
+    /// ```
+    ///
+    pub fn to_paths_with<F>(&self, separator: &str, f: F) -> Vec<String>
+    where
+        F: Fn(&T) -> String,
+    {
+        let root_id = match self.root_node_id() {
+            Some(root_id) => root_id,
+            None => return Vec::new(),
+        };
+
+        self.traverse_pre_order_ids(root_id)
+            .unwrap()
+            .filter(|node_id| node_id != root_id && self.get_unsafe(node_id).children().is_empty())
+            .map(|leaf_id| {
+                let mut segments: Vec<String> = self
+                    .ancestors(&leaf_id)
+                    .unwrap()
+                    .filter(|node| node.parent().is_some())
+                    .map(|node| f(node.data()))
+                    .collect();
+                segments.reverse();
+                segments.push(f(self.get_unsafe(&leaf_id).data()));
+                segments.join(separator)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod paths_tests {
+    use super::super::*;
+
+    #[test]
+    fn test_from_paths() {
+        let tree = Tree::from_paths(
+            vec!["/usr/local/bin/", "usr//local/lib", "usr", "var/log", ""],
+            "/",
+        );
+        assert!(tree.validate().is_ok());
+
+        let root_id = tree.root_node_id().unwrap();
+        let pre_order: Vec<&str> = tree
+            .traverse_pre_order(root_id)
+            .unwrap()
+            .map(|node| node.data().as_str())
+            .collect();
+        assert_eq!(
+            pre_order,
+            vec!["", "usr", "local", "bin", "lib", "var", "log"]
+        );
+
+        let empty = Tree::from_paths(Vec::<String>::new(), "/");
+        let root_id = empty.root_node_id().unwrap();
+        assert_eq!(empty.get(root_id).unwrap().data(), "");
+        assert!(empty.to_paths("/").is_empty());
+    }
+
+    #[test]
+    fn test_segments_are_compared_before_conversion() {
+        let mut calls = Vec::new();
+        let tree = Tree::from_paths_with(vec!["A.b", "a.b", "A.c"], ".", ' ', |segment| {
+            calls.push(segment.to_string());
+            segment.to_ascii_lowercase().chars().next().unwrap()
+        });
+
+        assert_eq!(calls, vec!["A", "b", "a", "b", "c"]);
+        assert_eq!(
+            tree.to_paths_with("", |c| c.to_string()),
+            vec!["ab", "ac", "ab"]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let paths = vec![
+            "src/lib.rs",
+            "src/tree/mod.rs",
+            "src/tree/iter.rs",
+            "README.md",
+            "tests/a.rs",
+        ];
+        let tree = Tree::from_paths(paths.clone(), "/");
+        assert_eq!(tree.to_paths("/"), paths);
+        assert_eq!(
+            Tree::from_paths(tree.to_paths("::"), "::").to_paths("/"),
+            paths
+        );
+
+        assert!(Tree::<String>::new().to_paths("/").is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_separator() {
+        Tree::from_paths(vec!["a"], "");
+    }
+}
@@ -0,0 +1,907 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::iter;
+use std::mem;
+
+use super::*;
+
+///
+/// A `Tree` that records every change made to it so that the changes can be undone and redone.
+///
+/// Each operation appends an entry to a log holding just enough of the old state of the `Tree` to
+/// put it back: the links of the `Node`s whose parent or children changed, the old root, replaced
+/// data and removed `Node`s.  Undoing puts that state back into the `Tree` (keeping the state it
+/// replaces for redoing), so removed `Node`s come back in their old slots and every `NodeId` that
+/// was valid before the operation is valid again afterwards.
+///
+/// Operations can be grouped with `begin_transaction` and `commit_transaction`, in which case
+/// they are undone and redone together.  Any new operation clears the redo log.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+/// use id_tree::MoveBehavior::*;
+/// use id_tree::RemoveBehavior::*;
+///
+/// let mut tree: RecordedTree<i32> = RecordedTree::new();
+///
+/// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+/// let a_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+/// let b_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+///
+/// tree.move_node(&b_id, ToParent(&a_id)).unwrap();
+/// tree.remove_node(a_id.clone(), DropChildren).unwrap();
+/// assert!(tree.get(&b_id).is_err());
+///
+/// assert!(tree.undo());
+/// assert_eq!(tree.get(&b_id).unwrap().parent(), Some(&a_id));
+///
+/// assert!(tree.undo());
+/// assert_eq!(tree.get(&b_id).unwrap().parent(), Some(&root_id));
+///
+/// assert!(tree.redo());
+/// assert_eq!(tree.get(&b_id).unwrap().parent(), Some(&a_id));
+/// ```
+///
+#[derive(Debug)]
+pub struct RecordedTree<T> {
+    tree: Tree<T>,
+    undo_log: Vec<Vec<Change<T>>>,
+    redo_log: Vec<Vec<Change<T>>>,
+    open_transactions: usize,
+}
+
+///
+/// One piece of the state of a `Tree`.  Applying a `Change` swaps the state it holds with the
+/// current state of the `Tree`, so applying it again reverses it.
+///
+#[derive(Debug)]
+pub(crate) enum Change<T> {
+    Root(Option<NodeId>),
+    Links {
+        node_id: NodeId,
+        parent: Option<NodeId>,
+        children: Vec<NodeId>,
+    },
+    Data(NodeId, T),
+    Slot(NodeId, Option<Node<T>>),
+    // a Node along with the free NodeId its slot was taken from, or None for a new slot
+    Insertion {
+        node_id: NodeId,
+        node: Option<Node<T>>,
+        reused_id: Option<NodeId>,
+    },
+}
+
+impl<T> Change<T> {
+    pub(crate) fn links(tree: &Tree<T>, node_id: &NodeId) -> Change<T> {
+        let node = tree.get_unsafe(node_id);
+        Change::Links {
+            node_id: node_id.clone(),
+            parent: node.parent().cloned(),
+            children: node.children().clone(),
+        }
+    }
+
+    pub(crate) fn apply(&mut self, tree: &mut Tree<T>) {
+        match *self {
+            Change::Root(ref mut root) => mem::swap(&mut tree.root, root),
+            Change::Links {
+                ref node_id,
+                ref mut parent,
+                ref mut children,
+            } => {
+                let node = tree.get_mut_unsafe(node_id);
+                let current_parent = node.parent().cloned();
+                node.set_parent(parent.take());
+                *parent = current_parent;
+                mem::swap(node.children_mut(), children);
+            }
+            Change::Data(ref node_id, ref mut data) => {
+                mem::swap(tree.get_mut_unsafe(node_id).data_mut(), data);
+            }
+            Change::Slot(ref node_id, ref mut slot) => match slot.take() {
+                Some(node) => tree.restore_node(node_id, node),
+                None => *slot = Some(tree.take_node(node_id.clone())),
+            },
+            Change::Insertion {
+                ref node_id,
+                ref mut node,
+                ref reused_id,
+            } => match node.take() {
+                Some(node) => {
+                    if reused_id.is_some() {
+                        tree.free_ids.pop();
+                        tree.generations[node_id.index] = node_id.generation;
+                    } else {
+                        tree.nodes.push(None);
+                        tree.generations.push(node_id.generation);
+                    }
+                    tree.nodes[node_id.index] = Some(node);
+                }
+                None => {
+                    // the slot (or the one pushed next) gets a generation that was never handed
+                    // out, so the NodeId of the Node taken out here stays invalid for good
+                    *node = tree.nodes[node_id.index].take();
+                    match *reused_id {
+                        Some(ref free_id) => {
+                            tree.free_ids.push(free_id.clone());
+                            tree.generations[node_id.index] = tree.issue_generation(node_id.index);
+                        }
+                        None => {
+                            tree.nodes.pop();
+                            tree.generations.pop();
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl<T> RecordedTree<T> {
+    ///
+    /// Creates a new, empty `RecordedTree`.
+    ///
+    /// ```
+    /// use id_tree::RecordedTree;
+    ///
+    /// let _tree: RecordedTree<i32> = RecordedTree::new();
+    /// ```
+    ///
+    pub fn new() -> RecordedTree<T> {
+        RecordedTree::from_tree(Tree::new())
+    }
+
+    ///
+    /// Starts recording the changes made to an existing `Tree`.  The `Tree` as given is the
+    /// oldest state that can be restored by undoing.
+    ///
+    pub fn from_tree(tree: Tree<T>) -> RecordedTree<T> {
+        RecordedTree {
+            tree,
+            undo_log: Vec::new(),
+            redo_log: Vec::new(),
+            open_transactions: 0,
+        }
+    }
+
+    ///
+    /// Returns an immutable reference to the underlying `Tree`.
+    ///
+    /// All of the read-only `Tree` methods (traversals, `ancestors`, `height`, etc.) are available
+    /// through this reference.
+    ///
+    pub fn tree(&self) -> &Tree<T> {
+        &self.tree
+    }
+
+    ///
+    /// Consumes the `RecordedTree`, dropping the log and returning the underlying `Tree`.
+    ///
+    pub fn into_tree(self) -> Tree<T> {
+        self.tree
+    }
+
+    ///
+    /// Returns a `Some` value containing the `NodeId` of the root `Node` if it exists.  Otherwise a
+    /// `None` value is returned.
+    ///
+    pub fn root_node_id(&self) -> Option<&NodeId> {
+        self.tree.root_node_id()
+    }
+
+    ///
+    /// Get an immutable reference to a `Node`.
+    ///
+    /// Returns a `Result` containing the immutable reference or a `NodeIdError` if one occurred.
+    ///
+    pub fn get(&self, node_id: &NodeId) -> Result<&Node<T>, NodeIdError> {
+        self.tree.get(node_id)
+    }
+
+    ///
+    /// Inserts a new `Node` into the `RecordedTree`.  The `InsertBehavior` provided will determine
+    /// where the `Node` is inserted.
+    ///
+    /// Returns a `Result` containing the `NodeId` of the `Node` that was inserted or a
+    /// `NodeIdError` if one occurred.
+    ///
+    pub fn insert(
+        &mut self,
+        node: Node<T>,
+        behavior: InsertBehavior,
+    ) -> Result<NodeId, NodeIdError> {
+        let mut changes = Vec::new();
+        let new_id = self
+            .tree
+            .recorded_insert(node, behavior, false, &mut changes)?;
+        self.record(changes);
+        Ok(new_id)
+    }
+
+    ///
+    /// Remove a `Node` from the `RecordedTree`.  The `RemoveBehavior` provided determines what
+    /// happens to the removed `Node`'s children.
+    ///
+    /// Unlike `Tree::remove_node`, the removed `Node` is not returned: it (along with its
+    /// descendants for `RemoveBehavior::DropChildren`) is kept in the log so that the removal can
+    /// be undone.
+    ///
+    /// Returns an empty `Result` or a `NodeIdError` if one occurred.
+    ///
+    pub fn remove_node(
+        &mut self,
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        let mut changes = Vec::new();
+        self.tree
+            .recorded_remove_node(node_id, behavior, &mut changes)?;
+        self.record(changes);
+        Ok(())
+    }
+
+    ///
+    /// Moves a `Node` in the `RecordedTree` to a new location based upon the `MoveBehavior`
+    /// provided.
+    ///
+    /// Returns an empty `Result` or a `NodeIdError` if one occurred.
+    ///
+    pub fn move_node(
+        &mut self,
+        node_id: &NodeId,
+        behavior: MoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        let mut changes = Vec::new();
+        self.tree
+            .recorded_move_node(node_id, behavior, &mut changes)?;
+        self.record(changes);
+        Ok(())
+    }
+
+    ///
+    /// Swap `Node`s in the `RecordedTree` based upon the `SwapBehavior` provided.
+    ///
+    /// Returns an empty `Result` or a `NodeIdError` if one occurred.
+    ///
+    pub fn swap_nodes(
+        &mut self,
+        first_id: &NodeId,
+        second_id: &NodeId,
+        behavior: SwapBehavior,
+    ) -> Result<(), NodeIdError> {
+        let mut changes = Vec::new();
+        self.tree
+            .recorded_swap_nodes(first_id, second_id, behavior, &mut changes)?;
+        self.record(changes);
+        Ok(())
+    }
+
+    ///
+    /// Sorts the children of one node, in-place, using compare to compare the nodes.  See
+    /// `Tree::sort_children_by` for more information.
+    ///
+    /// Returns an empty `Result` containing a `NodeIdError` if one occurred.
+    ///
+    pub fn sort_children_by<F>(&mut self, node_id: &NodeId, compare: F) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&Node<T>, &Node<T>) -> Ordering,
+    {
+        let mut changes = Vec::new();
+        self.tree
+            .recorded_sort_children_by(node_id, compare, &mut changes)?;
+        self.record(changes);
+        Ok(())
+    }
+
+    ///
+    /// Moves the node to a position amongst sibling nodes.  See `Tree::make_nth_sibling` for more
+    /// information.
+    ///
+    pub fn make_nth_sibling(&mut self, node_id: &NodeId, pos: usize) -> Result<(), NodeIdError> {
+        let mut changes = Vec::new();
+        self.tree
+            .recorded_make_nth_sibling(node_id, pos, &mut changes)?;
+        self.record(changes);
+        Ok(())
+    }
+
+    ///
+    /// Replaces the data of the `Node` specified by the `NodeId` given.
+    ///
+    /// Unlike `Node::replace_data`, the old data is not returned: it is kept in the log so that
+    /// the replacement can be undone.
+    ///
+    /// Returns an empty `Result` or a `NodeIdError` if one occurred.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: RecordedTree<&str> = RecordedTree::new();
+    /// let root_id = tree.insert(Node::new("draft"), AsRoot).unwrap();
+    ///
+    /// tree.replace_data(&root_id, "final").unwrap();
+    /// assert_eq!(tree.get(&root_id).unwrap().data(), &"final");
+    ///
+    /// tree.undo();
+    /// assert_eq!(tree.get(&root_id).unwrap().data(), &"draft");
+    /// ```
+    ///
+    pub fn replace_data(&mut self, node_id: &NodeId, data: T) -> Result<(), NodeIdError> {
+        let mut changes = Vec::new();
+        self.tree
+            .recorded_replace_data(node_id, data, &mut changes)?;
+        self.record(changes);
+        Ok(())
+    }
+
+    ///
+    /// Starts a transaction: every operation up to the matching `commit_transaction` is undone and
+    /// redone as a whole.  Transactions can be nested, in which case the outermost one decides.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: RecordedTree<i32> = RecordedTree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// tree.begin_transaction();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// tree.commit_transaction();
+    ///
+    /// assert!(tree.undo());
+    /// assert_eq!(tree.get(&root_id).unwrap().children().len(), 0);
+    /// ```
+    ///
+    pub fn begin_transaction(&mut self) {
+        if self.open_transactions == 0 {
+            self.undo_log.push(Vec::new());
+        }
+        self.open_transactions += 1;
+    }
+
+    ///
+    /// Ends the innermost transaction started with `begin_transaction`.  Does nothing if no
+    /// transaction is open.
+    ///
+    pub fn commit_transaction(&mut self) {
+        match self.open_transactions {
+            0 => {}
+            1 => self.close_transactions(),
+            _ => self.open_transactions -= 1,
+        }
+    }
+
+    ///
+    /// Undoes the most recent operation (or transaction) that has not been undone yet.  Any open
+    /// transaction is committed first.
+    ///
+    /// Returns `false` if there was nothing to undo.
+    ///
+    pub fn undo(&mut self) -> bool {
+        self.close_transactions();
+        match self.undo_log.pop() {
+            Some(mut changes) => {
+                for change in changes.iter_mut().rev() {
+                    change.apply(&mut self.tree);
+                }
+                self.tree.validate_mutation();
+                self.redo_log.push(changes);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Redoes the most recently undone operation (or transaction).  Any open transaction is
+    /// committed first.
+    ///
+    /// Returns `false` if there was nothing to redo.
+    ///
+    pub fn redo(&mut self) -> bool {
+        self.close_transactions();
+        match self.redo_log.pop() {
+            Some(mut changes) => {
+                for change in changes.iter_mut() {
+                    change.apply(&mut self.tree);
+                }
+                self.tree.validate_mutation();
+                self.undo_log.push(changes);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Returns `true` if there is anything to undo.
+    ///
+    pub fn can_undo(&self) -> bool {
+        self.undo_log.iter().any(|changes| !changes.is_empty())
+    }
+
+    ///
+    /// Returns `true` if there is anything to redo.
+    ///
+    pub fn can_redo(&self) -> bool {
+        !self.redo_log.is_empty()
+    }
+
+    ///
+    /// Forgets all recorded operations (dropping any removed `Node`s kept for undoing) and commits
+    /// any open transaction.
+    ///
+    pub fn clear_history(&mut self) {
+        self.undo_log.clear();
+        self.redo_log.clear();
+        self.open_transactions = 0;
+    }
+
+    fn record(&mut self, changes: Vec<Change<T>>) {
+        // an empty entry would make undo() report success without changing anything
+        if changes.is_empty() {
+            return;
+        }
+
+        self.redo_log.clear();
+        if self.open_transactions > 0 {
+            self.undo_log
+                .last_mut()
+                .expect("RecordedTree::record: Missing the entry of an open transaction.")
+                .extend(changes);
+        } else {
+            self.undo_log.push(changes);
+        }
+    }
+
+    // `Option::is_some_and` needs Rust 1.70
+    #[allow(clippy::unnecessary_map_or)]
+    fn close_transactions(&mut self) {
+        if self.open_transactions > 0 {
+            self.open_transactions = 0;
+            if self
+                .undo_log
+                .last()
+                .map_or(false, |changes| changes.is_empty())
+            {
+                self.undo_log.pop();
+            }
+        }
+    }
+}
+
+///
+/// The recorded versions of the `Tree` operations, shared by `RecordedTree` and `Transaction`.
+/// Each one only pushes its `Change`s once it has succeeded, so applying them in reverse order
+/// restores the `Tree`.
+///
+impl<T> Tree<T> {
+    ///
+    /// With `exact` set, reversing the insertion also puts the free slots back exactly as they
+    /// were.  Otherwise the slot is retired like any removed `Node`.  Either way, the `NodeId` can
+    /// never refer to a different `Node` later on.
+    ///
+    pub(crate) fn recorded_insert(
+        &mut self,
+        node: Node<T>,
+        behavior: InsertBehavior,
+        exact: bool,
+        changes: &mut Vec<Change<T>>,
+    ) -> Result<NodeId, NodeIdError> {
+        let touched: Vec<NodeId> = match behavior {
+            InsertBehavior::UnderNode(parent_id) => {
+                self.get(parent_id)?;
+                vec![parent_id.clone()]
+            }
+            InsertBehavior::AsRoot => self.root_node_id().cloned().into_iter().collect(),
+        };
+
+        let mut recorded = self.snapshot(&touched);
+        let reused_id = self.free_ids.last().cloned();
+        let new_id = self.insert(node, behavior)?;
+        recorded.push(if exact {
+            Change::Insertion {
+                node_id: new_id.clone(),
+                node: None,
+                reused_id,
+            }
+        } else {
+            Change::Slot(new_id.clone(), None)
+        });
+
+        changes.append(&mut recorded);
+        Ok(new_id)
+    }
+
+    pub(crate) fn recorded_remove_node(
+        &mut self,
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+        changes: &mut Vec<Change<T>>,
+    ) -> Result<(), NodeIdError> {
+        let node = self.get(&node_id)?;
+
+        let mut touched = vec![node_id.clone()];
+        touched.extend(node.parent().cloned());
+        let mut recorded = self.snapshot(&touched);
+
+        let node = if let RemoveBehavior::DropChildren = behavior {
+            // take the descendants out by hand, since Tree::remove_node would drop them, but
+            // report the removal the same way it does
+            let removal = self.pending_removal(&node_id, behavior);
+            let descendant_ids: Vec<NodeId> =
+                self.traverse_pre_order_ids(&node_id)?.skip(1).collect();
+            for descendant_id in descendant_ids {
+                let descendant = self.take_node(descendant_id.clone());
+                recorded.push(Change::Slot(descendant_id, Some(descendant)));
+            }
+            self.get_mut_unsafe(&node_id).children_mut().clear();
+
+            let node = self.remove_node_internal(node_id.clone());
+            self.validate_mutation();
+            if let Some(removal) = removal {
+                self.notify_removed(removal);
+            }
+            node
+        } else {
+            self.remove_node(node_id.clone(), behavior)?
+        };
+        recorded.push(Change::Slot(node_id, Some(node)));
+
+        changes.append(&mut recorded);
+        Ok(())
+    }
+
+    pub(crate) fn recorded_move_node(
+        &mut self,
+        node_id: &NodeId,
+        behavior: MoveBehavior,
+        changes: &mut Vec<Change<T>>,
+    ) -> Result<(), NodeIdError> {
+        let node = self.get(node_id)?;
+
+        let mut touched = vec![node_id.clone()];
+        touched.extend(node.parent().cloned());
+        match behavior {
+            MoveBehavior::ToRoot => touched.extend(self.root_node_id().cloned()),
+            MoveBehavior::ToParent(parent_id) => {
+                self.get(parent_id)?;
+                touched.push(parent_id.clone());
+            }
+        }
+
+        let mut recorded = self.snapshot(&touched);
+        self.move_node(node_id, behavior)?;
+
+        changes.append(&mut recorded);
+        Ok(())
+    }
+
+    pub(crate) fn recorded_swap_nodes(
+        &mut self,
+        first_id: &NodeId,
+        second_id: &NodeId,
+        behavior: SwapBehavior,
+        changes: &mut Vec<Change<T>>,
+    ) -> Result<(), NodeIdError> {
+        let first_parent_id = self.get(first_id)?.parent().cloned();
+        let second_parent_id = self.get(second_id)?.parent().cloned();
+
+        let mut touched = vec![first_id.clone(), second_id.clone()];
+        touched.extend(first_parent_id);
+        touched.extend(second_parent_id);
+
+        let mut recorded = self.snapshot(&touched);
+        self.swap_nodes(first_id, second_id, behavior)?;
+
+        changes.append(&mut recorded);
+        Ok(())
+    }
+
+    pub(crate) fn recorded_sort_children_by<F>(
+        &mut self,
+        node_id: &NodeId,
+        compare: F,
+        changes: &mut Vec<Change<T>>,
+    ) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&Node<T>, &Node<T>) -> Ordering,
+    {
+        self.get(node_id)?;
+
+        let recorded = Change::links(self, node_id);
+        self.sort_children_by(node_id, compare)?;
+
+        changes.push(recorded);
+        Ok(())
+    }
+
+    pub(crate) fn recorded_make_nth_sibling(
+        &mut self,
+        node_id: &NodeId,
+        pos: usize,
+        changes: &mut Vec<Change<T>>,
+    ) -> Result<(), NodeIdError> {
+        let parent_id = self
+            .get(node_id)?
+            .parent()
+            .ok_or(NodeIdError::NotASibling)?;
+
+        let recorded = Change::links(self, parent_id);
+        self.make_nth_sibling(node_id, pos)?;
+
+        changes.push(recorded);
+        Ok(())
+    }
+
+    pub(crate) fn recorded_replace_data(
+        &mut self,
+        node_id: &NodeId,
+        data: T,
+        changes: &mut Vec<Change<T>>,
+    ) -> Result<(), NodeIdError> {
+        let old_data = self.replace_data(node_id, data)?;
+        changes.push(Change::Data(node_id.clone(), old_data));
+        Ok(())
+    }
+
+    ///
+    /// Records the links of the given `Node`s and of all of their children, along with the root.
+    /// Every operation only changes the links of `Node`s in this set.
+    ///
+    fn snapshot(&self, touched: &[NodeId]) -> Vec<Change<T>> {
+        let mut changes = vec![Change::Root(self.root.clone())];
+        let mut seen = HashSet::new();
+        for node_id in touched {
+            let children = self.get_unsafe(node_id).children();
+            for linked_id in iter::once(node_id).chain(children) {
+                if seen.insert(linked_id) {
+                    changes.push(Change::links(self, linked_id));
+                }
+            }
+        }
+        changes
+    }
+}
+
+impl<T> Default for RecordedTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod recorded_tree_tests {
+    use super::super::*;
+
+    use test_fixtures::*;
+    use InsertBehavior::*;
+    use MoveBehavior::*;
+    use RemoveBehavior::*;
+    use SwapBehavior::*;
+
+    type Operation = Box<dyn Fn(&mut RecordedTree<i32>)>;
+
+    // applies each operation, then checks that undoing and redoing walks back and forth through
+    // exactly the same states
+    fn check_round_trip(tree: &mut RecordedTree<i32>, operations: Vec<Operation>) {
+        let mut states = vec![slots(tree.tree())];
+        for operation in &operations {
+            operation(tree);
+            states.push(slots(tree.tree()));
+            assert!(tree.tree().validate().is_ok());
+        }
+
+        for expected in states.iter().rev().skip(1) {
+            assert!(tree.undo());
+            assert_eq!(&slots(tree.tree()), expected);
+            assert!(tree.tree().validate().is_ok());
+        }
+        assert!(!tree.undo());
+
+        for expected in states.iter().skip(1) {
+            assert!(tree.redo());
+            assert_eq!(&slots(tree.tree()), expected);
+            assert!(tree.tree().validate().is_ok());
+        }
+        assert!(!tree.redo());
+    }
+
+    #[test]
+    fn test_remove_node() {
+        for behavior_index in 0..3 {
+            let (tree, ids) = small_tree();
+            let mut tree = RecordedTree::from_tree(tree);
+            let behavior = vec![DropChildren, LiftChildren, OrphanChildren].remove(behavior_index);
+
+            tree.remove_node(ids[1].clone(), behavior).unwrap();
+            tree.undo();
+            for node_id in &ids {
+                assert!(tree.get(node_id).is_ok());
+            }
+            tree.redo();
+            assert!(tree.get(&ids[1]).is_err());
+        }
+
+        let (tree, ids) = small_tree();
+        let mut tree = RecordedTree::from_tree(tree);
+        check_round_trip(
+            &mut tree,
+            vec![
+                Box::new({
+                    let ids = ids.clone();
+                    move |tree| tree.remove_node(ids[1].clone(), DropChildren).unwrap()
+                }),
+                Box::new({
+                    let ids = ids.clone();
+                    move |tree| tree.remove_node(ids[0].clone(), LiftChildren).unwrap()
+                }),
+                Box::new(|tree| {
+                    tree.insert(Node::new(5), AsRoot).unwrap();
+                }),
+                Box::new(move |tree| tree.remove_node(ids[2].clone(), OrphanChildren).unwrap()),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_structural_operations() {
+        let (tree, ids) = small_tree();
+        let mut tree = RecordedTree::from_tree(tree);
+        check_round_trip(
+            &mut tree,
+            vec![
+                Box::new({
+                    let ids = ids.clone();
+                    move |tree| tree.move_node(&ids[0], ToParent(&ids[3])).unwrap()
+                }),
+                Box::new({
+                    let ids = ids.clone();
+                    move |tree| tree.move_node(&ids[2], ToRoot).unwrap()
+                }),
+                Box::new({
+                    let ids = ids.clone();
+                    move |tree| tree.swap_nodes(&ids[1], &ids[4], TakeChildren).unwrap()
+                }),
+                Box::new({
+                    let ids = ids.clone();
+                    move |tree| tree.swap_nodes(&ids[0], &ids[2], ChildrenOnly).unwrap()
+                }),
+                Box::new({
+                    let ids = ids.clone();
+                    move |tree| tree.swap_nodes(&ids[3], &ids[1], LeaveChildren).unwrap()
+                }),
+                Box::new({
+                    let ids = ids.clone();
+                    move |tree| {
+                        tree.sort_children_by(&ids[2], |a, b| b.data().cmp(a.data()))
+                            .unwrap()
+                    }
+                }),
+                Box::new({
+                    let ids = ids.clone();
+                    move |tree| tree.make_nth_sibling(&ids[3], 0).unwrap()
+                }),
+                Box::new(move |tree| tree.replace_data(&ids[4], 40).unwrap()),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_node_ids_stay_valid() {
+        let (tree, ids) = small_tree();
+        let mut tree = RecordedTree::from_tree(tree);
+
+        let new_id = tree.insert(Node::new(5), UnderNode(&ids[2])).unwrap();
+        tree.remove_node(ids[0].clone(), DropChildren).unwrap();
+        // the freed slots are reused
+        let other_id = tree.insert(Node::new(6), AsRoot).unwrap();
+        assert_eq!(other_id.index, ids[0].index);
+
+        tree.undo();
+        assert_eq!(
+            tree.get(&other_id).err(),
+            Some(NodeIdError::NodeIdNoLongerValid)
+        );
+        tree.undo();
+        assert_eq!(tree.get(&new_id).unwrap().data(), &5);
+        assert_eq!(tree.root_node_id(), Some(&ids[0]));
+        tree.undo();
+        assert_eq!(
+            tree.get(&new_id).err(),
+            Some(NodeIdError::NodeIdNoLongerValid)
+        );
+
+        tree.redo();
+        tree.redo();
+        tree.redo();
+        assert_eq!(tree.get(&other_id).unwrap().data(), &6);
+        assert!(tree.get(&new_id).is_err());
+    }
+
+    #[test]
+    fn test_generations_never_go_back() {
+        let mut tree = RecordedTree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let x_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.remove_node(x_id.clone(), DropChildren).unwrap();
+        let y_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        assert_eq!(y_id.index, x_id.index);
+
+        // undoing the removal gives the slot its old generation back...
+        tree.undo();
+        tree.undo();
+        assert_eq!(tree.get(&x_id).unwrap().data(), &1);
+
+        // ...but the generation Y was handed out with must stay retired
+        tree.remove_node(x_id.clone(), DropChildren).unwrap();
+        let z_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+        assert_eq!(z_id.index, y_id.index);
+        assert_ne!(z_id, y_id);
+        assert_eq!(
+            tree.get(&y_id).err(),
+            Some(NodeIdError::NodeIdNoLongerValid)
+        );
+        assert_eq!(tree.get(&z_id).unwrap().data(), &3);
+    }
+
+    #[test]
+    fn test_transactions() {
+        let (tree, ids) = small_tree();
+        let mut tree = RecordedTree::from_tree(tree);
+        let before = slots(tree.tree());
+
+        tree.begin_transaction();
+        tree.move_node(&ids[4], ToParent(&ids[2])).unwrap();
+        tree.begin_transaction();
+        tree.replace_data(&ids[4], 40).unwrap();
+        tree.commit_transaction();
+        tree.remove_node(ids[3].clone(), DropChildren).unwrap();
+        tree.commit_transaction();
+        let after = slots(tree.tree());
+
+        assert!(tree.undo());
+        assert_eq!(slots(tree.tree()), before);
+        assert!(!tree.can_undo());
+        assert!(tree.redo());
+        assert_eq!(slots(tree.tree()), after);
+
+        // empty transactions are not recorded
+        tree.begin_transaction();
+        tree.commit_transaction();
+        assert!(tree.undo());
+        assert_eq!(slots(tree.tree()), before);
+
+        // failed operations are not recorded either, and new ones clear the redo log
+        let other_id = Tree::new().insert(Node::new(0), AsRoot).unwrap();
+        assert!(tree.move_node(&ids[4], ToParent(&other_id)).is_err());
+        assert!(tree.can_redo());
+        tree.replace_data(&ids[0], 10).unwrap();
+        assert!(!tree.can_redo());
+    }
+
+    #[test]
+    fn test_can_undo() {
+        let (tree, ids) = small_tree();
+        let mut tree = RecordedTree::from_tree(tree);
+
+        // an open transaction without any changes has nothing to undo
+        tree.begin_transaction();
+        assert!(!tree.can_undo());
+        assert!(!tree.undo());
+        assert!(!tree.can_undo());
+
+        tree.replace_data(&ids[1], 10).unwrap();
+        tree.begin_transaction();
+        assert!(tree.can_undo());
+        assert!(tree.undo());
+        assert_eq!(tree.tree().get(&ids[1]).unwrap().data(), &1);
+        assert!(!tree.can_undo());
+        assert!(!tree.undo());
+    }
+}
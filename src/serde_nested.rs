@@ -0,0 +1,616 @@
+//! A nested, human-readable serde representation for `Tree`s.
+//!
+//! The default `serde_support` representation mirrors the internals of a `Tree`, including its
+//! process-specific id, removed `Node`s and `NodeId`s waiting to be reused.  The functions in this
+//! module instead write the `Tree` recursively from its root, every `Node` as a struct with its
+//! `data` and its `children`:
+//!
+//! ```json
+//! { "data": 0, "children": [{ "data": 1, "children": [] }, { "data": 2, "children": [] }] }
+//! ```
+//!
+//! An empty `Tree` is written as a "none" value (`null` in JSON).  `Node`s that are not reachable
+//! from the root (orphans) are not written.  Deserializing builds a fresh `Tree` without any holes;
+//! `NodeId`s from the original `Tree` do not carry over.
+//!
+//! Use them through `#[serde(with = "id_tree::serde_nested")]`:
+//!
+//! ```
+//! extern crate id_tree;
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_json;
+//!
+//! use id_tree::*;
+//! use id_tree::InsertBehavior::*;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Document {
+//!     #[serde(with = "id_tree::serde_nested")]
+//!     outline: Tree<String>,
+//! }
+//!
+//! # fn main() {
+//! let mut outline = Tree::new();
+//! let root_id = outline.insert(Node::new("intro".to_string()), AsRoot).unwrap();
+//! outline.insert(Node::new("motivation".to_string()), UnderNode(&root_id)).unwrap();
+//!
+//! let json = serde_json::to_string(&Document { outline }).unwrap();
+//! assert_eq!(
+//!     json,
+//!     r#"{"outline":{"data":"intro","children":[{"data":"motivation","children":[]}]}}"#
+//! );
+//!
+//! let document: Document = serde_json::from_str(&json).unwrap();
+//! let root_id = document.outline.root_node_id().unwrap();
+//! assert_eq!(document.outline.get(root_id).unwrap().data(), "intro");
+//! # }
+//! ```
+//!
+//! #### Deep `Tree`s
+//! Deserializing inserts every `Node` into the new `Tree` as soon as it has been read, so
+//! `id_tree` does not build an intermediate nested value (or drop one recursively).  Recursion
+//! cannot be avoided altogether, though: serde's visitor model hands each nested value to a nested
+//! call, both here and inside the data format, so every level of `Node`s takes another few stack
+//! frames.
+//!
+//! The mitigation is a cap on the nesting.  `serialize` and `deserialize` stop with an error past
+//! `DEFAULT_MAX_DEPTH` levels of `Node`s instead of running out of stack, even with formats that
+//! have no recursion limit of their own.  `serialize_with_max_depth` and
+//! `deserialize_with_max_depth` take the cap as an argument (use them through
+//! `#[serde(serialize_with = "...", deserialize_with = "...")]` with a function of your own); a
+//! higher cap needs a correspondingly larger stack, such as that of a thread spawned with
+//! `std::thread::Builder::stack_size`.  Many formats have a limit of their own as well:
+//! `serde_json`, for example, rejects input nested deeper than 128 levels unless its recursion
+//! limit is disabled, and every `Node` takes two of them (its struct and its list of children).
+//! The default representation has no such limits.
+//!
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+
+use MoveBehavior;
+use Node;
+use NodeId;
+use Tree;
+
+///
+/// The deepest a `Node` can be nested (counting the root as the first level) for `serialize` and
+/// `deserialize`.  Deeper `Tree`s result in an error.  The limit leaves enough room for the nesting
+/// to fit into the default stack of a spawned thread, even in debug builds.
+///
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+///
+/// Serializes `tree` starting from its root `Node`.  See the module documentation for the format.
+///
+/// Fails if `Node`s are nested deeper than `DEFAULT_MAX_DEPTH` levels.
+///
+pub fn serialize<T, S>(tree: &Tree<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    serialize_with_max_depth(tree, serializer, DEFAULT_MAX_DEPTH)
+}
+
+///
+/// Serializes `tree` like `serialize`, but fails only if `Node`s are nested deeper than
+/// `max_depth` levels.
+///
+pub fn serialize_with_max_depth<T, S>(
+    tree: &Tree<T>,
+    serializer: S,
+    max_depth: usize,
+) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    tree.root_node_id()
+        .map(|node_id| NestedNode {
+            tree,
+            node_id,
+            depth: 1,
+            max_depth,
+        })
+        .serialize(serializer)
+}
+
+///
+/// Deserializes a `Tree` written by `serialize`.  See the module documentation for the format.
+///
+/// Fails if `Node`s are nested deeper than `DEFAULT_MAX_DEPTH` levels.
+///
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Tree<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    deserialize_with_max_depth(deserializer, DEFAULT_MAX_DEPTH)
+}
+
+///
+/// Deserializes a `Tree` like `deserialize`, but fails only if `Node`s are nested deeper than
+/// `max_depth` levels.
+///
+pub fn deserialize_with_max_depth<'de, T, D>(
+    deserializer: D,
+    max_depth: usize,
+) -> Result<Tree<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(TreeVisitor {
+        max_depth,
+        marker: PhantomData,
+    })
+}
+
+struct NestedNode<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    node_id: &'a NodeId,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a, T> Serialize for NestedNode<'a, T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.depth > self.max_depth {
+            return Err(ser::Error::custom(too_deep(self.max_depth)));
+        }
+        let node = self.tree.get_unsafe(self.node_id);
+
+        let mut state = serializer.serialize_struct("Node", 2)?;
+        state.serialize_field("data", node.data())?;
+        state.serialize_field(
+            "children",
+            &NestedChildren {
+                tree: self.tree,
+                node,
+                depth: self.depth + 1,
+                max_depth: self.max_depth,
+            },
+        )?;
+        state.end()
+    }
+}
+
+struct NestedChildren<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    node: &'a Node<T>,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a, T> Serialize for NestedChildren<'a, T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let tree = self.tree;
+        let depth = self.depth;
+        let max_depth = self.max_depth;
+        serializer.collect_seq(self.node.children().iter().map(|node_id| NestedNode {
+            tree,
+            node_id,
+            depth,
+            max_depth,
+        }))
+    }
+}
+
+struct TreeVisitor<T> {
+    max_depth: usize,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T> Visitor<'de> for TreeVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Tree<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a nested tree or none")
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Tree::new())
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Tree::new())
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut tree = Tree::new();
+        let root_id = NodeSeed {
+            tree: &mut tree,
+            depth: 1,
+            max_depth: self.max_depth,
+        }
+        .deserialize(deserializer)?;
+        tree.move_node(&root_id, MoveBehavior::ToRoot)
+            .expect("serde_nested: the root NodeId was just created by this Tree.");
+        Ok(tree)
+    }
+}
+
+///
+/// Deserializes one `Node` (and all of its descendants) straight into `tree`.  The `Node` itself
+/// is inserted after its children, which is what allows the fields to come in any order.
+///
+struct NodeSeed<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    depth: usize,
+    max_depth: usize,
+}
+
+const FIELDS: &[&str] = &["data", "children"];
+
+impl<'a, 'de, T> DeserializeSeed<'de> for NodeSeed<'a, T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = NodeId;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if self.depth > self.max_depth {
+            return Err(de::Error::custom(too_deep(self.max_depth)));
+        }
+        deserializer.deserialize_struct("Node", FIELDS, self)
+    }
+}
+
+impl<'a, T> NodeSeed<'a, T> {
+    fn finish(self, data: T, children: Vec<NodeId>) -> NodeId {
+        let node_id = self.tree.insert_new_node(Node::new(data));
+        for child_id in &children {
+            self.tree.set_as_parent_and_child(&node_id, child_id);
+        }
+        node_id
+    }
+}
+
+impl<'a, 'de, T> Visitor<'de> for NodeSeed<'a, T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = NodeId;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a Node with data and children")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let data = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let children = seq
+            .next_element_seed(ChildrenSeed {
+                tree: &mut *self.tree,
+                depth: self.depth + 1,
+                max_depth: self.max_depth,
+            })?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        Ok(self.finish(data, children))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut data = None;
+        let mut children = None;
+
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::Data => {
+                    if data.is_some() {
+                        return Err(de::Error::duplicate_field("data"));
+                    }
+                    data = Some(map.next_value()?);
+                }
+                Field::Children => {
+                    if children.is_some() {
+                        return Err(de::Error::duplicate_field("children"));
+                    }
+                    children = Some(map.next_value_seed(ChildrenSeed {
+                        tree: &mut *self.tree,
+                        depth: self.depth + 1,
+                        max_depth: self.max_depth,
+                    })?);
+                }
+            }
+        }
+
+        let data = data.ok_or_else(|| de::Error::missing_field("data"))?;
+        let children = children.ok_or_else(|| de::Error::missing_field("children"))?;
+
+        Ok(self.finish(data, children))
+    }
+}
+
+///
+/// Deserializes a sequence of `Node`s into `tree`, returning their `NodeId`s in order.
+///
+struct ChildrenSeed<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a, 'de, T> DeserializeSeed<'de> for ChildrenSeed<'a, T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Vec<NodeId>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'a, 'de, T> Visitor<'de> for ChildrenSeed<'a, T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Vec<NodeId>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of Nodes")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut children = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(child_id) = seq.next_element_seed(NodeSeed {
+            tree: &mut *self.tree,
+            depth: self.depth,
+            max_depth: self.max_depth,
+        })? {
+            children.push(child_id);
+        }
+        Ok(children)
+    }
+}
+
+fn too_deep(max_depth: usize) -> String {
+    format!("Nodes nested deeper than {} levels", max_depth)
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum Field {
+    Data,
+    Children,
+}
+
+#[cfg(test)]
+mod serde_nested_tests {
+    extern crate serde_json;
+
+    use serde::Deserialize;
+
+    use super::super::*;
+    use super::{deserialize_with_max_depth, serialize_with_max_depth, DEFAULT_MAX_DEPTH};
+    use InsertBehavior::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Wrapper {
+        #[serde(with = "super")]
+        tree: Tree<i32>,
+    }
+
+    fn to_json(tree: Tree<i32>) -> String {
+        serde_json::to_string(&Wrapper { tree }).unwrap()
+    }
+
+    fn from_json(json: &str) -> Result<Tree<i32>, serde_json::Error> {
+        serde_json::from_str::<Wrapper>(json).map(|wrapper| wrapper.tree)
+    }
+
+    fn pre_order(tree: &Tree<i32>) -> Vec<(i32, usize)> {
+        match tree.root_node_id() {
+            Some(root_id) => tree
+                .traverse_pre_order(root_id)
+                .unwrap()
+                .map(|node| (*node.data(), node.children().len()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // linked by hand, since validate_mutations would check the whole chain on every insert
+    fn chain(height: usize) -> Tree<i32> {
+        let mut tree = Tree::new();
+        let mut node_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        for i in 1..height {
+            let child_id = tree.insert_new_node(Node::new(i as i32));
+            tree.set_as_parent_and_child(&node_id, &child_id);
+            node_id = child_id;
+        }
+        tree
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+        tree.insert(Node::new(4), UnderNode(&node_2_id)).unwrap();
+        tree.remove_node(node_1_id, RemoveBehavior::LiftChildren)
+            .unwrap();
+
+        let expected = pre_order(&tree);
+        let json = to_json(tree);
+        assert_eq!(
+            json,
+            r#"{"tree":{"data":0,"children":[{"data":2,"children":[{"data":4,"children":[]}]},{"data":3,"children":[]}]}}"#
+        );
+
+        let tree = from_json(&json).unwrap();
+        assert_eq!(pre_order(&tree), expected);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_empty() {
+        let json = to_json(Tree::new());
+        assert_eq!(json, r#"{"tree":null}"#);
+
+        let tree = from_json(&json).unwrap();
+        assert_eq!(tree.root_node_id(), None);
+    }
+
+    #[test]
+    fn test_field_order_and_sequences() {
+        let tree = from_json(r#"{"tree":{"children":[[1,[]],{"data":2,"children":[]}],"data":0}}"#)
+            .unwrap();
+        assert_eq!(pre_order(&tree), vec![(0, 2), (1, 0), (2, 0)]);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(from_json(r#"{"tree":{"data":0}}"#).is_err());
+        assert!(from_json(r#"{"tree":{"children":[]}}"#).is_err());
+        assert!(from_json(r#"{"tree":{"data":0,"data":1,"children":[]}}"#).is_err());
+        assert!(
+            from_json(r#"{"tree":{"data":0,"children":[{"data":"one","children":[]}]}}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_deep() {
+        let tree = from_json(&to_json(chain(60))).unwrap();
+        assert_eq!(tree.height(), 60);
+        assert!(tree.validate().is_ok());
+
+        // past serde_json's recursion limit: an error, not a stack overflow
+        let mut json = String::from(r#"{"tree":"#);
+        for _ in 0..10_000 {
+            json.push_str(r#"{"data":0,"children":["#);
+        }
+        for _ in 0..10_000 {
+            json.push_str("]}");
+        }
+        json.push('}');
+        assert!(from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_max_depth() {
+        // serde_json without its recursion limit, so that only DEFAULT_MAX_DEPTH stands in the way
+        let from_unbounded_json = |json: &str| {
+            let mut deserializer = serde_json::Deserializer::from_str(json);
+            deserializer.disable_recursion_limit();
+            Wrapper::deserialize(&mut deserializer).map(|wrapper| wrapper.tree)
+        };
+
+        let json = to_json(chain(DEFAULT_MAX_DEPTH));
+        let tree = from_unbounded_json(&json).unwrap();
+        assert_eq!(pre_order(&tree).len(), DEFAULT_MAX_DEPTH);
+
+        let error = serde_json::to_string(&Wrapper {
+            tree: chain(DEFAULT_MAX_DEPTH + 1),
+        })
+        .unwrap_err();
+        assert!(error.to_string().contains("deeper than"), "{}", error);
+
+        // far deeper than the stack could take
+        let error = serde_json::to_string(&Wrapper {
+            tree: chain(100_001),
+        })
+        .unwrap_err();
+        assert!(error.to_string().contains("deeper than"), "{}", error);
+
+        let mut json = String::from(r#"{"tree":"#);
+        for _ in 0..100_001 {
+            json.push_str(r#"{"data":0,"children":["#);
+        }
+        for _ in 0..100_001 {
+            json.push_str("]}");
+        }
+        json.push('}');
+        let error = from_unbounded_json(&json).unwrap_err();
+        assert!(error.to_string().contains("deeper than"), "{}", error);
+    }
+
+    #[test]
+    fn test_custom_max_depth() {
+        let to_json = |tree: &Tree<i32>, max_depth| {
+            let mut json = Vec::new();
+            serialize_with_max_depth(tree, &mut serde_json::Serializer::new(&mut json), max_depth)
+                .map(|_| String::from_utf8(json).unwrap())
+        };
+        let from_json = |json: &str, max_depth| {
+            let mut deserializer = serde_json::Deserializer::from_str(json);
+            deserializer.disable_recursion_limit();
+            deserialize_with_max_depth::<i32, _>(&mut deserializer, max_depth)
+        };
+
+        // a lower cap
+        let error = to_json(&chain(4), 3).unwrap_err();
+        assert!(error.to_string().contains("deeper than 3"), "{}", error);
+        let json = to_json(&chain(4), 4).unwrap();
+        let error = from_json(&json, 3).unwrap_err();
+        assert!(error.to_string().contains("deeper than 3"), "{}", error);
+        assert_eq!(pre_order(&from_json(&json, 4).unwrap()).len(), 4);
+
+        // a higher one, given a stack to match
+        let max_depth = 4 * DEFAULT_MAX_DEPTH;
+        std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(move || {
+                let json = to_json(&chain(max_depth), max_depth).unwrap();
+                let tree = from_json(&json, max_depth).unwrap();
+                assert_eq!(pre_order(&tree).len(), max_depth);
+                assert!(tree.validate().is_ok());
+
+                let error = to_json(&chain(max_depth + 1), max_depth).unwrap_err();
+                assert!(error.to_string().contains("deeper than"), "{}", error);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
@@ -0,0 +1,410 @@
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use cursor::Cursor;
+use InsertBehavior::*;
+use Node;
+use NodeId;
+use ParseError;
+use ParseErrorKind;
+use Tree;
+
+impl<T: Display> Tree<T> {
+    ///
+    /// Writes the `Tree` as an S-expression, using the `Display` implementation of `T` for the
+    /// atoms.  See `Tree::to_sexpr_with`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: Tree<String> = Tree::from_sexpr("(a (b c d) e)").unwrap();
+    /// assert_eq!(tree.to_sexpr(), "(a (b c d) e)");
+    /// ```
+    ///
+    pub fn to_sexpr(&self) -> String {
+        self.to_sexpr_with(|data| data.to_string())
+    }
+}
+
+impl<T: FromStr> Tree<T> {
+    ///
+    /// Parses an S-expression into a `Tree`, using the `FromStr` implementation of `T` for the
+    /// atoms.  See `Tree::from_sexpr_with`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: Tree<i32> = Tree::from_sexpr("(1 (2 3) 4)").unwrap();
+    ///
+    /// let root_id = tree.root_node_id().unwrap();
+    /// let data: Vec<i32> = tree
+    ///     .traverse_pre_order(root_id)
+    ///     .unwrap()
+    ///     .map(|node| *node.data())
+    ///     .collect();
+    /// assert_eq!(data, vec![1, 2, 3, 4]);
+    ///
+    /// let error = Tree::<i32>::from_sexpr("(1 (2 x))").unwrap_err();
+    /// assert_eq!((error.line(), error.column()), (1, 7));
+    /// ```
+    ///
+    pub fn from_sexpr(input: &str) -> Result<Tree<T>, ParseError<T::Err>> {
+        Tree::from_sexpr_with(input, str::parse)
+    }
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Writes the `Tree` as an S-expression, turning the data of each `Node` into an atom with
+    /// the given closure.
+    ///
+    /// A `Node` with children is written as a list of its own atom followed by its children, and
+    /// a `Node` without children as just its atom.  Atoms are written in double quotes (with `\"`
+    /// and `\\` escaped) when they are empty or contain whitespace, parentheses, quotes or
+    /// backslashes.  Orphaned `Node`s are left out and an empty `Tree` gives an empty string.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<(&str, i32)> = Tree::new();
+    /// let root_id = tree.insert(Node::new(("add", 0)), AsRoot).unwrap();
+    /// tree.insert(Node::new(("x", 1)), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(("y z", 2)), UnderNode(&root_id)).unwrap();
+    ///
+    /// let sexpr = tree.to_sexpr_with(|&(name, index)| format!("{}#{}", name, index));
+    /// assert_eq!(sexpr, "(add#0 x#1 \"y z#2\")");
+    /// ```
+    ///
+    pub fn to_sexpr_with<F>(&self, f: F) -> String
+    where
+        F: Fn(&T) -> String,
+    {
+        let mut s = String::new();
+        self.write_sexpr_with(&mut s, f)
+            .expect("writing to a String cannot fail");
+        s
+    }
+
+    ///
+    /// Writes the `Tree` as an S-expression to the given writer.  See `Tree::to_sexpr_with`.
+    ///
+    pub fn write_sexpr_with<W, F>(&self, w: &mut W, f: F) -> fmt::Result
+    where
+        W: fmt::Write,
+        F: Fn(&T) -> String,
+    {
+        enum Step<'a> {
+            Open(&'a NodeId),
+            Close,
+            Space,
+        }
+
+        let mut stack = Vec::new();
+        if let Some(root_id) = self.root_node_id() {
+            stack.push(Step::Open(root_id));
+        }
+
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Open(node_id) => {
+                    let node = self.get_unsafe(node_id);
+                    if node.children().is_empty() {
+                        write_atom(w, &f(node.data()))?;
+                        continue;
+                    }
+
+                    w.write_char('(')?;
+                    write_atom(w, &f(node.data()))?;
+                    stack.push(Step::Close);
+                    for child_id in node.children().iter().rev() {
+                        stack.push(Step::Open(child_id));
+                        stack.push(Step::Space);
+                    }
+                }
+                Step::Close => w.write_char(')')?,
+                Step::Space => w.write_char(' ')?,
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Parses an S-expression into a `Tree`, turning each atom into the data of a `Node` with the
+    /// given closure.
+    ///
+    /// The first element of every list is the parent of the elements after it, so `(a (b c) d)`
+    /// is `a` with the children `b` and `d`, and `b` with the child `c`.  An atom on its own is a
+    /// `Node` without children, as is a list with a single atom.
+    ///
+    /// Atoms are separated by whitespace and parentheses, or written in double quotes, where
+    /// `\"`, `\\`, `\n`, `\t` and `\r` are escapes.  Empty (or all-whitespace) input gives an
+    /// empty `Tree`.  Parsing does not recurse.  Returns a `ParseError` with the line and column of
+    /// the first problem in the input.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree = Tree::from_sexpr_with("(\"a b\" \"c\\\"d\")", |atom| {
+    ///     Ok::<_, ()>(atom.to_uppercase())
+    /// })
+    /// .unwrap();
+    ///
+    /// let root_id = tree.root_node_id().unwrap();
+    /// assert_eq!(tree.get(root_id).unwrap().data(), "A B");
+    /// assert_eq!(tree.to_sexpr(), "(\"A B\" \"C\\\"D\")");
+    ///
+    /// let error = Tree::from_sexpr_with("(a b) c", |atom| Ok::<_, ()>(atom.to_string()))
+    ///     .unwrap_err();
+    /// assert_eq!(error.kind(), &ParseErrorKind::MultipleRoots);
+    /// assert_eq!((error.line(), error.column()), (1, 7));
+    /// ```
+    ///
+    pub fn from_sexpr_with<E, F>(input: &str, mut f: F) -> Result<Tree<T>, ParseError<E>>
+    where
+        F: FnMut(&str) -> Result<T, E>,
+    {
+        let mut tree = Tree::new();
+        let mut cursor = Cursor::new(input);
+        // the lists whose closing parenthesis has not been seen yet
+        let mut open: Vec<NodeId> = Vec::new();
+
+        loop {
+            cursor.skip_whitespace();
+            let is_list = match cursor.peek() {
+                None if open.is_empty() => break,
+                None => return Err(cursor.error(ParseErrorKind::UnexpectedEnd)),
+                Some(')') if open.is_empty() => {
+                    return Err(cursor.error(ParseErrorKind::UnexpectedCharacter));
+                }
+                Some(')') => {
+                    cursor.next();
+                    open.pop();
+                    continue;
+                }
+                Some(_) if open.is_empty() && tree.root_node_id().is_some() => {
+                    return Err(cursor.error(ParseErrorKind::MultipleRoots));
+                }
+                Some('(') => {
+                    cursor.next();
+                    cursor.skip_whitespace();
+                    true
+                }
+                Some(_) => false,
+            };
+
+            let (line, column) = cursor.position();
+            let atom = parse_atom(&mut cursor)?;
+            let data =
+                f(&atom).map_err(|e| ParseError::new(line, column, ParseErrorKind::Label(e)))?;
+
+            let behavior = match open.last() {
+                Some(parent_id) => UnderNode(parent_id),
+                None => AsRoot,
+            };
+            let node_id = tree
+                .insert(Node::new(data), behavior)
+                .expect("parsed parents are always in the Tree");
+            if is_list {
+                open.push(node_id);
+            }
+        }
+
+        Ok(tree)
+    }
+}
+
+///
+/// Whether a character can appear in an unquoted atom.
+///
+fn is_atom_char(c: char) -> bool {
+    !c.is_whitespace() && !"()\"".contains(c)
+}
+
+///
+/// Parses a quoted or unquoted atom, which has to be there.
+///
+fn parse_atom<E>(cursor: &mut Cursor) -> Result<String, ParseError<E>> {
+    match cursor.peek() {
+        Some('"') => {}
+        Some(c) if is_atom_char(c) => return Ok(cursor.take_while(is_atom_char).to_string()),
+        Some(_) => return Err(cursor.error(ParseErrorKind::UnexpectedCharacter)),
+        None => return Err(cursor.error(ParseErrorKind::UnexpectedEnd)),
+    }
+
+    let start = cursor.error(ParseErrorKind::UnexpectedEnd);
+    cursor.next();
+    let mut atom = String::new();
+    loop {
+        match cursor.next() {
+            Some('"') => return Ok(atom),
+            Some('\\') => {
+                let escaped = match cursor.peek() {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some(_) => return Err(cursor.error(ParseErrorKind::UnexpectedCharacter)),
+                    None => return Err(start),
+                };
+                cursor.next();
+                atom.push(escaped);
+            }
+            Some(c) => atom.push(c),
+            None => return Err(start),
+        }
+    }
+}
+
+///
+/// Writes an atom, quoting it if needed.
+///
+fn write_atom<W: fmt::Write>(w: &mut W, atom: &str) -> fmt::Result {
+    if !atom.is_empty() && atom.chars().all(|c| is_atom_char(c) && c != '\\') {
+        return w.write_str(atom);
+    }
+
+    w.write_char('"')?;
+    for c in atom.chars() {
+        match c {
+            '"' => w.write_str("\\\"")?,
+            '\\' => w.write_str("\\\\")?,
+            '\n' => w.write_str("\\n")?,
+            '\t' => w.write_str("\\t")?,
+            '\r' => w.write_str("\\r")?,
+            c => w.write_char(c)?,
+        }
+    }
+    w.write_char('"')
+}
+
+#[cfg(test)]
+mod sexpr_tests {
+    use super::super::*;
+
+    fn parse(input: &str) -> Tree<String> {
+        Tree::from_sexpr(input).unwrap()
+    }
+
+    fn error(input: &str) -> (usize, usize, ParseErrorKind<()>) {
+        let error = Tree::from_sexpr_with(input, |atom| match atom {
+            "bad" => Err(()),
+            atom => Ok(atom.to_string()),
+        })
+        .unwrap_err();
+        (error.line(), error.column(), error.into_kind())
+    }
+
+    #[test]
+    fn test_parse() {
+        let tree = parse("(a (b c d) e)");
+        let root_id = tree.root_node_id().unwrap();
+        let pre_order: Vec<&str> = tree
+            .traverse_pre_order(root_id)
+            .unwrap()
+            .map(|node| node.data().as_str())
+            .collect();
+        assert_eq!(pre_order, vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(tree.height(), 3);
+        assert!(tree.validate().is_ok());
+
+        assert_eq!(parse("a").to_sexpr(), "a");
+        assert_eq!(parse("( a )").to_sexpr(), "a");
+        assert_eq!(parse("(a\n  (b)\n  c)").to_sexpr(), "(a b c)");
+        assert!(parse(" \n ").root_node_id().is_none());
+    }
+
+    #[test]
+    fn test_quoting() {
+        let tree = parse("(\"\" \"a b\" \"q\\\"\\\\\\n\\t\\r\" x\\y)");
+        let root_id = tree.root_node_id().unwrap();
+        let children: Vec<&str> = tree
+            .children(root_id)
+            .unwrap()
+            .map(|node| node.data().as_str())
+            .collect();
+        assert_eq!(children, vec!["a b", "q\"\\\n\t\r", "x\\y"]);
+        assert_eq!(tree.get(root_id).unwrap().data(), "");
+
+        assert_eq!(
+            tree.to_sexpr(),
+            "(\"\" \"a b\" \"q\\\"\\\\\\n\\t\\r\" \"x\\\\y\")"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for input in &[
+            "",
+            "a",
+            "(a b)",
+            "(a (b c d) e)",
+            "(a (b (c (d e))) (f g) h)",
+            "(\"(\" \")\" \"\\\"\")",
+        ] {
+            let tree = parse(input);
+            assert_eq!(&tree.to_sexpr(), input);
+            assert_eq!(parse(&tree.to_sexpr()).to_sexpr(), tree.to_sexpr());
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(error("(a b"), (1, 5, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(error("(a b))"), (1, 6, ParseErrorKind::UnexpectedCharacter));
+        assert_eq!(error(")"), (1, 1, ParseErrorKind::UnexpectedCharacter));
+        assert_eq!(error("()"), (1, 2, ParseErrorKind::UnexpectedCharacter));
+        assert_eq!(
+            error("((a) b)"),
+            (1, 2, ParseErrorKind::UnexpectedCharacter)
+        );
+        assert_eq!(error("(\n"), (2, 1, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(error("a\nb"), (2, 1, ParseErrorKind::MultipleRoots));
+        assert_eq!(error("(a \"b c)"), (1, 4, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(
+            error("(a \"b\\x\")"),
+            (1, 7, ParseErrorKind::UnexpectedCharacter)
+        );
+        assert_eq!(error("(a\n  (b bad))"), (2, 6, ParseErrorKind::Label(())));
+    }
+
+    #[test]
+    fn test_from_str_and_display() {
+        let tree: Tree<f64> = Tree::from_sexpr("(1.5 2 (3 -4))").unwrap();
+        assert_eq!(tree.to_sexpr(), "(1.5 2 (3 -4))");
+
+        let error = Tree::<u8>::from_sexpr("(1 256)").unwrap_err();
+        assert_eq!((error.line(), error.column()), (1, 4));
+        match error.into_kind() {
+            ParseErrorKind::Label(_) => {}
+            kind => panic!("unexpected {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        // validate_mutations checks the whole Tree after every insert
+        let depth = if cfg!(feature = "validate_mutations") {
+            2_000
+        } else {
+            100_000
+        };
+        let mut input = String::new();
+        for _ in 0..depth {
+            input.push_str("(a ");
+        }
+        input.push('b');
+        input.push_str(&")".repeat(depth));
+
+        let tree = parse(&input);
+        let root_id = tree.root_node_id().unwrap();
+        assert_eq!(
+            tree.traverse_pre_order_ids(root_id).unwrap().count(),
+            depth + 1
+        );
+        assert_eq!(tree.to_sexpr(), input);
+    }
+}
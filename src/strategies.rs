@@ -0,0 +1,350 @@
+//! `proptest` strategies for generating `Tree`s of common shapes.
+//!
+//! Every strategy takes a strategy for the data of each `Node` and a size range for the number of
+//! `Node`s.  Shrinking removes `Node`s, so a failing case shrinks toward smaller trees of the same
+//! shape.  An empty size range start allows the empty `Tree`.
+//!
+//! ```
+//! extern crate id_tree;
+//! extern crate proptest;
+//!
+//! use id_tree::strategies;
+//! use proptest::prelude::*;
+//! use proptest::test_runner::TestRunner;
+//!
+//! # fn main() {
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&strategies::random_recursive(any::<u8>(), 1..50), |tree| {
+//!         prop_assert!(tree.root_node_id().is_some());
+//!         prop_assert!(tree.height() <= 50);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! # }
+//! ```
+//!
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+use proptest::arbitrary::{any_with, Arbitrary};
+use proptest::collection::{vec, SizeRange};
+use proptest::sample::Index;
+use proptest::strategy::{BoxedStrategy, Strategy};
+
+use Tree;
+
+///
+/// Generates random recursive trees: every `Node` after the root is attached to a parent chosen
+/// uniformly among the `Node`s generated before it.
+///
+/// This is the strategy used by `Tree`'s `Arbitrary` implementation.
+///
+pub fn random_recursive<S>(
+    element: S,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = Tree<S::Value>>
+where
+    S: Strategy,
+{
+    vec((element, proptest::arbitrary::any::<Index>()), size).prop_map(|nodes| {
+        Tree::from_preceding_parents(
+            nodes
+                .into_iter()
+                .enumerate()
+                .map(|(position, (data, parent))| {
+                    (
+                        data,
+                        Some(position).filter(|&p| p > 0).map(|p| parent.index(p)),
+                    )
+                })
+                .collect(),
+        )
+    })
+}
+
+///
+/// Generates balanced trees where every `Node` has `arity` children, filling each level from left
+/// to right before starting the next one.
+///
+/// # Panics
+/// Panics if `arity` is zero.
+///
+pub fn k_ary<S>(
+    element: S,
+    arity: usize,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = Tree<S::Value>>
+where
+    S: Strategy,
+{
+    assert!(arity > 0, "strategies::k_ary: arity has to be at least 1.");
+
+    by_position(element, size, move |position| (position - 1) / arity)
+}
+
+///
+/// Generates chains, where every `Node` is the only child of the one before it.
+///
+pub fn chain<S>(element: S, size: impl Into<SizeRange>) -> impl Strategy<Value = Tree<S::Value>>
+where
+    S: Strategy,
+{
+    by_position(element, size, |position| position - 1)
+}
+
+///
+/// Generates stars, where every `Node` besides the root is a child of the root.
+///
+pub fn star<S>(element: S, size: impl Into<SizeRange>) -> impl Strategy<Value = Tree<S::Value>>
+where
+    S: Strategy,
+{
+    by_position(element, size, |_| 0)
+}
+
+///
+/// Generates trees uniformly among all labeled trees of each size by decoding a random Prüfer
+/// sequence.  The `Node` labeled `0` becomes the root, and children are ordered by label.
+///
+pub fn prufer<S>(element: S, size: impl Into<SizeRange>) -> impl Strategy<Value = Tree<S::Value>>
+where
+    S: Strategy,
+{
+    // one index per node keeps the sequence valid while shrinking removes nodes, only the first
+    // `len - 2` of them are used
+    vec((element, proptest::arbitrary::any::<Index>()), size).prop_map(|nodes| {
+        let len = nodes.len();
+        let (data, indices): (Vec<_>, Vec<_>) = nodes.into_iter().unzip();
+        let sequence: Vec<usize> = indices
+            .iter()
+            .take(len.saturating_sub(2))
+            .map(|index| index.index(len))
+            .collect();
+
+        let neighbors = decode_prufer(len, &sequence);
+
+        // walk down from label 0 so that every parent comes before its children
+        let mut order = Vec::with_capacity(len);
+        let mut position_of = vec![None; len];
+        let mut queue: VecDeque<(usize, Option<usize>)> = VecDeque::new();
+        if len > 0 {
+            queue.push_back((0, None));
+        }
+        while let Some((label, parent_position)) = queue.pop_front() {
+            position_of[label] = Some(order.len());
+            order.push((label, parent_position));
+            for &neighbor in &neighbors[label] {
+                // in a tree the only neighbor seen before is the parent
+                if position_of[neighbor].is_none() {
+                    queue.push_back((neighbor, position_of[label]));
+                }
+            }
+        }
+
+        let mut data: Vec<Option<S::Value>> = data.into_iter().map(Some).collect();
+        Tree::from_preceding_parents(
+            order
+                .into_iter()
+                .map(|(label, parent)| (data[label].take().unwrap(), parent))
+                .collect(),
+        )
+    })
+}
+
+impl<T> Arbitrary for Tree<T>
+where
+    T: Arbitrary + Debug + 'static,
+{
+    type Parameters = T::Parameters;
+    type Strategy = BoxedStrategy<Tree<T>>;
+
+    ///
+    /// Generates random recursive trees of up to 32 `Node`s.  See `strategies::random_recursive`.
+    ///
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        random_recursive(any_with::<T>(args), 0..32).boxed()
+    }
+}
+
+///
+/// Generates trees where the parent of the `Node` at each position (after the root at position 0)
+/// only depends on that position.
+///
+fn by_position<S, F>(
+    element: S,
+    size: impl Into<SizeRange>,
+    parent_of: F,
+) -> impl Strategy<Value = Tree<S::Value>>
+where
+    S: Strategy,
+    F: Fn(usize) -> usize,
+{
+    vec(element, size).prop_map(move |data| {
+        Tree::from_preceding_parents(
+            data.into_iter()
+                .enumerate()
+                .map(|(position, data)| (data, Some(position).filter(|&p| p > 0).map(&parent_of)))
+                .collect(),
+        )
+    })
+}
+
+///
+/// Decodes a Prüfer sequence into the sorted neighbor lists of a tree with `len` labeled nodes.
+///
+fn decode_prufer(len: usize, sequence: &[usize]) -> Vec<Vec<usize>> {
+    let mut neighbors = vec![Vec::new(); len];
+    if len < 2 {
+        return neighbors;
+    }
+
+    let mut degree = vec![1; len];
+    for &label in sequence {
+        degree[label] += 1;
+    }
+
+    let mut leaves: BinaryHeap<Reverse<usize>> = (0..len)
+        .filter(|&label| degree[label] == 1)
+        .map(Reverse)
+        .collect();
+
+    let mut connect = |a: usize, b: usize| {
+        neighbors[a].push(b);
+        neighbors[b].push(a);
+    };
+
+    for &label in sequence {
+        let Reverse(leaf) = leaves
+            .pop()
+            .expect("a Prüfer sequence always leaves a leaf");
+        connect(leaf, label);
+        degree[label] -= 1;
+        if degree[label] == 1 {
+            leaves.push(Reverse(label));
+        }
+    }
+
+    let Reverse(first) = leaves.pop().unwrap();
+    let Reverse(second) = leaves.pop().unwrap();
+    connect(first, second);
+
+    for list in &mut neighbors {
+        list.sort();
+    }
+    neighbors
+}
+
+#[cfg(test)]
+mod strategies_tests {
+    use super::*;
+    use proptest::strategy::ValueTree;
+    use proptest::test_runner::TestRunner;
+
+    fn sample<S: Strategy>(strategy: S) -> Vec<S::Value> {
+        let mut runner = TestRunner::deterministic();
+        (0..64)
+            .map(|_| strategy.new_tree(&mut runner).unwrap().current())
+            .collect()
+    }
+
+    fn node_count<T>(tree: &Tree<T>) -> usize {
+        tree.root_node_id()
+            .map(|root_id| tree.traverse_pre_order_ids(root_id).unwrap().count())
+            .unwrap_or(0)
+    }
+
+    fn max_children<T>(tree: &Tree<T>) -> usize {
+        tree.root_node_id()
+            .map(|root_id| {
+                tree.traverse_pre_order(root_id)
+                    .unwrap()
+                    .map(|node| node.children().len())
+                    .max()
+                    .unwrap()
+            })
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn test_shapes() {
+        for tree in sample(random_recursive(0..10u8, 0..40)) {
+            assert!(tree.validate().is_ok());
+            assert!(node_count(&tree) < 40);
+        }
+
+        for tree in sample(k_ary(0..10u8, 3, 0..40)) {
+            assert!(tree.validate().is_ok());
+            assert!(max_children(&tree) <= 3);
+            let count = node_count(&tree);
+            // a full ternary tree of height h holds at least (3^(h-1) - 1) / 2 + 1 nodes
+            if count > 0 {
+                assert!((3usize.pow(tree.height() as u32 - 1) - 1) / 2 < count);
+            }
+        }
+
+        for tree in sample(chain(0..10u8, 1..40)) {
+            assert!(tree.validate().is_ok());
+            assert_eq!(tree.height(), node_count(&tree));
+        }
+
+        for tree in sample(star(0..10u8, 2..40)) {
+            assert!(tree.validate().is_ok());
+            assert_eq!(tree.height(), 2);
+            assert_eq!(max_children(&tree) + 1, node_count(&tree));
+        }
+
+        for tree in sample(prufer(0..10u8, 0..40)) {
+            assert!(tree.validate().is_ok());
+            assert!(node_count(&tree) < 40);
+        }
+    }
+
+    #[test]
+    fn test_prufer_all_nodes_connected() {
+        let mut runner = TestRunner::deterministic();
+        for len in 0..12 {
+            let tree = prufer(0..10u8, len)
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            assert_eq!(node_count(&tree), len);
+        }
+    }
+
+    #[test]
+    fn test_decode_prufer() {
+        // the example from the wikipedia article on Prüfer sequences, shifted to start at 0
+        let neighbors = decode_prufer(6, &[3, 3, 3, 4]);
+        assert_eq!(
+            neighbors,
+            vec![
+                vec![3],
+                vec![3],
+                vec![3],
+                vec![0, 1, 2, 4],
+                vec![3, 5],
+                vec![4]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shrinks_toward_smaller_trees() {
+        let mut runner = TestRunner::deterministic();
+        let mut value_tree = random_recursive(0..10u8, 10..40)
+            .new_tree(&mut runner)
+            .unwrap();
+
+        let mut count = node_count(&value_tree.current());
+        while value_tree.simplify() {
+            let next = node_count(&value_tree.current());
+            assert!(next <= count);
+            count = next;
+        }
+        assert_eq!(count, 10);
+    }
+}
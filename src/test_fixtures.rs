@@ -0,0 +1,111 @@
+use NodeId;
+use RemoveBehavior::*;
+use Tree;
+
+// a Tree collected from the position of each Node's parent and its data, along with the NodeIds
+// of the Nodes in the same order
+pub(crate) fn from_parents<T: Clone>(nodes: &[(Option<usize>, T)]) -> (Tree<T>, Vec<NodeId>) {
+    let tree: Tree<T> = nodes.iter().cloned().collect();
+    let ids = (0..nodes.len())
+        .map(|index| tree.new_node_id(index))
+        .collect();
+    (tree, ids)
+}
+
+//      0
+//     / \
+//    1   2
+//   / \
+//  3   4
+//
+// along with the NodeIds of the Nodes, in order of their data
+pub(crate) fn small_tree() -> (Tree<i32>, Vec<NodeId>) {
+    from_parents(&[
+        (None, 0),
+        (Some(0), 1),
+        (Some(0), 2),
+        (Some(1), 3),
+        (Some(1), 4),
+    ])
+}
+
+//        0
+//      / | \
+//     1  4  5
+//    / \     \
+//   2   3     6
+//             |
+//             7
+//
+// along with the NodeIds of the Nodes, in order of their data
+pub(crate) fn wide_tree() -> (Tree<i32>, Vec<NodeId>) {
+    from_parents(&[
+        (None, 0),
+        (Some(0), 1),
+        (Some(1), 2),
+        (Some(1), 3),
+        (Some(0), 4),
+        (Some(0), 5),
+        (Some(5), 6),
+        (Some(6), 7),
+    ])
+}
+
+//      0
+//      |
+//      1
+//      |
+//      2
+//
+// along with the NodeIds of the Nodes, in order of their data, including that of 3, which was
+// inserted under 0 and removed again
+pub(crate) fn with_removed_node() -> (Tree<i32>, Vec<NodeId>) {
+    let (mut tree, ids) = from_parents(&[(None, 0), (Some(0), 1), (Some(1), 2), (Some(0), 3)]);
+    tree.remove_node(ids[3].clone(), DropChildren).unwrap();
+    (tree, ids)
+}
+
+//      zero
+//     /    \
+//   one    two
+//    |
+//  three
+//
+// plus "five" -> "\"six\"", orphaned by removing "four" from under the root, along with the
+// NodeIds of all seven Nodes
+pub(crate) fn with_orphans() -> (Tree<&'static str>, Vec<NodeId>) {
+    let (mut tree, ids) = from_parents(&[
+        (None, "zero"),
+        (Some(0), "one"),
+        (Some(0), "two"),
+        (Some(1), "three"),
+        (Some(0), "four"),
+        (Some(4), "five"),
+        (Some(5), "\"six\""),
+    ]);
+    tree.remove_node(ids[4].clone(), OrphanChildren).unwrap();
+    (tree, ids)
+}
+
+pub(crate) type Slots = (
+    Option<NodeId>,
+    Vec<Option<(i32, Option<NodeId>, Vec<NodeId>)>>,
+);
+
+// the root and the data and links of every Node, slot for slot
+pub(crate) fn slots(tree: &Tree<i32>) -> Slots {
+    let nodes = tree
+        .nodes
+        .iter()
+        .map(|node| {
+            node.as_ref().map(|node| {
+                (
+                    *node.data(),
+                    node.parent().cloned(),
+                    node.children().clone(),
+                )
+            })
+        })
+        .collect();
+    (tree.root.clone(), nodes)
+}
@@ -0,0 +1,347 @@
+use std::cmp::Ordering;
+
+use recorded_tree::Change;
+use InsertBehavior;
+use MoveBehavior;
+use Node;
+use NodeId;
+use NodeIdError;
+use RemoveBehavior;
+use SwapBehavior;
+use Tree;
+
+///
+/// A batch of operations being applied to a `Tree` by `Tree::transaction`.
+///
+/// Every operation is applied to the `Tree` right away, while the state it replaces is kept aside
+/// until the transaction is over.  If the transaction fails, that state is put back in reverse
+/// order, leaving the `Tree` exactly as it was before the transaction started.
+///
+pub struct Transaction<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    changes: Vec<Change<T>>,
+    committed: bool,
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Applies a batch of operations to the `Tree` as a whole.
+    ///
+    /// The closure gets a `Transaction` through which to modify the `Tree`.  If it returns `Ok`,
+    /// its changes are kept.  If it returns `Err` (or panics), every change it made is rolled back
+    /// and the `Tree` is restored to its prior state: the same `Node`s in the same slots, with the
+    /// same `NodeId`s valid and the same free slots in the same order.  The `NodeId`s of `Node`s
+    /// inserted during a rolled back transaction stay invalid, even once their slots are reused.
+    ///
+    /// Observers registered with `Tree::add_observer` only hear about the changes once the
+    /// transaction is committed, and never about rolled back changes.
+    ///
+    /// Returns the `Result` returned by the closure.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::MoveBehavior::*;
+    /// use id_tree::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let other_id = Tree::new().insert(Node::new(2), AsRoot).unwrap();
+    ///
+    /// let result = tree.transaction(|tx| {
+    ///     tx.remove_node(child_id.clone(), DropChildren)?;
+    ///     tx.insert(Node::new(3), UnderNode(&root_id))?;
+    ///     // this NodeId is from another Tree, so everything above is undone
+    ///     tx.move_node(&root_id, ToParent(&other_id))
+    /// });
+    ///
+    /// assert_eq!(result, Err(NodeIdError::InvalidNodeIdForTree));
+    /// assert_eq!(tree.get(&child_id).unwrap().data(), &1);
+    /// assert_eq!(tree.get(&root_id).unwrap().children(), &vec![child_id]);
+    /// ```
+    ///
+    pub fn transaction<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut Transaction<'_, T>) -> Result<R, E>,
+    {
+        self.hold_events();
+        let mut transaction = Transaction {
+            tree: self,
+            changes: Vec::new(),
+            committed: false,
+        };
+
+        let result = f(&mut transaction);
+        transaction.committed = result.is_ok();
+
+        // dropping the Transaction rolls back the changes unless it was committed
+        drop(transaction);
+        result
+    }
+}
+
+impl<'a, T> Transaction<'a, T> {
+    ///
+    /// Returns an immutable reference to the `Tree` as it is at this point of the transaction.
+    ///
+    pub fn tree(&self) -> &Tree<T> {
+        self.tree
+    }
+
+    ///
+    /// Returns a `Some` value containing the `NodeId` of the root `Node` if it exists.  Otherwise a
+    /// `None` value is returned.
+    ///
+    pub fn root_node_id(&self) -> Option<&NodeId> {
+        self.tree.root_node_id()
+    }
+
+    ///
+    /// Get an immutable reference to a `Node`.
+    ///
+    /// Returns a `Result` containing the immutable reference or a `NodeIdError` if one occurred.
+    ///
+    pub fn get(&self, node_id: &NodeId) -> Result<&Node<T>, NodeIdError> {
+        self.tree.get(node_id)
+    }
+
+    ///
+    /// Inserts a new `Node` into the `Tree`.  See `Tree::insert` for more information.
+    ///
+    pub fn insert(
+        &mut self,
+        node: Node<T>,
+        behavior: InsertBehavior,
+    ) -> Result<NodeId, NodeIdError> {
+        self.tree
+            .recorded_insert(node, behavior, true, &mut self.changes)
+    }
+
+    ///
+    /// Removes a `Node` from the `Tree`.  See `Tree::remove_node` for more information.
+    ///
+    /// The removed `Node` (along with its descendants for `RemoveBehavior::DropChildren`) is kept
+    /// until the transaction is over in case it has to be put back, so it is not returned.
+    ///
+    pub fn remove_node(
+        &mut self,
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.tree
+            .recorded_remove_node(node_id, behavior, &mut self.changes)
+    }
+
+    ///
+    /// Moves a `Node` in the `Tree` to a new location.  See `Tree::move_node` for more
+    /// information.
+    ///
+    pub fn move_node(
+        &mut self,
+        node_id: &NodeId,
+        behavior: MoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.tree
+            .recorded_move_node(node_id, behavior, &mut self.changes)
+    }
+
+    ///
+    /// Swaps two `Node`s in the `Tree`.  See `Tree::swap_nodes` for more information.
+    ///
+    pub fn swap_nodes(
+        &mut self,
+        first_id: &NodeId,
+        second_id: &NodeId,
+        behavior: SwapBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.tree
+            .recorded_swap_nodes(first_id, second_id, behavior, &mut self.changes)
+    }
+
+    ///
+    /// Sorts the children of one node, in-place, using compare to compare the nodes.  See
+    /// `Tree::sort_children_by` for more information.
+    ///
+    pub fn sort_children_by<F>(&mut self, node_id: &NodeId, compare: F) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&Node<T>, &Node<T>) -> Ordering,
+    {
+        self.tree
+            .recorded_sort_children_by(node_id, compare, &mut self.changes)
+    }
+
+    ///
+    /// Moves the node to a position amongst sibling nodes.  See `Tree::make_nth_sibling` for more
+    /// information.
+    ///
+    pub fn make_nth_sibling(&mut self, node_id: &NodeId, pos: usize) -> Result<(), NodeIdError> {
+        self.tree
+            .recorded_make_nth_sibling(node_id, pos, &mut self.changes)
+    }
+
+    ///
+    /// Replaces the data of the `Node` specified by the `NodeId` given.
+    ///
+    /// The old data is kept until the transaction is over in case it has to be put back, so it is
+    /// not returned.
+    ///
+    pub fn replace_data(&mut self, node_id: &NodeId, data: T) -> Result<(), NodeIdError> {
+        self.tree
+            .recorded_replace_data(node_id, data, &mut self.changes)
+    }
+}
+
+impl<'a, T> Drop for Transaction<'a, T> {
+    fn drop(&mut self) {
+        if !self.committed && !self.changes.is_empty() {
+            while let Some(mut change) = self.changes.pop() {
+                change.apply(self.tree);
+            }
+            self.tree.validate_mutation();
+        }
+        self.tree.release_events(self.committed);
+    }
+}
+
+#[cfg(test)]
+mod transaction_tests {
+    use std::panic;
+
+    use super::super::*;
+
+    use test_fixtures::*;
+    use InsertBehavior::*;
+    use MoveBehavior::*;
+    use RemoveBehavior::*;
+    use SwapBehavior::*;
+
+    // the slots along with the free ones, in order
+    fn state(tree: &Tree<i32>) -> (Slots, Vec<NodeId>) {
+        (slots(tree), tree.free_ids.clone())
+    }
+
+    // leaves two free slots behind
+    fn with_free_slots() -> (Tree<i32>, Vec<NodeId>) {
+        let (mut tree, ids) = small_tree();
+        let removed_id = tree.insert(Node::new(-1), UnderNode(&ids[0])).unwrap();
+        tree.insert(Node::new(-2), UnderNode(&removed_id)).unwrap();
+        tree.remove_node(removed_id, DropChildren).unwrap();
+        (tree, ids)
+    }
+
+    #[test]
+    fn test_rollback_restores_exact_state() {
+        let (mut tree, ids) = with_free_slots();
+        let before = state(&tree);
+
+        let result: Result<(), &str> = tree.transaction(|tx| {
+            // reuses both free slots and then adds a new one
+            let a_id = tx.insert(Node::new(5), UnderNode(&ids[2])).unwrap();
+            let b_id = tx.insert(Node::new(6), UnderNode(&a_id)).unwrap();
+            tx.insert(Node::new(7), AsRoot).unwrap();
+            tx.remove_node(b_id, OrphanChildren).unwrap();
+            tx.remove_node(ids[1].clone(), LiftChildren).unwrap();
+            tx.move_node(&ids[3], ToParent(&ids[2])).unwrap();
+            tx.move_node(&ids[0], ToRoot).unwrap();
+            tx.swap_nodes(&ids[2], &ids[4], TakeChildren).unwrap();
+            tx.swap_nodes(&ids[3], &a_id, ChildrenOnly).unwrap();
+            tx.sort_children_by(&ids[0], |a, b| b.data().cmp(a.data()))
+                .unwrap();
+            tx.make_nth_sibling(&ids[4], 0).unwrap();
+            tx.replace_data(&ids[0], 10).unwrap();
+            tx.remove_node(ids[0].clone(), DropChildren).unwrap();
+            assert!(tx.tree().validate().is_ok());
+            Err("give up")
+        });
+
+        assert_eq!(result, Err("give up"));
+        assert_eq!(state(&tree), before);
+        assert!(tree.validate().is_ok());
+        for node_id in &ids {
+            assert!(tree.get(node_id).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_rolled_back_ids_stay_invalid() {
+        let (mut tree, ids) = with_free_slots();
+
+        let mut held = Vec::new();
+        let result: Result<(), ()> = tree.transaction(|tx| {
+            // reuses both free slots and then adds a new one
+            for data in 5..8 {
+                held.push(tx.insert(Node::new(data), UnderNode(&ids[2])).unwrap());
+            }
+            Err(())
+        });
+        assert!(result.is_err());
+
+        for data in 5..8 {
+            let new_id = tree
+                .insert(Node::new(data * 10), UnderNode(&ids[2]))
+                .unwrap();
+            assert!(held.iter().any(|held_id| held_id.index == new_id.index));
+            assert!(!held.contains(&new_id));
+        }
+        for held_id in &held {
+            assert_eq!(
+                tree.get(held_id).err(),
+                Some(NodeIdError::NodeIdNoLongerValid)
+            );
+        }
+    }
+
+    #[test]
+    fn test_commit() {
+        let (mut tree, ids) = with_free_slots();
+
+        let result = tree.transaction(|tx| {
+            let new_id = tx.insert(Node::new(5), UnderNode(&ids[3]))?;
+            tx.remove_node(ids[2].clone(), DropChildren)?;
+            tx.move_node(&ids[4], ToParent(&new_id))?;
+            Ok::<_, NodeIdError>(new_id)
+        });
+        let new_id = result.unwrap();
+
+        assert_eq!(tree.get(&ids[4]).unwrap().parent(), Some(&new_id));
+        assert_eq!(
+            tree.get(&ids[2]).err(),
+            Some(NodeIdError::NodeIdNoLongerValid)
+        );
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn test_failed_operation() {
+        let (mut tree, ids) = with_free_slots();
+        let before = state(&tree);
+
+        let result = tree.transaction(|tx| {
+            tx.replace_data(&ids[4], 40)?;
+            tx.move_node(&ids[1], ToParent(&ids[4]))?;
+            // removing the same Node twice fails
+            tx.remove_node(ids[4].clone(), DropChildren)?;
+            tx.remove_node(ids[4].clone(), DropChildren)
+        });
+
+        assert_eq!(result, Err(NodeIdError::NodeIdNoLongerValid));
+        assert_eq!(state(&tree), before);
+    }
+
+    #[test]
+    fn test_panic_rolls_back() {
+        let (mut tree, ids) = with_free_slots();
+        let before = state(&tree);
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            tree.transaction(|tx| -> Result<(), ()> {
+                tx.remove_node(ids[1].clone(), DropChildren).unwrap();
+                panic!("oops");
+            })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(state(&tree), before);
+    }
+}
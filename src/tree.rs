@@ -1,5 +1,8 @@
 use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::iter::FromIterator;
 
+use super::observers::Observers;
 use super::snowflake::ProcessUniqueId;
 use super::*;
 
@@ -125,16 +128,22 @@ impl<T> TreeBuilder<T> {
             id: tree_id,
             root: None,
             nodes: Vec::with_capacity(self.node_capacity),
+            generations: Vec::with_capacity(self.node_capacity),
+            next_generations: Vec::with_capacity(self.node_capacity),
             free_ids: Vec::with_capacity(self.swap_capacity),
+            observers: Observers::default(),
         };
 
         if self.root.is_some() {
             let node_id = NodeId {
-                tree_id: tree_id,
+                tree_id,
                 index: 0,
+                generation: 0,
             };
 
             tree.nodes.push(self.root.take());
+            let generation = tree.issue_generation(0);
+            tree.generations.push(generation);
             tree.root = Some(node_id);
         }
 
@@ -142,24 +151,38 @@ impl<T> TreeBuilder<T> {
     }
 }
 
+impl<T> Default for TreeBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 ///
 /// A tree structure consisting of `Node`s.
 ///
 /// # Panics
 /// While it is highly unlikely, any function that takes a `NodeId` _can_ `panic`.  This, however,
 /// should only happen due to improper `NodeId` management within `id_tree` and should have nothing
-/// to do with the library user's code.
+/// to do with the library user's code.  Invalid `NodeId`s and requests that would break the
+/// structure of the `Tree` are reported with a `NodeIdError` instead.
 ///
 /// **If this ever happens please report the issue.** `Panic`s are not expected behavior for this
 /// library, but they can happen due to bugs.
 ///
 #[derive(Debug)]
-#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", derive(Serialize))]
 pub struct Tree<T> {
-    id: ProcessUniqueId,
-    root: Option<NodeId>,
+    pub(crate) id: ProcessUniqueId,
+    pub(crate) root: Option<NodeId>,
     pub(crate) nodes: Vec<Option<Node<T>>>,
-    free_ids: Vec<NodeId>,
+    // the generation of each slot in `nodes`, which goes up every time a Node is removed from it
+    pub(crate) generations: Vec<u32>,
+    // the first generation of each slot that has not been handed out yet, which never goes down
+    // (not even when a removal is undone) and can outlive slots that were rolled back
+    pub(crate) next_generations: Vec<u32>,
+    pub(crate) free_ids: Vec<NodeId>,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub(crate) observers: Observers<T>,
 }
 
 impl<T> Tree<T> {
@@ -249,12 +272,75 @@ impl<T> Tree<T> {
                          invalid NodeId.",
                     ));
                 }
-                self.insert_with_parent(node, parent_id)
+                let result = self.insert_with_parent(node, parent_id);
+                self.validate_mutation();
+                if let Ok(ref new_id) = result {
+                    self.notify_inserted(new_id);
+                }
+                result
+            }
+            InsertBehavior::AsRoot => {
+                let old_root = self.root.clone();
+                let old_placements = self.placements(&old_root.iter().collect::<Vec<_>>());
+                let new_root_id = self.set_root(node);
+                self.validate_mutation();
+                self.notify_inserted(&new_root_id);
+                self.notify_moved(old_placements);
+                Ok(new_root_id)
             }
-            InsertBehavior::AsRoot => Ok(self.set_root(node)),
         }
     }
 
+    ///
+    /// Inserts a new `Node` for each piece of data as the last children of the `Node` specified by
+    /// the given `NodeId`, in order.
+    ///
+    /// Returns a `Result` containing the `NodeId`s of the inserted `Node`s or a `NodeIdError` if
+    /// the given `NodeId` is invalid, in which case nothing is inserted.  See also the `Extend`
+    /// implementation, which takes a parent for each piece of data.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// let child_ids = tree.extend_children(&root_id, 1..4).unwrap();
+    ///
+    /// assert_eq!(tree.children_ids(&root_id).unwrap().cloned().collect::<Vec<_>>(), child_ids);
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<i32>>(), vec![0, 1, 2, 3]);
+    /// ```
+    ///
+    pub fn extend_children<I>(
+        &mut self,
+        parent_id: &NodeId,
+        data: I,
+    ) -> Result<Vec<NodeId>, NodeIdError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let (is_valid, error) = self.is_valid_node_id(parent_id);
+        if !is_valid {
+            return Err(error.expect(
+                "Tree::extend_children: Missing an error value but found an invalid NodeId.",
+            ));
+        }
+
+        let data = data.into_iter();
+        let mut child_ids = Vec::with_capacity(data.size_hint().0);
+        for child_data in data {
+            let child_id = self.insert_new_node(Node::new(child_data));
+            self.set_as_parent_and_child(parent_id, &child_id);
+            child_ids.push(child_id);
+        }
+        self.validate_mutation();
+        for child_id in &child_ids {
+            self.notify_inserted(child_id);
+        }
+        Ok(child_ids)
+    }
+
     ///
     /// Sets the root of the `Tree`.
     ///
@@ -333,6 +419,35 @@ impl<T> Tree<T> {
         }
     }
 
+    ///
+    /// Replaces the data of the `Node` specified by the `NodeId` given.
+    ///
+    /// Unlike replacing the data through `get_mut`, this is reported to the `Tree`'s observers as a
+    /// `TreeEvent::DataChanged`.
+    ///
+    /// Returns a `Result` containing the old data or a `NodeIdError` if one occurred.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    ///
+    /// assert_eq!(tree.replace_data(&root_id, 2), Ok(1));
+    /// assert_eq!(tree.get(&root_id).unwrap().data(), &2);
+    /// ```
+    ///
+    pub fn replace_data(&mut self, node_id: &NodeId, data: T) -> Result<T, NodeIdError> {
+        let old_data = self.get_mut(node_id)?.replace_data(data);
+        if self.is_observed() {
+            self.notify(TreeEvent::DataChanged {
+                node_id: node_id.clone(),
+            });
+        }
+        Ok(old_data)
+    }
+
     /// Remove a `Node` from the `Tree`.  The `RemoveBehavior` provided determines what happens to
     /// the removed `Node`'s children.
     ///
@@ -380,11 +495,17 @@ impl<T> Tree<T> {
             ));
         }
 
-        match behavior {
+        let removal = self.pending_removal(&node_id, behavior);
+        let result = match behavior {
             RemoveBehavior::DropChildren => self.remove_node_drop_children(node_id),
             RemoveBehavior::LiftChildren => self.remove_node_lift_children(node_id),
             RemoveBehavior::OrphanChildren => self.remove_node_orphan_children(node_id),
+        };
+        self.validate_mutation();
+        if let (true, Some(removal)) = (result.is_ok(), removal) {
+            self.notify_removed(removal);
         }
+        result
     }
 
     ///
@@ -430,6 +551,11 @@ impl<T> Tree<T> {
 
     /// Moves a `Node` in the `Tree` to a new location based upon the `MoveBehavior` provided.
     ///
+    /// Returns an empty `Result` containing a `NodeIdError` if one occurred.  Moving a `Node`
+    /// under itself returns `NodeIdError::WouldCreateCycle`, while moving the root `Node` to the
+    /// root does nothing.  Moving the root `Node` under an orphaned `Node` makes the top of that
+    /// orphan's branch the new root.
+    ///
     /// ```
     /// use id_tree::*;
     /// use id_tree::InsertBehavior::*;
@@ -461,7 +587,8 @@ impl<T> Tree<T> {
             ));
         }
 
-        match behavior {
+        let old_placements = self.pending_moves(node_id, &behavior);
+        let result = match behavior {
             MoveBehavior::ToRoot => self.move_node_to_root(node_id),
             MoveBehavior::ToParent(parent_id) => {
                 let (is_valid, error) = self.is_valid_node_id(parent_id);
@@ -471,9 +598,17 @@ impl<T> Tree<T> {
                          an invalid NodeId.",
                     ));
                 }
+                if node_id == parent_id {
+                    return Err(NodeIdError::WouldCreateCycle);
+                }
                 self.move_node_to_parent(node_id, parent_id)
             }
+        };
+        self.validate_mutation();
+        if result.is_ok() {
+            self.notify_moved(old_placements);
         }
+        result
     }
 
     /// Moves a `Node` inside a `Tree` to a new parent leaving all children in their place.
@@ -529,6 +664,16 @@ impl<T> Tree<T> {
             }
 
             self.set_as_parent_and_child(parent_id, node_id);
+
+            if self.root.as_ref() == Some(node_id) {
+                // the root was moved under an orphaned Node, so the top of that Node's branch
+                // is the new root.
+                let mut new_root_id = parent_id.clone();
+                while let Some(parent_id) = self.get_unsafe(&new_root_id).parent().cloned() {
+                    new_root_id = parent_id;
+                }
+                self.root = Some(new_root_id);
+            }
         }
 
         Ok(())
@@ -539,6 +684,10 @@ impl<T> Tree<T> {
     ///
     fn move_node_to_root(&mut self, node_id: &NodeId) -> Result<(), NodeIdError> {
         let old_root = self.root.clone();
+        if old_root.as_ref() == Some(node_id) {
+            // already the root, nothing to do
+            return Ok(());
+        }
 
         if let Some(parent_id) = self.get_unsafe(node_id).parent().cloned() {
             self.detach_from_parent(&parent_id, node_id);
@@ -594,10 +743,13 @@ impl<T> Tree<T> {
             ));
         }
 
+        let old_order = self.children_order(node_id);
         let mut children = self.get_mut_unsafe(node_id).take_children();
         children.sort_by(|a, b| compare(self.get_unsafe(a), self.get_unsafe(b)));
         self.get_mut_unsafe(node_id).set_children(children);
 
+        self.validate_mutation();
+        self.notify_reordered(node_id, old_order);
         Ok(())
     }
 
@@ -638,10 +790,13 @@ impl<T> Tree<T> {
             ));
         }
 
+        let old_order = self.children_order(node_id);
         let mut children = self.get_mut_unsafe(node_id).take_children();
         children.sort_by_key(|a| self.get_unsafe(a).data());
         self.get_mut_unsafe(node_id).set_children(children);
 
+        self.validate_mutation();
+        self.notify_reordered(node_id, old_order);
         Ok(())
     }
 
@@ -688,19 +843,27 @@ impl<T> Tree<T> {
             ));
         }
 
+        let old_order = self.children_order(node_id);
         let mut children = self.get_mut_unsafe(node_id).take_children();
         children.sort_by_key(|a| f(self.get_unsafe(a)));
         self.get_mut_unsafe(node_id).set_children(children);
 
+        self.validate_mutation();
+        self.notify_reordered(node_id, old_order);
         Result::Ok(())
     }
 
     ///
-    /// Moves the node to a position amongst sibling nodes. If the `pos` provided is too large then
-    /// the node in question will be placed after all of its other siblings.
+    /// Moves the node to a position amongst sibling nodes.  Use `make_last_sibling` to place it
+    /// after all of its other siblings.
     ///
     /// Any children will remain attached to this node.
     ///
+    /// Returns an empty `Result` containing a `NodeIdError` if one occurred.  Calling this on a
+    /// `Node` without a parent returns `NodeIdError::NotASibling`, and a `pos` that is not less
+    /// than the number of siblings (counting the node itself) returns
+    /// `NodeIdError::PositionOutOfRange`.
+    ///
     /// ```
     /// use id_tree::*;
     /// use id_tree::InsertBehavior::*;
@@ -726,44 +889,37 @@ impl<T> Tree<T> {
     /// for (child, expected) in my_tree.children(&root_id).unwrap().zip(expected_values.iter()) {
     ///     assert_eq!(child.data(), expected);
     /// }
+    ///
+    /// assert_eq!(
+    ///     my_tree.make_nth_sibling(&c1, 4),
+    ///     Err(NodeIdError::PositionOutOfRange)
+    /// );
     /// ```
     ///
     pub fn make_nth_sibling(&mut self, node: &NodeId, pos: usize) -> Result<(), NodeIdError> {
         let parent = self
             .get(node)?
             .parent()
-            .ok_or(NodeIdError::NodeIdNoLongerValid)?
+            .ok_or(NodeIdError::NotASibling)?
             .clone();
 
-        let num_children = self.children_ids(&parent)?.count();
-        let pos = pos.min(num_children - 1);
+        if pos >= self.get_unsafe(&parent).children().len() {
+            return Err(NodeIdError::PositionOutOfRange);
+        }
+
+        let old_order = self.children_order(&parent);
+        let children = self.get_mut_unsafe(&parent).children_mut();
 
-        // First determine the current index that the node has
         // unwrap should not be reachable, since we are searching under node's
         // own parent, barring bugs in id_tree
-        let mut current_pos = self
-            .children_ids(&parent)?
-            .enumerate()
-            .find_map(|(i, n)| if n == node { Some(i) } else { None })
-            .unwrap();
+        let current_pos = children.iter().position(|id| id == node).unwrap();
 
-        while current_pos != pos {
-            let pos_to_swap = if current_pos < pos {
-                current_pos + 1
-            } else if current_pos > pos {
-                current_pos - 1
-            } else {
-                break;
-            };
-            let node_to_swap = self
-                .children_ids(&parent)?
-                .nth(pos_to_swap)
-                .unwrap()
-                .clone();
-            self.swap_nodes(node, &node_to_swap, SwapBehavior::TakeChildren)?;
-            current_pos = pos_to_swap;
-        }
+        // shift the siblings in between over by one instead of swapping our way there
+        let id = children.remove(current_pos);
+        children.insert(pos, id);
 
+        self.validate_mutation();
+        self.notify_reordered(&parent, old_order);
         Ok(())
     }
 
@@ -807,9 +963,13 @@ impl<T> Tree<T> {
         }
 
         let mut moved = false;
-        if let Some(parent_id) = self.get(node_id)?.parent().cloned() {
+        let parent_id = self.get(node_id)?.parent().cloned();
+        let old_order = parent_id
+            .as_ref()
+            .and_then(|parent_id| self.children_order(parent_id));
+        if let Some(ref parent_id) = parent_id {
             let parent = self
-                .get_mut(&parent_id)
+                .get_mut(parent_id)
                 .expect("Tree::make_first_sibling: invalid parent id");
             let mut position = parent.children.iter().position(|id| id == node_id).unwrap();
             moved = position > 0;
@@ -818,14 +978,19 @@ impl<T> Tree<T> {
                 position -= 1;
             }
         }
+        self.validate_mutation();
+        if let Some(ref parent_id) = parent_id {
+            self.notify_reordered(parent_id, old_order);
+        }
         Ok(moved)
     }
 
-    /// Puts the node in the last position relative to other sibling nodes.
+    /// Puts the node in the last position relative to other sibling nodes.  Unlike
+    /// `make_nth_sibling`, this does not need to know how many siblings there are.
     ///
     /// Any children will remain attached to this node.
     ///
-    /// Returns false if the node was already the first node or the root node.
+    /// Returns false if the node was already the last node or the root node.
     ///
     /// ```
     /// use id_tree::*;
@@ -861,9 +1026,13 @@ impl<T> Tree<T> {
         }
 
         let mut moved = false;
-        if let Some(parent_id) = self.get(node_id)?.parent().cloned() {
+        let parent_id = self.get(node_id)?.parent().cloned();
+        let old_order = parent_id
+            .as_ref()
+            .and_then(|parent_id| self.children_order(parent_id));
+        if let Some(ref parent_id) = parent_id {
             let parent = self
-                .get_mut(&parent_id)
+                .get_mut(parent_id)
                 .expect("Tree::make_last_sibling: invalid parent id");
             let mut position = parent.children.iter().position(|id| id == node_id).unwrap();
             let last_position = parent.children.len() - 1;
@@ -873,6 +1042,10 @@ impl<T> Tree<T> {
                 position += 1;
             }
         }
+        self.validate_mutation();
+        if let Some(ref parent_id) = parent_id {
+            self.notify_reordered(parent_id, old_order);
+        }
         Ok(moved)
     }
 
@@ -883,7 +1056,7 @@ impl<T> Tree<T> {
     /// This keeps the positions of the `Node`s in their parents' children collection.
     ///
     /// Returns an empty `Result` containing a `NodeIdError` if one occurred on either provided
-    /// `NodeId`.
+    /// `NodeId`.  Swapping a `Node` with itself returns `NodeIdError::SameNode`.
     ///
     /// ```
     /// use id_tree::*;
@@ -922,11 +1095,30 @@ impl<T> Tree<T> {
                 .expect("Tree::swap_nodes: Missing an error value but found an invalid NodeId."));
         }
 
-        match behavior {
+        if first_id == second_id {
+            return Err(NodeIdError::SameNode);
+        }
+
+        let old_placements = self.placements(&[first_id, second_id]);
+        let result = match behavior {
             SwapBehavior::TakeChildren => self.swap_nodes_take_children(first_id, second_id),
             SwapBehavior::LeaveChildren => self.swap_nodes_leave_children(first_id, second_id),
             SwapBehavior::ChildrenOnly => self.swap_nodes_children_only(first_id, second_id),
+        };
+        self.validate_mutation();
+        if let (true, [(_, first_from), (_, second_from)]) = (result.is_ok(), &old_placements[..]) {
+            let event = TreeEvent::Swapped {
+                first_id: first_id.clone(),
+                second_id: second_id.clone(),
+                behavior,
+                first_from: first_from.clone(),
+                first_to: self.placement(first_id),
+                second_from: second_from.clone(),
+                second_to: self.placement(second_id),
+            };
+            self.notify(event);
         }
+        result
     }
 
     /// Swaps two `Node`s including their children given their `NodeId`s.
@@ -963,8 +1155,8 @@ impl<T> Tree<T> {
 
             self.detach_from_parent(&lower_parent_id, lower_id);
 
-            if upper_parent_id.is_some() {
-                self.get_mut_unsafe(upper_parent_id.as_ref().unwrap())
+            if let Some(ref upper_parent_id) = upper_parent_id {
+                self.get_mut_unsafe(upper_parent_id)
                     .replace_child(upper_id.clone(), lower_id.clone());
             } else if self.root.as_ref() == Some(upper_id) {
                 self.root = Some(lower_id.clone());
@@ -976,49 +1168,48 @@ impl<T> Tree<T> {
         } else {
             // just across
 
-            let is_same_parent =
-                self.get_unsafe(first_id).parent() == self.get_unsafe(second_id).parent();
+            let first_parent_id = self.get_unsafe(first_id).parent().cloned();
+            let second_parent_id = self.get_unsafe(second_id).parent().cloned();
 
-            if is_same_parent {
-                let parent_id = self.get_unsafe(first_id).parent().cloned();
-                if let Some(parent_id) = parent_id {
+            if first_parent_id == second_parent_id {
+                if let Some(parent_id) = first_parent_id {
                     // same parent
-                    // get indices
                     let parent = self.get_mut_unsafe(&parent_id);
                     let first_index = parent
                         .children()
                         .iter()
-                        .enumerate()
-                        .find(|&(_, id)| id == first_id)
-                        .unwrap()
-                        .0;
+                        .position(|id| id == first_id)
+                        .unwrap();
                     let second_index = parent
                         .children()
                         .iter()
-                        .enumerate()
-                        .find(|&(_, id)| id == second_id)
-                        .unwrap()
-                        .0;
+                        .position(|id| id == second_id)
+                        .unwrap();
 
                     parent.children_mut().swap(first_index, second_index);
                 } else {
-                    // swapping the root with itself??
+                    // neither has a parent, but one of them might be the root
+                    self.swap_root(first_id, second_id);
                 }
             } else {
-                let first_parent_id = self.get_unsafe(first_id).parent().cloned().unwrap();
-                let second_parent_id = self.get_unsafe(second_id).parent().cloned().unwrap();
-
                 // replace parents
                 self.get_mut_unsafe(first_id)
-                    .set_parent(Some(second_parent_id.clone()));
+                    .set_parent(second_parent_id.clone());
                 self.get_mut_unsafe(second_id)
-                    .set_parent(Some(first_parent_id.clone()));
+                    .set_parent(first_parent_id.clone());
 
                 // change children
-                self.get_mut_unsafe(&first_parent_id)
-                    .replace_child(first_id.clone(), second_id.clone());
-                self.get_mut_unsafe(&second_parent_id)
-                    .replace_child(second_id.clone(), first_id.clone());
+                if let Some(ref first_parent_id) = first_parent_id {
+                    self.get_mut_unsafe(first_parent_id)
+                        .replace_child(first_id.clone(), second_id.clone());
+                }
+                if let Some(ref second_parent_id) = second_parent_id {
+                    self.get_mut_unsafe(second_parent_id)
+                        .replace_child(second_id.clone(), first_id.clone());
+                }
+
+                // one of them had no parent, so it might have been the root
+                self.swap_root(first_id, second_id);
             }
         }
 
@@ -1184,6 +1375,9 @@ impl<T> Tree<T> {
             if upper_id == &lower_parent {
                 // direct child
                 upper_children.retain(|id| id != lower_id);
+            } else {
+                // lower is about to become a child of upper, so its old parent has to let go
+                self.detach_from_parent(&lower_parent, lower_id);
             }
 
             //swap children of these nodes
@@ -1251,7 +1445,7 @@ impl<T> Tree<T> {
     /// assert!(ancestors.next().is_none());
     /// ```
     ///
-    pub fn ancestors(&self, node_id: &NodeId) -> Result<Ancestors<T>, NodeIdError> {
+    pub fn ancestors(&self, node_id: &NodeId) -> Result<Ancestors<'_, T>, NodeIdError> {
         let (is_valid, error) = self.is_valid_node_id(node_id);
         if !is_valid {
             return Err(error
@@ -1280,7 +1474,7 @@ impl<T> Tree<T> {
     /// assert!(ancestor_ids.next().is_none());
     /// ```
     ///
-    pub fn ancestor_ids(&self, node_id: &NodeId) -> Result<AncestorIds<T>, NodeIdError> {
+    pub fn ancestor_ids(&self, node_id: &NodeId) -> Result<AncestorIds<'_, T>, NodeIdError> {
         let (is_valid, error) = self.is_valid_node_id(node_id);
         if !is_valid {
             return Err(error.expect(
@@ -1311,7 +1505,7 @@ impl<T> Tree<T> {
     /// assert!(children.next().is_none());
     /// ```
     ///
-    pub fn children(&self, node_id: &NodeId) -> Result<Children<T>, NodeIdError> {
+    pub fn children(&self, node_id: &NodeId) -> Result<Children<'_, T>, NodeIdError> {
         let (is_valid, error) = self.is_valid_node_id(node_id);
         if !is_valid {
             return Err(
@@ -1341,7 +1535,7 @@ impl<T> Tree<T> {
     /// assert!(children_ids.next().is_none());
     /// ```
     ///
-    pub fn children_ids(&self, node_id: &NodeId) -> Result<ChildrenIds, NodeIdError> {
+    pub fn children_ids(&self, node_id: &NodeId) -> Result<ChildrenIds<'_>, NodeIdError> {
         let (is_valid, error) = self.is_valid_node_id(node_id);
         if !is_valid {
             return Err(error.expect(
@@ -1375,7 +1569,7 @@ impl<T> Tree<T> {
     pub fn traverse_pre_order(
         &self,
         node_id: &NodeId,
-    ) -> Result<PreOrderTraversal<T>, NodeIdError> {
+    ) -> Result<PreOrderTraversal<'_, T>, NodeIdError> {
         let (is_valid, error) = self.is_valid_node_id(node_id);
         if !is_valid {
             return Err(error.expect(
@@ -1409,8 +1603,8 @@ impl<T> Tree<T> {
     pub fn traverse_pre_order_ids(
         &self,
         node_id: &NodeId,
-    ) -> Result<PreOrderTraversalIds<T>, NodeIdError> {
-        let (is_valid, error) = self.is_valid_node_id(&node_id);
+    ) -> Result<PreOrderTraversalIds<'_, T>, NodeIdError> {
+        let (is_valid, error) = self.is_valid_node_id(node_id);
         if !is_valid {
             return Err(error.expect(
                 "Tree::traverse_pre_order_ids: Missing an error value but found an invalid NodeId.",
@@ -1443,7 +1637,7 @@ impl<T> Tree<T> {
     pub fn traverse_post_order(
         &self,
         node_id: &NodeId,
-    ) -> Result<PostOrderTraversal<T>, NodeIdError> {
+    ) -> Result<PostOrderTraversal<'_, T>, NodeIdError> {
         let (is_valid, error) = self.is_valid_node_id(node_id);
         if !is_valid {
             return Err(error.expect(
@@ -1511,7 +1705,7 @@ impl<T> Tree<T> {
     pub fn traverse_level_order(
         &self,
         node_id: &NodeId,
-    ) -> Result<LevelOrderTraversal<T>, NodeIdError> {
+    ) -> Result<LevelOrderTraversal<'_, T>, NodeIdError> {
         let (is_valid, error) = self.is_valid_node_id(node_id);
         if !is_valid {
             return Err(error.expect(
@@ -1545,7 +1739,7 @@ impl<T> Tree<T> {
     pub fn traverse_level_order_ids(
         &self,
         node_id: &NodeId,
-    ) -> Result<LevelOrderTraversalIds<T>, NodeIdError> {
+    ) -> Result<LevelOrderTraversalIds<'_, T>, NodeIdError> {
         let (is_valid, error) = self.is_valid_node_id(node_id);
         if !is_valid {
             return Err(error.expect(
@@ -1556,6 +1750,353 @@ impl<T> Tree<T> {
         Ok(LevelOrderTraversalIds::new(self, node_id.clone()))
     }
 
+    ///
+    /// Returns an `Iter` over the data of every `Node` below the root, in pre-order.  This is
+    /// what iterating over `&Tree` gives.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(3), UnderNode(&child_id)).unwrap();
+    ///
+    /// assert_eq!(tree.iter().sum::<i32>(), 6);
+    /// for data in &tree {
+    ///     assert!(*data < 4);
+    /// }
+    /// assert_eq!(tree.iter().collect::<Vec<&i32>>(), vec![&0, &1, &3, &2]);
+    /// ```
+    ///
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self)
+    }
+
+    ///
+    /// Consumes the `Tree`, returning an `IntoPreOrder` iterator over the data of every `Node`
+    /// below the root along with its depth.  Orphaned `Node`s are dropped.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<&str> = Tree::new();
+    /// let root_id = tree.insert(Node::new("a"), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new("b"), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new("c"), UnderNode(&child_id)).unwrap();
+    /// tree.insert(Node::new("d"), UnderNode(&root_id)).unwrap();
+    ///
+    /// let nodes: Vec<(&str, usize)> = tree.into_pre_order().collect();
+    /// assert_eq!(nodes, vec![("a", 0), ("b", 1), ("c", 2), ("d", 1)]);
+    /// ```
+    ///
+    pub fn into_pre_order(self) -> IntoPreOrder<T> {
+        IntoPreOrder::new(self.nodes, self.root)
+    }
+
+    ///
+    /// Consumes the `Tree`, returning an `IntoPostOrder` iterator over the data of every `Node`
+    /// below the root along with its depth.  Every `Node` comes after all of its children, which
+    /// makes this the order for building something else bottom-up.  Orphaned `Node`s are dropped.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// // (1 + 2) * 3
+    /// let mut tree: Tree<&str> = Tree::new();
+    /// let times_id = tree.insert(Node::new("*"), AsRoot).unwrap();
+    /// let plus_id = tree.insert(Node::new("+"), UnderNode(&times_id)).unwrap();
+    /// tree.insert(Node::new("1"), UnderNode(&plus_id)).unwrap();
+    /// tree.insert(Node::new("2"), UnderNode(&plus_id)).unwrap();
+    /// tree.insert(Node::new("3"), UnderNode(&times_id)).unwrap();
+    ///
+    /// // the finished values of the children of the Node at each depth
+    /// let mut values: Vec<Vec<i32>> = vec![Vec::new()];
+    /// for (data, depth) in tree.into_post_order() {
+    ///     values.resize(depth + 2, Vec::new());
+    ///     let operands = values.pop().unwrap();
+    ///     let value = match data {
+    ///         "+" => operands.iter().sum(),
+    ///         "*" => operands.iter().product(),
+    ///         number => number.parse().unwrap(),
+    ///     };
+    ///     values[depth].push(value);
+    /// }
+    /// assert_eq!(values, vec![vec![9]]);
+    /// ```
+    ///
+    pub fn into_post_order(self) -> IntoPostOrder<T> {
+        IntoPostOrder::new(self.nodes, self.root)
+    }
+
+    ///
+    /// Consumes the `Tree`, returning an `IntoLevelOrder` iterator over the data of every `Node`
+    /// below the root along with its depth.  Orphaned `Node`s are dropped.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<&str> = Tree::new();
+    /// let root_id = tree.insert(Node::new("a"), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new("b"), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new("c"), UnderNode(&child_id)).unwrap();
+    /// tree.insert(Node::new("d"), UnderNode(&root_id)).unwrap();
+    ///
+    /// let nodes: Vec<(&str, usize)> = tree.into_level_order().collect();
+    /// assert_eq!(nodes, vec![("a", 0), ("b", 1), ("d", 1), ("c", 2)]);
+    /// ```
+    ///
+    pub fn into_level_order(self) -> IntoLevelOrder<T> {
+        IntoLevelOrder::new(self.nodes, self.root)
+    }
+
+    ///
+    /// Removes the `Node` specified by the given `NodeId` and everything below it, returning a
+    /// `DrainSubtree` iterator over their data (in pre-order) along with their depth below the
+    /// given `Node`.
+    ///
+    /// The branch is cut off from the `Tree` right away, and whatever the iterator has not yet
+    /// returned is removed when it is dropped.  Draining the root leaves an empty `Tree` (apart
+    /// from any orphaned `Node`s).
+    ///
+    /// Returns a `NodeIdError` if the given `NodeId` is invalid.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    /// tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+    ///
+    /// let drained: Vec<(i32, usize)> = tree.drain_subtree(&child_id).unwrap().collect();
+    ///
+    /// assert_eq!(drained, vec![(1, 0), (2, 1)]);
+    /// assert_eq!(tree.iter().collect::<Vec<&i32>>(), vec![&0, &3]);
+    /// assert!(tree.get(&child_id).is_err());
+    /// ```
+    ///
+    pub fn drain_subtree(&mut self, node_id: &NodeId) -> Result<DrainSubtree<'_, T>, NodeIdError> {
+        let (is_valid, error) = self.is_valid_node_id(node_id);
+        if !is_valid {
+            return Err(error.expect(
+                "Tree::drain_subtree: Missing an error value but found an invalid NodeId.",
+            ));
+        }
+
+        let removal = self.pending_removal(node_id, RemoveBehavior::DropChildren);
+        if self.root.as_ref() == Some(node_id) {
+            self.root = None;
+        }
+        if let Some(parent_id) = self.get_unsafe(node_id).parent().cloned() {
+            self.detach_from_parent(&parent_id, node_id);
+            self.clear_parent(node_id);
+        }
+        self.validate_mutation();
+        if let Some(removal) = removal {
+            self.notify_removed(removal);
+        }
+
+        Ok(DrainSubtree::new(self, node_id.clone()))
+    }
+
+    ///
+    /// Checks that the internal structure of the `Tree` is consistent.
+    ///
+    /// This verifies that parent and child links agree with each other, that the root `Node` has
+    /// no parent, that there are no cycles or duplicate children, that every `NodeId` up for
+    /// reuse points to an empty slot and that every stored `NodeId` belongs to this `Tree`.
+    ///
+    /// Returns every violation found.  This is meant for debugging and fuzzing; a `Tree` that is
+    /// only modified through its own methods should always be valid.  Enabling the
+    /// `validate_mutations` feature runs this check after every call that modifies the `Tree` in
+    /// debug builds, panicking as soon as a violation shows up.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// tree.remove_node(child_id, DropChildren).unwrap();
+    ///
+    /// assert!(tree.validate().is_ok());
+    /// ```
+    ///
+    pub fn validate(&self) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = Vec::new();
+
+        if let Some(ref root_id) = self.root {
+            if let Some(violation) = self.check_stored_id(root_id) {
+                violations.push(violation);
+            } else if self.get_unsafe(root_id).parent().is_some() {
+                violations.push(InvariantViolation::RootHasParent(root_id.clone()));
+            }
+        }
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let node = match *node {
+                Some(ref node) => node,
+                None => continue,
+            };
+            let node_id = self.new_node_id(index);
+
+            if let Some(parent_id) = node.parent() {
+                if let Some(violation) = self.check_stored_id(parent_id) {
+                    violations.push(violation);
+                } else if !self.get_unsafe(parent_id).children().contains(&node_id) {
+                    violations.push(InvariantViolation::ParentMismatch {
+                        node: node_id.clone(),
+                        parent: parent_id.clone(),
+                    });
+                }
+            }
+
+            let mut seen = HashSet::new();
+            for child_id in node.children() {
+                if let Some(violation) = self.check_stored_id(child_id) {
+                    violations.push(violation);
+                    continue;
+                }
+                if !seen.insert(child_id.index) {
+                    violations.push(InvariantViolation::DuplicateChild {
+                        node: node_id.clone(),
+                        child: child_id.clone(),
+                    });
+                } else if self.get_unsafe(child_id).parent() != Some(&node_id) {
+                    violations.push(InvariantViolation::ChildMismatch {
+                        node: node_id.clone(),
+                        child: child_id.clone(),
+                    });
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for free_id in &self.free_ids {
+            if free_id.tree_id != self.id {
+                violations.push(InvariantViolation::ForeignNodeId(free_id.clone()));
+            } else if free_id.index >= self.nodes.len() {
+                violations.push(InvariantViolation::DanglingNodeId(free_id.clone()));
+            } else if self.nodes[free_id.index].is_some() {
+                violations.push(InvariantViolation::OccupiedFreeSlot(free_id.clone()));
+            } else if !seen.insert(free_id.index) {
+                violations.push(InvariantViolation::DuplicateFreeId(free_id.clone()));
+            }
+        }
+
+        self.find_cycles(&mut violations);
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    ///
+    /// Walks up from every `Node` until reaching a `Node` without a (valid) parent, reporting
+    /// each cycle found along the way once.
+    ///
+    fn find_cycles(&self, violations: &mut Vec<InvariantViolation>) {
+        const UNVISITED: u8 = 0;
+        const ON_PATH: u8 = 1;
+        const DONE: u8 = 2;
+
+        let mut state = vec![UNVISITED; self.nodes.len()];
+        let mut path = Vec::new();
+
+        for start in 0..self.nodes.len() {
+            let mut current = start;
+            loop {
+                if self.nodes[current].is_none() || state[current] == DONE {
+                    break;
+                }
+                if state[current] == ON_PATH {
+                    violations.push(InvariantViolation::Cycle(self.new_node_id(current)));
+                    break;
+                }
+                state[current] = ON_PATH;
+                path.push(current);
+
+                match self.nodes[current].as_ref().and_then(|node| node.parent()) {
+                    Some(parent_id) if self.check_stored_id(parent_id).is_none() => {
+                        current = parent_id.index;
+                    }
+                    _ => break,
+                }
+            }
+
+            for index in path.drain(..) {
+                state[index] = DONE;
+            }
+        }
+    }
+
+    fn check_stored_id(&self, node_id: &NodeId) -> Option<InvariantViolation> {
+        match self.is_valid_node_id(node_id) {
+            (true, _) => None,
+            (false, Some(NodeIdError::InvalidNodeIdForTree)) => {
+                Some(InvariantViolation::ForeignNodeId(node_id.clone()))
+            }
+            (false, _) => Some(InvariantViolation::DanglingNodeId(node_id.clone())),
+        }
+    }
+
+    ///
+    /// Panics if the `Tree` is no longer valid.  Only does anything in debug builds with the
+    /// `validate_mutations` feature enabled.
+    ///
+    #[inline]
+    pub(crate) fn validate_mutation(&self) {
+        #[cfg(all(feature = "validate_mutations", debug_assertions))]
+        {
+            if let Err(violations) = self.validate() {
+                panic!("Tree invariants violated: {:?}", violations);
+            }
+        }
+    }
+
+    ///
+    /// Gives the `Tree` a new id, updating every `NodeId` it stores.
+    ///
+    #[cfg(feature = "serde_support")]
+    fn reassign_id(&mut self, tree_id: ProcessUniqueId) {
+        self.id = tree_id;
+
+        let node_ids = self.root.iter_mut().chain(self.free_ids.iter_mut());
+        for node_id in node_ids {
+            node_id.tree_id = tree_id;
+        }
+
+        for node in self.nodes.iter_mut().filter_map(|node| node.as_mut()) {
+            for node_id in node.parent.iter_mut().chain(node.children.iter_mut()) {
+                node_id.tree_id = tree_id;
+            }
+        }
+    }
+
+    ///
+    /// Builds a `Tree` from `Node` data and the position of each `Node`'s parent, which has to
+    /// come before the `Node` itself.  See the `FromIterator` implementation.
+    ///
+    #[cfg(any(feature = "arbitrary", feature = "proptest"))]
+    pub(crate) fn from_preceding_parents(nodes: Vec<(T, Option<usize>)>) -> Tree<T> {
+        nodes
+            .into_iter()
+            .map(|(data, parent)| (parent, data))
+            .collect()
+    }
+
     // Nothing should make it past this function.
     // If there is a way for a NodeId to be invalid, it should be caught here.
     fn is_valid_node_id(&self, node_id: &NodeId) -> (bool, Option<NodeIdError>) {
@@ -1564,15 +2105,13 @@ impl<T> Tree<T> {
         }
 
         if node_id.index >= self.nodes.len() {
-            panic!(
-                "NodeId: {:?} is out of bounds. This is most likely a bug in id_tree. Please \
-                 report this issue!",
-                node_id
-            );
+            return (false, Some(NodeIdError::IndexOutOfBounds));
         }
 
         unsafe {
-            if self.nodes.get_unchecked(node_id.index).is_none() {
+            if self.nodes.get_unchecked(node_id.index).is_none()
+                || *self.generations.get_unchecked(node_id.index) != node_id.generation
+            {
                 return (false, Some(NodeIdError::NodeIdNoLongerValid));
             }
         }
@@ -1580,7 +2119,7 @@ impl<T> Tree<T> {
         (true, None)
     }
 
-    fn find_subtree_root_between_ids<'a>(
+    pub(crate) fn find_subtree_root_between_ids<'a>(
         &'a self,
         lower_id: &'a NodeId,
         upper_id: &'a NodeId,
@@ -1597,7 +2136,7 @@ impl<T> Tree<T> {
         None
     }
 
-    fn set_as_parent_and_child(&mut self, parent_id: &NodeId, child_id: &NodeId) {
+    pub(crate) fn set_as_parent_and_child(&mut self, parent_id: &NodeId, child_id: &NodeId) {
         self.get_mut_unsafe(parent_id).add_child(child_id.clone());
 
         self.get_mut_unsafe(child_id)
@@ -1610,26 +2149,28 @@ impl<T> Tree<T> {
             .retain(|child_id| child_id != node_id);
     }
 
-    fn insert_new_node(&mut self, new_node: Node<T>) -> NodeId {
+    pub(crate) fn insert_new_node(&mut self, new_node: Node<T>) -> NodeId {
         if !self.free_ids.is_empty() {
-            let new_node_id: NodeId = self
+            let free_id: NodeId = self
                 .free_ids
                 .pop()
                 .expect("Tree::insert_new_node: Couldn't pop from Vec with len() > 0.");
 
             self.nodes.push(Some(new_node));
-            self.nodes.swap_remove(new_node_id.index);
+            self.nodes.swap_remove(free_id.index);
 
-            new_node_id
+            self.new_node_id(free_id.index)
         } else {
             let new_node_index = self.nodes.len();
             self.nodes.push(Some(new_node));
+            let generation = self.issue_generation(new_node_index);
+            self.generations.push(generation);
 
             self.new_node_id(new_node_index)
         }
     }
 
-    fn remove_node_internal(&mut self, node_id: NodeId) -> Node<T> {
+    pub(crate) fn remove_node_internal(&mut self, node_id: NodeId) -> Node<T> {
         if let Some(root_id) = self.root.clone() {
             if node_id == root_id {
                 self.root = None;
@@ -1654,25 +2195,68 @@ impl<T> Tree<T> {
         node
     }
 
-    fn take_node(&mut self, node_id: NodeId) -> Node<T> {
+    pub(crate) fn take_node(&mut self, node_id: NodeId) -> Node<T> {
         self.nodes.push(None);
         let node = self.nodes.swap_remove(node_id.index).expect(
             "Tree::take_node: An invalid NodeId made it past id_tree's internal checks. \
              Please report this issue!",
         );
+        self.generations[node_id.index] = self.issue_generation(node_id.index);
         self.free_ids.push(node_id);
 
         node
     }
 
-    fn new_node_id(&self, node_index: usize) -> NodeId {
+    ///
+    /// Returns a generation for the slot that no `NodeId` has been handed out with yet.
+    ///
+    pub(crate) fn issue_generation(&mut self, node_index: usize) -> u32 {
+        if node_index >= self.next_generations.len() {
+            self.next_generations.resize(node_index + 1, 0);
+        }
+
+        let generation = self.next_generations[node_index];
+        self.next_generations[node_index] = generation.wrapping_add(1);
+        generation
+    }
+
+    ///
+    /// Puts a `Node` that was taken out with `take_node` back into its old slot, making the given
+    /// `NodeId` valid again.  The slot's generation goes back to the one of the `NodeId`, but the
+    /// generations handed out in the meantime are never handed out again.
+    ///
+    pub(crate) fn restore_node(&mut self, node_id: &NodeId, node: Node<T>) {
+        if let Some(position) = self
+            .free_ids
+            .iter()
+            .rposition(|free_id| free_id.index == node_id.index)
+        {
+            self.free_ids.remove(position);
+        }
+        self.nodes[node_id.index] = Some(node);
+        self.generations[node_id.index] = node_id.generation;
+    }
+
+    pub(crate) fn new_node_id(&self, node_index: usize) -> NodeId {
         NodeId {
             tree_id: self.id,
             index: node_index,
+            generation: self.generations[node_index],
         }
     }
 
-    fn clear_parent(&mut self, node_id: &NodeId) {
+    ///
+    /// If either of the `Node`s is the root, make the other one the root instead.
+    ///
+    fn swap_root(&mut self, first_id: &NodeId, second_id: &NodeId) {
+        if self.root.as_ref() == Some(first_id) {
+            self.root = Some(second_id.clone());
+        } else if self.root.as_ref() == Some(second_id) {
+            self.root = Some(first_id.clone());
+        }
+    }
+
+    fn clear_parent(&mut self, node_id: &NodeId) {
         self.set_parent(node_id, None);
     }
 
@@ -1699,7 +2283,7 @@ impl<T> Tree<T> {
         }
     }
 
-    fn get_mut_unsafe(&mut self, node_id: &NodeId) -> &mut Node<T> {
+    pub(crate) fn get_mut_unsafe(&mut self, node_id: &NodeId) -> &mut Node<T> {
         unsafe {
             self.nodes.get_unchecked_mut(node_id.index).as_mut().expect(
                 "Tree::get_mut_unsafe: An invalid NodeId made it past id_tree's internal \
@@ -1756,43 +2340,235 @@ where
 {
     fn clone(&self) -> Self {
         let tree_id = ProcessUniqueId::new();
+        let rebind = |node_id: &NodeId| NodeId {
+            tree_id,
+            index: node_id.index,
+            generation: node_id.generation,
+        };
 
         Tree {
             id: tree_id,
-            root: self.root.as_ref().map(|x| NodeId {
-                tree_id,
-                index: x.index,
-            }),
+            root: self.root.as_ref().map(rebind),
             nodes: self
                 .nodes
                 .iter()
                 .map(|x| {
                     x.as_ref().map(|y| Node {
                         data: y.data.clone(),
-                        parent: y.parent.as_ref().map(|z| NodeId {
-                            tree_id,
-                            index: z.index,
-                        }),
-                        children: y
-                            .children
-                            .iter()
-                            .map(|z| NodeId {
-                                tree_id,
-                                index: z.index,
-                            })
-                            .collect(),
+                        parent: y.parent.as_ref().map(rebind),
+                        children: y.children.iter().map(rebind).collect(),
                     })
                 })
                 .collect(),
-            free_ids: self
-                .free_ids
-                .iter()
-                .map(|x| NodeId {
-                    tree_id,
-                    index: x.index,
-                })
-                .collect(),
+            generations: self.generations.clone(),
+            next_generations: self.next_generations.clone(),
+            free_ids: self.free_ids.iter().map(rebind).collect(),
+            observers: Observers::default(),
+        }
+    }
+}
+
+impl<T> IntoIterator for Tree<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    ///
+    /// Moves the data out of every `Node` below the root, in pre-order.  Orphaned `Node`s are
+    /// dropped.
+    ///
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter::new(self.nodes, self.root)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Tree<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> FromIterator<(Option<usize>, T)> for Tree<T> {
+    ///
+    /// Builds a `Tree` from the position of each `Node`'s parent (counting from 0) and its data.
+    /// A parent has to come before its children.  The `Node` without a parent becomes the root.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// //      a
+    /// //     / \
+    /// //    b   d
+    /// //    |
+    /// //    c
+    /// let tree: Tree<char> = vec![(None, 'a'), (Some(0), 'b'), (Some(1), 'c'), (Some(0), 'd')]
+    ///     .into_iter()
+    ///     .collect();
+    ///
+    /// assert_eq!(tree.into_iter().collect::<String>(), "abcd");
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if a parent position does not come before the `Node` itself, or if more than one
+    /// `Node` comes without a parent.
+    ///
+    fn from_iter<I>(nodes: I) -> Tree<T>
+    where
+        I: IntoIterator<Item = (Option<usize>, T)>,
+    {
+        let nodes = nodes.into_iter();
+        let mut tree = TreeBuilder::new()
+            .with_node_capacity(nodes.size_hint().0)
+            .build();
+        let mut ids: Vec<NodeId> = Vec::with_capacity(nodes.size_hint().0);
+
+        for (parent, data) in nodes {
+            let behavior = match parent {
+                Some(parent) if parent < ids.len() => InsertBehavior::UnderNode(&ids[parent]),
+                Some(parent) => panic!(
+                    "Tree::from_iter: the parent at position {} does not come before position {}.",
+                    parent,
+                    ids.len()
+                ),
+                None if tree.root.is_none() => InsertBehavior::AsRoot,
+                None => panic!(
+                    "Tree::from_iter: position {} has no parent, but the Tree already has a root.",
+                    ids.len()
+                ),
+            };
+            let id = tree.insert(Node::new(data), behavior).unwrap();
+            ids.push(id);
+        }
+
+        tree
+    }
+}
+
+impl<'a, T> Extend<(&'a NodeId, T)> for Tree<T> {
+    ///
+    /// Inserts a new `Node` for each piece of data as the last child of the `Node` it comes with.
+    /// Use `Tree::extend_children` to insert under a single parent without panicking.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// tree.extend((1..4).map(|data| (&root_id, data)));
+    ///
+    /// assert_eq!(tree.children(&root_id).unwrap().count(), 3);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if any of the `NodeId`s is invalid.
+    ///
+    fn extend<I>(&mut self, nodes: I)
+    where
+        I: IntoIterator<Item = (&'a NodeId, T)>,
+    {
+        for (parent_id, data) in nodes {
+            if let Err(error) = self.insert(Node::new(data), InsertBehavior::UnderNode(parent_id)) {
+                panic!("Tree::extend: {}", error);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl<'de, T> serde::Deserialize<'de> for Tree<T>
+where
+    T: serde::Deserialize<'de>,
+{
+    ///
+    /// Deserializes a `Tree` written with `serde_support`.
+    ///
+    /// The `Tree` gets a new id (and all of its `NodeId`s are rewritten to match) so that it
+    /// cannot be confused with any other `Tree` in this process.  Input that does not describe a
+    /// valid `Tree` (see `Tree::validate`) results in an error.
+    ///
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename = "Tree")]
+        struct RawTree<T> {
+            id: ProcessUniqueId,
+            root: Option<NodeId>,
+            nodes: Vec<Option<Node<T>>>,
+            // missing from Trees written before NodeIds had generations
+            #[serde(default)]
+            generations: Vec<u32>,
+            #[serde(default)]
+            next_generations: Vec<u32>,
+            free_ids: Vec<NodeId>,
+        }
+
+        let mut raw = RawTree::deserialize(deserializer)?;
+        if raw.generations.is_empty() {
+            raw.generations = vec![0; raw.nodes.len()];
+        } else if raw.generations.len() != raw.nodes.len() {
+            return Err(serde::de::Error::custom(
+                "invalid Tree: the number of generations does not match the number of nodes",
+            ));
+        }
+        if raw.next_generations.is_empty() {
+            raw.next_generations = raw.generations.iter().map(|g| g.wrapping_add(1)).collect();
+        } else if raw.next_generations.len() < raw.nodes.len() {
+            return Err(serde::de::Error::custom(
+                "invalid Tree: some nodes are missing their next generation",
+            ));
+        }
+        let mut tree = Tree {
+            id: raw.id,
+            root: raw.root,
+            nodes: raw.nodes,
+            generations: raw.generations,
+            next_generations: raw.next_generations,
+            free_ids: raw.free_ids,
+            observers: Observers::default(),
+        };
+
+        if let Err(violations) = tree.validate() {
+            let violations: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            return Err(serde::de::Error::custom(format!(
+                "invalid Tree: {}",
+                violations.join("; ")
+            )));
+        }
+
+        tree.reassign_id(ProcessUniqueId::new());
+        Ok(tree)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T> arbitrary::Arbitrary<'a> for Tree<T>
+where
+    T: arbitrary::Arbitrary<'a>,
+{
+    ///
+    /// Builds a random recursive `Tree`: every `Node` after the root picks its parent uniformly
+    /// among the `Node`s that came before it.
+    ///
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.arbitrary_len::<(T, u32)>()?;
+
+        let mut nodes = Vec::with_capacity(len);
+        for index in 0..len {
+            let parent = if index == 0 {
+                None
+            } else {
+                Some(u.int_in_range(0..=index - 1)?)
+            };
+            nodes.push((T::arbitrary(u)?, parent));
         }
+
+        Ok(Tree::from_preceding_parents(nodes))
     }
 }
 
@@ -1831,52 +2607,10 @@ impl<T: std::fmt::Debug> Tree<T> {
     /// tree.write_formatted(&mut s).unwrap();
     /// assert_eq!(&s, "");
     /// ```
+    ///
+    /// See `TreeFormatter` for more control over the output.
     pub fn write_formatted<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
-        if let Some(node_id) = self.root_node_id() {
-            let childn = 0;
-            let level = 0;
-            let last = vec![];
-            let mut stack = vec![(node_id, childn, level, last)];
-            while let Some((node_id, childn, level, last)) = stack.pop() {
-                debug_assert_eq!(
-                    last.len(),
-                    level,
-                    "each previous level should indicate whether it has reached the last node"
-                );
-                let node = self
-                    .get(node_id)
-                    .expect("getting node of existing node ref id");
-                if childn == 0 {
-                    for i in 1..level {
-                        if last[i - 1] {
-                            write!(w, "    ")?;
-                        } else {
-                            write!(w, "│   ")?;
-                        }
-                    }
-                    if level > 0 {
-                        if last[level - 1] {
-                            write!(w, "└── ")?;
-                        } else {
-                            write!(w, "├── ")?;
-                        }
-                    }
-                    writeln!(w, "{:?}", node.data())?;
-                }
-                let mut children = node.children().iter().skip(childn);
-                if let Some(child_id) = children.next() {
-                    let mut next_last = last.clone();
-                    if children.next().is_some() {
-                        stack.push((node_id, childn + 1, level, last));
-                        next_last.push(false);
-                    } else {
-                        next_last.push(true);
-                    }
-                    stack.push((child_id, 0, level + 1, next_last));
-                }
-            }
-        }
-        Ok(())
+        TreeFormatter::new(self, |data| format!("{:?}", data)).write(w)
     }
 }
 
@@ -1950,8 +2684,10 @@ mod tree_builder_tests {
 
 #[cfg(test)]
 mod tree_tests {
+    use super::super::InvariantViolation;
     use super::super::Node;
     use super::super::NodeId;
+    use super::super::NodeIdError;
     use super::Tree;
     use super::TreeBuilder;
 
@@ -1994,6 +2730,131 @@ mod tree_tests {
         assert_eq!(root.data(), &6);
     }
 
+    #[test]
+    fn test_validate() {
+        use test_fixtures::*;
+
+        assert!(Tree::<i32>::new().validate().is_ok());
+        assert!(with_removed_node().0.validate().is_ok());
+
+        // one-sided links
+        let (mut broken, ids) = with_removed_node();
+        let (node_1_id, node_2_id) = (ids[1].clone(), ids[2].clone());
+        broken.get_mut_unsafe(&node_1_id).children_mut().clear();
+        assert_eq!(
+            broken.validate(),
+            Err(vec![InvariantViolation::ParentMismatch {
+                node: node_2_id.clone(),
+                parent: node_1_id.clone(),
+            }])
+        );
+
+        let (mut broken, ids) = with_removed_node();
+        let (root_id, node_2_id) = (ids[0].clone(), ids[2].clone());
+        broken.get_mut_unsafe(&root_id).add_child(node_2_id.clone());
+        assert_eq!(
+            broken.validate(),
+            Err(vec![InvariantViolation::ChildMismatch {
+                node: root_id.clone(),
+                child: node_2_id.clone(),
+            }])
+        );
+
+        // duplicates and a parent for the root
+        let (mut broken, ids) = with_removed_node();
+        let (node_1_id, node_2_id) = (ids[1].clone(), ids[2].clone());
+        broken
+            .get_mut_unsafe(&node_1_id)
+            .add_child(node_2_id.clone());
+        assert_eq!(
+            broken.validate(),
+            Err(vec![InvariantViolation::DuplicateChild {
+                node: node_1_id.clone(),
+                child: node_2_id.clone(),
+            }])
+        );
+
+        let (mut broken, ids) = with_removed_node();
+        let (root_id, node_2_id) = (ids[0].clone(), ids[2].clone());
+        broken.set_as_parent_and_child(&node_2_id, &root_id);
+        let violations = broken.validate().unwrap_err();
+        assert!(violations.contains(&InvariantViolation::RootHasParent(root_id.clone())));
+        assert_eq!(
+            violations
+                .iter()
+                .filter(|v| matches!(**v, InvariantViolation::Cycle(_)))
+                .count(),
+            1
+        );
+
+        // bad ids
+        let (mut broken, ids) = with_removed_node();
+        let (node_2_id, node_3_id) = (ids[2].clone(), ids[3].clone());
+        broken.free_ids.push(node_2_id.clone());
+        broken.free_ids.push(node_3_id.clone());
+        assert_eq!(
+            broken.validate(),
+            Err(vec![
+                InvariantViolation::OccupiedFreeSlot(node_2_id.clone()),
+                InvariantViolation::DuplicateFreeId(node_3_id.clone()),
+            ])
+        );
+
+        let (mut broken, ids) = with_removed_node();
+        let (node_2_id, node_3_id) = (ids[2].clone(), ids[3].clone());
+        broken
+            .get_mut_unsafe(&node_2_id)
+            .add_child(node_3_id.clone());
+        assert_eq!(
+            broken.validate(),
+            Err(vec![InvariantViolation::DanglingNodeId(node_3_id.clone())])
+        );
+
+        let other_tree: Tree<i32> = TreeBuilder::new().with_root(Node::new(0)).build();
+        let foreign_id = other_tree.root_node_id().unwrap().clone();
+        let (mut broken, ids) = with_removed_node();
+        let node_2_id = ids[2].clone();
+        broken
+            .get_mut_unsafe(&node_2_id)
+            .add_child(foreign_id.clone());
+        assert_eq!(
+            broken.validate(),
+            Err(vec![InvariantViolation::ForeignNodeId(foreign_id)])
+        );
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let bytes: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        let mut u = Unstructured::new(&bytes);
+        for _ in 0..16 {
+            let tree = Tree::<u8>::arbitrary(&mut u).unwrap();
+            assert!(tree.validate().is_ok());
+        }
+
+        let tree = Tree::<u8>::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert_eq!(tree.root_node_id(), None);
+    }
+
+    #[test]
+    fn test_get_out_of_bounds() {
+        use NodeIdError;
+
+        let tree = TreeBuilder::new().with_root(Node::new(5)).build();
+
+        let root_id = tree.root.clone().unwrap();
+        let bad_id = NodeId {
+            tree_id: root_id.tree_id,
+            index: 10,
+            generation: 0,
+        };
+
+        assert_eq!(tree.get(&bad_id).err(), Some(NodeIdError::IndexOutOfBounds));
+    }
+
     #[test]
     fn test_set_root() {
         use InsertBehavior::*;
@@ -2026,8 +2887,8 @@ mod tree_tests {
             assert_eq!(node_b_ref.data(), &b);
             assert_eq!(root_ref.data(), &b);
 
-            let node_b_child_id = node_b_ref.children().get(0).unwrap();
-            let node_b_child_ref = tree.get(&node_b_child_id).unwrap();
+            let node_b_child_id = node_b_ref.children().first().unwrap();
+            let node_b_child_ref = tree.get(node_b_child_id).unwrap();
             assert_eq!(node_b_child_ref.data(), &a);
         }
     }
@@ -2070,11 +2931,11 @@ mod tree_tests {
         let root_node_ref = tree.get(&root_id).unwrap();
         let root_children: &Vec<NodeId> = root_node_ref.children();
 
-        let child_1_id = root_children.get(0).unwrap();
+        let child_1_id = root_children.first().unwrap();
         let child_2_id = root_children.get(1).unwrap();
 
-        let child_1_ref = tree.get(&child_1_id).unwrap();
-        let child_2_ref = tree.get(&child_2_id).unwrap();
+        let child_1_ref = tree.get(child_1_id).unwrap();
+        let child_2_ref = tree.get(child_2_id).unwrap();
 
         assert_eq!(child_1_ref.data(), &a);
         assert_eq!(child_2_ref.data(), &b);
@@ -2259,6 +3120,28 @@ mod tree_tests {
         assert_eq!(tree.root_node_id(), Some(&node_2_id));
     }
 
+    #[test]
+    fn test_move_root_under_orphan() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2_id = tree.insert(Node::new(2), UnderNode(&node_1_id)).unwrap();
+        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_2_id)).unwrap();
+
+        // 1 and 2 end up as an orphaned branch
+        tree.remove_node_orphan_children(node_1_id.clone()).unwrap();
+        tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+
+        tree.move_node(&root_id, ToParent(&node_3_id)).unwrap();
+
+        assert_eq!(tree.root_node_id(), Some(&node_2_id));
+        assert_eq!(tree.get(&root_id).unwrap().parent(), Some(&node_3_id));
+        assert!(tree.validate().is_ok());
+    }
+
     #[test]
     fn test_move_node_to_root() {
         use InsertBehavior::*;
@@ -2866,6 +3749,8 @@ mod tree_tests {
                 .unwrap()
                 .children()
                 .contains(&node_4_id,));
+            assert!(tree.get(&node_3_id).unwrap().children().is_empty());
+            assert!(tree.validate().is_ok());
         }
 
         // test down swap (with root)
@@ -3062,4 +3947,328 @@ mod tree_tests {
         // ensure the tree and the cloned tree are equal
         assert_eq!(tree, cloned);
     }
+
+    #[test]
+    fn test_iterators() {
+        use std::rc::Rc;
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+        let orphan_id = tree.insert(Node::new(4), UnderNode(&node_1_id)).unwrap();
+        tree.insert(Node::new(5), UnderNode(&orphan_id)).unwrap();
+        tree.remove_node(orphan_id, OrphanChildren).unwrap();
+
+        assert_eq!(tree.iter().cloned().collect::<Vec<i32>>(), vec![0, 1, 3, 2]);
+        let mut borrowed = Vec::new();
+        for data in &tree {
+            borrowed.push(*data);
+        }
+        assert_eq!(borrowed, vec![0, 1, 3, 2]);
+        assert_eq!(tree.into_iter().collect::<Vec<i32>>(), vec![0, 1, 3, 2]);
+        assert_eq!(Tree::<i32>::new().iter().count(), 0);
+        assert_eq!(Tree::<i32>::new().into_iter().count(), 0);
+
+        // data that is not yielded (including orphans) is dropped with the iterator
+        let shared = Rc::new(());
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(shared.clone()), AsRoot).unwrap();
+        let orphan_id = tree
+            .insert(Node::new(shared.clone()), UnderNode(&root_id))
+            .unwrap();
+        tree.insert(Node::new(shared.clone()), UnderNode(&orphan_id))
+            .unwrap();
+        tree.insert(Node::new(shared.clone()), UnderNode(&root_id))
+            .unwrap();
+        tree.remove_node(orphan_id, OrphanChildren).unwrap();
+        let mut into_iter = tree.into_iter();
+        assert!(into_iter.next().is_some());
+        drop(into_iter);
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn test_into_traversals() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        //      0
+        //     / \
+        //    1   2
+        //   / \
+        //  3   4
+        let build = || {
+            let tree: Tree<i32> = vec![
+                (None, 0),
+                (Some(0), 1),
+                (Some(0), 2),
+                (Some(1), 3),
+                (Some(1), 4),
+            ]
+            .into_iter()
+            .collect();
+            tree
+        };
+
+        assert_eq!(
+            build().into_pre_order().collect::<Vec<_>>(),
+            vec![(0, 0), (1, 1), (3, 2), (4, 2), (2, 1)]
+        );
+        assert_eq!(
+            build().into_post_order().collect::<Vec<_>>(),
+            vec![(3, 2), (4, 2), (1, 1), (2, 1), (0, 0)]
+        );
+        assert_eq!(
+            build().into_level_order().collect::<Vec<_>>(),
+            vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+        );
+
+        // orphans are left out
+        let mut tree = build();
+        let root_id = tree.root_node_id().unwrap().clone();
+        let orphan_id = tree.insert(Node::new(5), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(6), UnderNode(&orphan_id)).unwrap();
+        tree.remove_node(orphan_id, OrphanChildren).unwrap();
+        assert_eq!(tree.clone().into_pre_order().count(), 5);
+        assert_eq!(tree.clone().into_post_order().count(), 5);
+        assert_eq!(tree.into_level_order().count(), 5);
+
+        assert_eq!(Tree::<i32>::new().into_pre_order().count(), 0);
+        assert_eq!(Tree::<i32>::new().into_post_order().count(), 0);
+        assert_eq!(Tree::<i32>::new().into_level_order().count(), 0);
+    }
+
+    #[test]
+    fn test_into_traversals_deep() {
+        let depth: usize = if cfg!(feature = "validate_mutations") {
+            2_000
+        } else {
+            100_000
+        };
+        let chain = || -> Tree<usize> {
+            (0..depth)
+                .map(|index| (index.checked_sub(1), index))
+                .collect()
+        };
+
+        assert!(chain().into_pre_order().all(|(data, depth)| data == depth));
+        assert!(chain()
+            .into_post_order()
+            .zip((0..depth).rev())
+            .all(|((data, node_depth), expected)| data == expected && node_depth == expected));
+        assert_eq!(chain().into_level_order().count(), depth);
+    }
+
+    #[test]
+    fn test_drain_subtree() {
+        use InsertBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        let node_3_id = tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
+        tree.insert(Node::new(4), UnderNode(&node_3_id)).unwrap();
+        tree.insert(Node::new(5), UnderNode(&node_1_id)).unwrap();
+
+        // dropping the iterator early still removes the whole branch
+        {
+            let mut drain = tree.drain_subtree(&node_1_id).unwrap();
+            assert_eq!(drain.next(), Some((1, 0)));
+            assert_eq!(drain.next(), Some((3, 1)));
+        }
+        assert!(tree.validate().is_ok());
+        assert_eq!(tree.iter().cloned().collect::<Vec<i32>>(), vec![0, 2]);
+        assert_eq!(tree.free_ids.len(), 4);
+        assert_eq!(tree.get(&node_3_id), Err(NodeIdError::NodeIdNoLongerValid));
+
+        // freed slots are reused
+        tree.insert(Node::new(6), UnderNode(&node_2_id)).unwrap();
+        assert_eq!(tree.nodes.len(), 6);
+
+        assert_eq!(
+            tree.drain_subtree(&node_1_id).map(|_| ()),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
+
+        let drained: Vec<(i32, usize)> = tree.drain_subtree(&root_id).unwrap().collect();
+        assert_eq!(drained, vec![(0, 0), (2, 1), (6, 2)]);
+        assert!(tree.root_node_id().is_none());
+        assert!(tree.validate().is_ok());
+        assert_eq!(tree.nodes.iter().filter(|node| node.is_some()).count(), 0);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let tree: Tree<i32> = vec![(None, 0), (Some(0), 1), (Some(1), 2), (Some(0), 3)]
+            .into_iter()
+            .collect();
+        assert!(tree.validate().is_ok());
+        assert_eq!(tree.iter().cloned().collect::<Vec<i32>>(), vec![0, 1, 2, 3]);
+        let root_id = tree.root_node_id().unwrap();
+        assert_eq!(tree.children(root_id).unwrap().count(), 2);
+
+        let empty: Tree<i32> = Vec::new().into_iter().collect();
+        assert!(empty.root_node_id().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "does not come before")]
+    fn test_from_iter_parent_after_child() {
+        let _: Tree<i32> = vec![(None, 0), (Some(2), 1), (Some(0), 2)]
+            .into_iter()
+            .collect();
+    }
+
+    #[test]
+    #[should_panic(expected = "already has a root")]
+    fn test_from_iter_second_root() {
+        let _: Tree<i32> = vec![(None, 0), (Some(0), 1), (None, 2)]
+            .into_iter()
+            .collect();
+    }
+
+    #[test]
+    fn test_extend() {
+        use InsertBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let child_ids = tree.extend_children(&root_id, vec![1, 2]).unwrap();
+        assert_eq!(child_ids.len(), 2);
+        tree.extend(vec![(&child_ids[0], 3), (&root_id, 4), (&child_ids[0], 5)]);
+        assert!(tree.validate().is_ok());
+        assert_eq!(
+            tree.iter().cloned().collect::<Vec<i32>>(),
+            vec![0, 1, 3, 5, 2, 4]
+        );
+        assert!(tree
+            .extend_children(&root_id, Vec::new())
+            .unwrap()
+            .is_empty());
+
+        let other_id = Tree::new().insert(Node::new(0), AsRoot).unwrap();
+        assert_eq!(
+            tree.extend_children(&other_id, vec![6]),
+            Err(NodeIdError::InvalidNodeIdForTree)
+        );
+        assert_eq!(tree.iter().count(), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tree::extend")]
+    fn test_extend_invalid_node_id() {
+        use InsertBehavior::*;
+
+        let mut tree = Tree::new();
+        let other_id = Tree::new().insert(Node::new(0), AsRoot).unwrap();
+        tree.extend(vec![(&other_id, 1)]);
+    }
+}
+
+#[cfg(all(test, feature = "serde_support"))]
+mod tree_serde_tests {
+    extern crate serde_json;
+
+    use self::serde_json::Value;
+    use super::super::*;
+    use test_fixtures::*;
+    use InsertBehavior::*;
+
+    fn from_value(value: Value) -> Result<Tree<i32>, serde_json::Error> {
+        serde_json::from_value(value)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (tree, ids) = with_removed_node();
+        let (root_id, child_id) = (&ids[0], &ids[1]);
+
+        let copy = from_value(serde_json::to_value(&tree).unwrap()).unwrap();
+        assert!(copy.validate().is_ok());
+        assert_eq!(copy, tree);
+
+        // the copy is a different Tree, but the structure carries over
+        assert_eq!(
+            copy.get(root_id).err(),
+            Some(NodeIdError::InvalidNodeIdForTree)
+        );
+        let copy_root_id = copy.root_node_id().unwrap();
+        let copy_child_id = &copy.get(copy_root_id).unwrap().children()[0];
+        assert_eq!(copy.get(copy_child_id).unwrap().data(), &1);
+        assert_eq!(
+            copy.get(copy_child_id).unwrap().parent(),
+            Some(copy_root_id)
+        );
+        assert!(tree.get(child_id).is_ok());
+    }
+
+    #[test]
+    fn test_without_generations() {
+        let (tree, _) = with_removed_node();
+        let mut value = serde_json::to_value(&tree).unwrap();
+        assert_eq!(value["generations"], serde_json::json!([0, 0, 0, 1]));
+
+        // as written before NodeIds had generations
+        fn strip_generations(value: &mut Value) {
+            match *value {
+                Value::Object(ref mut object) => {
+                    object.remove("generations");
+                    object.remove("next_generations");
+                    object.remove("generation");
+                    object.values_mut().for_each(strip_generations);
+                }
+                Value::Array(ref mut array) => array.iter_mut().for_each(strip_generations),
+                _ => {}
+            }
+        }
+        strip_generations(&mut value);
+        assert!(!value.to_string().contains("generation"));
+
+        let copy = from_value(value).unwrap();
+        assert_eq!(copy, tree);
+
+        let mut mismatched = serde_json::to_value(&tree).unwrap();
+        mismatched["generations"] = serde_json::json!([0, 0, 0]);
+        let error = from_value(mismatched).unwrap_err().to_string();
+        assert!(error.contains("number of generations"), "{}", error);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let (tree, _) = with_removed_node();
+        let value = serde_json::to_value(&tree).unwrap();
+
+        // a child index past the end of the arena
+        let mut dangling = value.clone();
+        dangling["nodes"][0]["children"][0]["index"] = Value::from(7);
+        let error = from_value(dangling).unwrap_err().to_string();
+        assert!(error.contains("invalid Tree"), "{}", error);
+
+        // the root as the parent of its own child and vice versa
+        let mut cycle = value.clone();
+        cycle["nodes"][0]["parent"] = cycle["nodes"][0]["children"][0].clone();
+        cycle["root"] = Value::Null;
+        let error = from_value(cycle).unwrap_err().to_string();
+        assert!(error.contains("its own ancestor"), "{}", error);
+
+        // a NodeId from some other Tree
+        let mut foreign = value.clone();
+        foreign["nodes"][1]["parent"]["tree_id"] =
+            serde_json::to_value(Tree::<i32>::new().insert(Node::new(0), AsRoot).unwrap()).unwrap()
+                ["tree_id"]
+                .clone();
+        let error = from_value(foreign).unwrap_err().to_string();
+        assert!(error.contains("different Tree"), "{}", error);
+
+        // a free slot that is still in use
+        let mut occupied = value;
+        occupied["free_ids"][0]["index"] = Value::from(0);
+        let error = from_value(occupied).unwrap_err().to_string();
+        assert!(error.contains("free NodeId"), "{}", error);
+    }
 }
@@ -28,6 +28,27 @@ fn test_old_node_id() {
     assert_eq!(error, NodeIdNoLongerValid);
 }
 
+#[test]
+fn test_reused_node_id() {
+    let mut tree: Tree<i32> = TreeBuilder::new().build();
+
+    let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    let child_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    let child_id_copy = child_id.clone();
+
+    tree.remove_node(child_id, DropChildren).unwrap();
+    let new_child_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+    assert_ne!(new_child_id, child_id_copy);
+
+    // the new Node reuses the slot, but the old NodeId does not reach it
+    assert_eq!(tree.get(&child_id_copy).err(), Some(NodeIdNoLongerValid));
+    assert_eq!(
+        tree.remove_node(child_id_copy, DropChildren).err(),
+        Some(NodeIdNoLongerValid)
+    );
+    assert_eq!(tree.get(&new_child_id).unwrap().data(), &3);
+}
+
 #[test]
 fn test_get_node_from_other_tree() {
     let mut tree_a: Tree<i32> = TreeBuilder::new().build();
@@ -361,3 +382,107 @@ fn test_children_ids_old_id() {
     let error = ancestors.err().unwrap();
     assert_eq!(error, NodeIdNoLongerValid);
 }
+
+#[test]
+fn test_move_node_under_itself() {
+    let mut tree = Tree::new();
+
+    let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    let child_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+
+    let result = tree.move_node(&child_id, ToParent(&child_id));
+    assert!(result.is_err());
+
+    let error = result.err().unwrap();
+    assert_eq!(error, WouldCreateCycle);
+
+    // nothing changed
+    assert_eq!(tree.get(&child_id).unwrap().parent(), Some(&root_id));
+    assert_eq!(tree.get(&root_id).unwrap().children(), &vec![child_id]);
+}
+
+#[test]
+fn test_move_root_to_root() {
+    let mut tree = Tree::new();
+
+    let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    let child_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+
+    assert!(tree.move_node(&root_id, ToRoot).is_ok());
+
+    assert_eq!(tree.root_node_id(), Some(&root_id));
+    assert!(tree.get(&root_id).unwrap().parent().is_none());
+    assert_eq!(tree.get(&root_id).unwrap().children(), &vec![child_id]);
+}
+
+#[test]
+fn test_swap_node_with_itself() {
+    let mut tree = Tree::new();
+
+    let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+
+    let result = tree.swap_nodes(&root_id, &root_id, TakeChildren);
+    assert_eq!(result.err().unwrap(), SameNode);
+
+    let result = tree.swap_nodes(&root_id, &root_id, LeaveChildren);
+    assert_eq!(result.err().unwrap(), SameNode);
+
+    let result = tree.swap_nodes(&root_id, &root_id, ChildrenOnly);
+    assert_eq!(result.err().unwrap(), SameNode);
+}
+
+#[test]
+fn test_make_nth_sibling_of_root() {
+    let mut tree = Tree::new();
+
+    let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+
+    let result = tree.make_nth_sibling(&root_id, 0);
+    assert!(result.is_err());
+
+    let error = result.err().unwrap();
+    assert_eq!(error, NotASibling);
+}
+
+#[test]
+fn test_make_nth_sibling_out_of_range() {
+    let mut tree = Tree::new();
+
+    let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    let first_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    let second_id = tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+
+    let result = tree.make_nth_sibling(&first_id, 2);
+    assert_eq!(result.err().unwrap(), PositionOutOfRange);
+
+    let result = tree.make_nth_sibling(&first_id, usize::MAX);
+    assert_eq!(result.err().unwrap(), PositionOutOfRange);
+
+    // the order is left alone
+    let children = tree.get(&root_id).unwrap().children();
+    assert_eq!(children, &vec![first_id.clone(), second_id]);
+
+    let grandchild_id = tree.insert(Node::new(4), UnderNode(&first_id)).unwrap();
+    let result = tree.make_nth_sibling(&grandchild_id, 1);
+    assert_eq!(result.err().unwrap(), PositionOutOfRange);
+    assert!(tree.make_nth_sibling(&grandchild_id, 0).is_ok());
+}
+
+#[test]
+fn test_swap_orphan_with_child() {
+    let mut tree = Tree::new();
+
+    let root_id = tree.insert(Node::new(1), AsRoot).unwrap();
+    let child_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    let orphan_id = tree.insert(Node::new(3), UnderNode(&child_id)).unwrap();
+    tree.remove_node(child_id, OrphanChildren).unwrap();
+
+    let other_id = tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+
+    tree.swap_nodes(&orphan_id, &other_id, TakeChildren)
+        .unwrap();
+
+    assert_eq!(tree.get(&orphan_id).unwrap().parent(), Some(&root_id));
+    assert!(tree.get(&other_id).unwrap().parent().is_none());
+    assert_eq!(tree.get(&root_id).unwrap().children(), &vec![orphan_id]);
+}
@@ -0,0 +1,128 @@
+#[macro_use]
+extern crate id_tree;
+
+use id_tree::InsertBehavior::*;
+use id_tree::Node;
+use id_tree::NodeId;
+use id_tree::Tree;
+
+fn pre_order<T: Clone>(tree: &Tree<T>) -> Vec<T> {
+    match tree.root_node_id() {
+        Some(root_id) => tree
+            .traverse_pre_order(root_id)
+            .unwrap()
+            .map(|node| node.data().clone())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn children<T: Clone>(tree: &Tree<T>, node_id: &NodeId) -> Vec<T> {
+    tree.children(node_id)
+        .unwrap()
+        .map(|node| node.data().clone())
+        .collect()
+}
+
+#[test]
+fn test_shapes() {
+    let empty: Tree<i32> = tree!();
+    assert!(empty.root_node_id().is_none());
+
+    let single = tree!(7);
+    assert_eq!(pre_order(&single), vec![7]);
+
+    let leaf_list = tree!(7 => {});
+    assert_eq!(pre_order(&leaf_list), vec![7]);
+
+    let tree = tree! { 0 => { 1 => { 3, 4 }, 2 } };
+    assert_eq!(pre_order(&tree), vec![0, 1, 3, 4, 2]);
+    assert_eq!(tree.height(), 3);
+
+    let trailing = tree! { 0 => { 1 => { 3, 4, }, 2, } };
+    assert_eq!(pre_order(&trailing), vec![0, 1, 3, 4, 2]);
+
+    let deep = tree!(0 => { 1 => { 2 => { 3 => { 4 } } } });
+    assert_eq!(deep.height(), 5);
+}
+
+#[test]
+fn test_expressions() {
+    let base = 10;
+    let tree = tree! {
+        base * 2 => {
+            String::from("x").len() as i32 => { -1, { base + 1 } },
+            if base > 5 { 1 } else { 0 },
+        }
+    };
+    assert_eq!(pre_order(&tree), vec![20, 1, -1, 11, 1]);
+
+    let strings = tree!("a".to_string() => { "b".to_string() });
+    assert_eq!(pre_order(&strings), vec!["a", "b"]);
+}
+
+#[test]
+fn test_named_ids() {
+    tree! {
+        let tree = root: "root" => {
+            "a" => { a_child: "a1", "a2" },
+            b: "b" => { "b1" },
+            c: "c",
+        }
+    };
+
+    assert_eq!(tree.get(&root).unwrap().data(), &"root");
+    assert_eq!(children(&tree, &root), vec!["a", "b", "c"]);
+    assert_eq!(children(&tree, &b), vec!["b1"]);
+    assert!(children(&tree, &c).is_empty());
+    let a = tree.children_ids(&root).unwrap().next().unwrap();
+    assert_eq!(tree.get(&a_child).unwrap().parent(), Some(a));
+    assert_eq!(children(&tree, a), vec!["a1", "a2"]);
+}
+
+#[test]
+fn test_named_mutable() {
+    tree! { let mut tree = root: 0 => { 1 } };
+    tree.insert(Node::new(2), UnderNode(&root)).unwrap();
+    assert_eq!(pre_order(&tree), vec![0, 1, 2]);
+
+    tree! { let single = only: 5 };
+    assert_eq!(single.get(&only).unwrap().data(), &5);
+}
+
+#[test]
+fn test_wide() {
+    let tree = tree! {
+        0 => {
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20,
+            21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40,
+            41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60,
+            61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79, 80,
+            81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 91, 92, 93, 94, 95, 96, 97, 98, 99, 100,
+            101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120,
+            121, 122, 123, 124, 125, 126, 127, 128, 129, 130, 131, 132, 133, 134, 135, 136, 137, 138, 139, 140,
+            141, 142, 143, 144, 145, 146, 147, 148, 149, 150, 151, 152, 153, 154, 155, 156, 157, 158, 159, 160,
+            161, 162, 163, 164, 165, 166, 167, 168, 169, 170, 171, 172, 173, 174, 175, 176, 177, 178, 179, 180,
+            181, 182, 183, 184, 185, 186, 187, 188, 189, 190, 191, 192, 193, 194, 195, 196, 197, 198, 199, 200,
+        }
+    };
+    let root_id = tree.root_node_id().unwrap();
+    assert_eq!(children(&tree, root_id), (1..201).collect::<Vec<i32>>());
+
+    tree! {
+        let named = root: -1 => {
+            n0: 0, n1: 1, n2: 2, n3: 3, n4: 4, n5: 5, n6: 6, n7: 7, n8: 8, n9: 9,
+            n10: 10, n11: 11, n12: 12, n13: 13, n14: 14, n15: 15, n16: 16, n17: 17, n18: 18, n19: 19,
+            n20: 20, n21: 21, n22: 22, n23: 23, n24: 24, n25: 25, n26: 26, n27: 27, n28: 28, n29: 29,
+            n30: 30, n31: 31, n32: 32, n33: 33, n34: 34, n35: 35, n36: 36, n37: 37, n38: 38, n39: 39,
+            n40: 40, n41: 41, n42: 42, n43: 43, n44: 44, n45: 45, n46: 46, n47: 47, n48: 48, n49: 49,
+            n50: 50, n51: 51, n52: 52, n53: 53, n54: 54, n55: 55, n56: 56, n57: 57, n58: 58, n59: 59,
+            n60: 60, n61: 61, n62: 62, n63: 63, n64: 64, n65: 65, n66: 66, n67: 67, n68: 68, n69: 69,
+            n70: 70, n71: 71, n72: 72, n73: 73, n74: 74, n75: 75, n76: 76, n77: 77, n78: 78, n79: 79,
+            n80: 80, n81: 81, n82: 82, n83: 83, n84: 84, n85: 85, n86: 86, n87: 87, n88: 88, n89: 89,
+            n90: 90, n91: 91, n92: 92, n93: 93, n94: 94, n95: 95, n96: 96, n97: 97, n98: 98, n99: 99,
+        }
+    };
+    assert_eq!(children(&named, &root), (0..100).collect::<Vec<i32>>());
+    assert_eq!(named.get(&n99).unwrap().data(), &99);
+}
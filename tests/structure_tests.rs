@@ -0,0 +1,544 @@
+//! Property tests that run random sequences of structural operations against both a `Tree` and a
+//! naive reference model, comparing the two after every step.
+
+extern crate id_tree;
+extern crate proptest;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use id_tree::InsertBehavior::*;
+use id_tree::MoveBehavior::*;
+use id_tree::*;
+use proptest::prelude::*;
+
+#[derive(Clone, Copy, Debug)]
+enum RemoveKind {
+    Drop,
+    Lift,
+    Orphan,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SwapKind {
+    Take,
+    Leave,
+    ChildrenOnly,
+}
+
+///
+/// A single step of a test run.  Nodes are picked by position among the live nodes (modulo their
+/// count), so every generated sequence can be applied to any tree.
+///
+#[derive(Clone, Debug)]
+enum Op {
+    InsertRoot(i32),
+    InsertUnder(usize, i32),
+    Remove(usize, RemoveKind),
+    MoveToRoot(usize),
+    MoveToParent(usize, usize),
+    Swap(usize, usize, SwapKind),
+    Sort(usize),
+    MakeNthSibling(usize, usize),
+    MakeLastSibling(usize),
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    let remove_kind = prop_oneof![
+        Just(RemoveKind::Drop),
+        Just(RemoveKind::Lift),
+        Just(RemoveKind::Orphan),
+    ];
+    let swap_kind = prop_oneof![
+        Just(SwapKind::Take),
+        Just(SwapKind::Leave),
+        Just(SwapKind::ChildrenOnly),
+    ];
+
+    prop_oneof![
+        1 => (0..5i32).prop_map(Op::InsertRoot),
+        6 => (0..16usize, 0..5i32).prop_map(|(p, d)| Op::InsertUnder(p, d)),
+        2 => (0..16usize, remove_kind).prop_map(|(n, k)| Op::Remove(n, k)),
+        1 => (0..16usize).prop_map(Op::MoveToRoot),
+        3 => (0..16usize, 0..16usize).prop_map(|(n, p)| Op::MoveToParent(n, p)),
+        3 => (0..16usize, 0..16usize, swap_kind).prop_map(|(a, b, k)| Op::Swap(a, b, k)),
+        1 => (0..16usize).prop_map(Op::Sort),
+        1 => (0..16usize, 0..6usize).prop_map(|(n, pos)| Op::MakeNthSibling(n, pos)),
+        1 => (0..16usize).prop_map(Op::MakeLastSibling),
+    ]
+}
+
+#[derive(Clone, Debug)]
+struct ModelNode {
+    data: i32,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+///
+/// The reference model.  Nodes are identified by handles that are never reused, and every
+/// operation is written the simplest possible way rather than the fastest.
+///
+#[derive(Clone, Debug, Default)]
+struct Model {
+    nodes: BTreeMap<usize, ModelNode>,
+    root: Option<usize>,
+    next_handle: usize,
+}
+
+impl Model {
+    fn pick(&self, n: usize) -> Option<usize> {
+        if self.nodes.is_empty() {
+            None
+        } else {
+            self.nodes.keys().nth(n % self.nodes.len()).cloned()
+        }
+    }
+
+    fn new_node(&mut self, data: i32) -> usize {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.nodes.insert(
+            handle,
+            ModelNode {
+                data,
+                parent: None,
+                children: Vec::new(),
+            },
+        );
+        handle
+    }
+
+    fn parent(&self, h: usize) -> Option<usize> {
+        self.nodes[&h].parent
+    }
+
+    fn attach(&mut self, parent: usize, child: usize) {
+        self.nodes.get_mut(&child).unwrap().parent = Some(parent);
+        self.nodes.get_mut(&parent).unwrap().children.push(child);
+    }
+
+    fn detach(&mut self, h: usize) {
+        if let Some(p) = self.parent(h) {
+            self.nodes.get_mut(&p).unwrap().children.retain(|&c| c != h);
+        }
+        self.nodes.get_mut(&h).unwrap().parent = None;
+    }
+
+    fn replace_in_parent(&mut self, parent: usize, old: usize, new: usize) {
+        for c in self.nodes.get_mut(&parent).unwrap().children.iter_mut() {
+            if *c == old {
+                *c = new;
+            }
+        }
+    }
+
+    /// Returns the child of `upper` on the path down to `lower`, if `upper` is above `lower`.
+    fn child_towards(&self, upper: usize, lower: usize) -> Option<usize> {
+        let mut current = lower;
+        while let Some(p) = self.parent(current) {
+            if p == upper {
+                return Some(current);
+            }
+            current = p;
+        }
+        None
+    }
+
+    fn swap_root(&mut self, a: usize, b: usize) {
+        if self.root == Some(a) {
+            self.root = Some(b);
+        } else if self.root == Some(b) {
+            self.root = Some(a);
+        }
+    }
+
+    fn insert_root(&mut self, data: i32) -> usize {
+        let h = self.new_node(data);
+        if let Some(old_root) = self.root {
+            self.attach(h, old_root);
+        }
+        self.root = Some(h);
+        h
+    }
+
+    fn insert_under(&mut self, parent: usize, data: i32) -> usize {
+        let h = self.new_node(data);
+        self.attach(parent, h);
+        h
+    }
+
+    fn remove(&mut self, h: usize, kind: RemoveKind) {
+        let children = self.nodes[&h].children.clone();
+        match kind {
+            RemoveKind::Drop => {
+                for c in children {
+                    self.remove(c, RemoveKind::Drop);
+                }
+            }
+            RemoveKind::Lift if self.parent(h).is_some() => {
+                let p = self.parent(h).unwrap();
+                for c in children {
+                    self.attach(p, c);
+                }
+            }
+            RemoveKind::Lift | RemoveKind::Orphan => {
+                for c in children {
+                    self.nodes.get_mut(&c).unwrap().parent = None;
+                }
+            }
+        }
+        self.detach(h);
+        if self.root == Some(h) {
+            self.root = None;
+        }
+        self.nodes.remove(&h);
+    }
+
+    fn move_to_root(&mut self, h: usize) {
+        if self.root == Some(h) {
+            return;
+        }
+        self.detach(h);
+        let old_root = self.root;
+        self.root = Some(h);
+        if let Some(old_root) = old_root {
+            self.move_to_parent(old_root, h).unwrap();
+        }
+    }
+
+    fn move_to_parent(&mut self, h: usize, parent: usize) -> Result<(), NodeIdError> {
+        if h == parent {
+            return Err(NodeIdError::WouldCreateCycle);
+        }
+        if let Some(subtree_root) = self.child_towards(h, parent) {
+            // moving down: the branch leading to the new parent takes our old place
+            self.nodes
+                .get_mut(&h)
+                .unwrap()
+                .children
+                .retain(|&c| c != subtree_root);
+            self.nodes.get_mut(&subtree_root).unwrap().parent = None;
+            if self.root == Some(h) {
+                self.root = Some(subtree_root);
+            } else if let Some(old_parent) = self.parent(h) {
+                self.detach(h);
+                self.attach(old_parent, subtree_root);
+            }
+        } else {
+            self.detach(h);
+            if self.root == Some(h) {
+                // moved under an orphan, whose topmost ancestor takes over as the root
+                let mut top = parent;
+                while let Some(p) = self.parent(top) {
+                    top = p;
+                }
+                self.root = Some(top);
+            }
+        }
+        self.attach(parent, h);
+        Ok(())
+    }
+
+    fn swap(&mut self, a: usize, b: usize, kind: SwapKind) -> Result<(), NodeIdError> {
+        if a == b {
+            return Err(NodeIdError::SameNode);
+        }
+        match kind {
+            SwapKind::Take => self.swap_take_children(a, b),
+            SwapKind::Leave => self.swap_leave_children(a, b),
+            SwapKind::ChildrenOnly => self.swap_children_only(a, b),
+        }
+        Ok(())
+    }
+
+    fn lower_upper(&self, a: usize, b: usize) -> Option<(usize, usize)> {
+        if self.child_towards(b, a).is_some() {
+            Some((a, b))
+        } else if self.child_towards(a, b).is_some() {
+            Some((b, a))
+        } else {
+            None
+        }
+    }
+
+    fn swap_take_children(&mut self, a: usize, b: usize) {
+        if let Some((lower, upper)) = self.lower_upper(a, b) {
+            // lower takes upper's place and upper hangs below lower
+            self.detach(lower);
+            match self.parent(upper) {
+                Some(upper_parent) => {
+                    self.replace_in_parent(upper_parent, upper, lower);
+                    self.nodes.get_mut(&lower).unwrap().parent = Some(upper_parent);
+                }
+                None => {
+                    if self.root == Some(upper) {
+                        self.root = Some(lower);
+                    }
+                }
+            }
+            self.attach(lower, upper);
+        } else {
+            let (pa, pb) = (self.parent(a), self.parent(b));
+            if pa == pb {
+                if let Some(p) = pa {
+                    let children = &mut self.nodes.get_mut(&p).unwrap().children;
+                    let ia = children.iter().position(|&c| c == a).unwrap();
+                    let ib = children.iter().position(|&c| c == b).unwrap();
+                    children.swap(ia, ib);
+                } else {
+                    self.swap_root(a, b);
+                }
+            } else {
+                if let Some(pa) = pa {
+                    self.replace_in_parent(pa, a, b);
+                }
+                if let Some(pb) = pb {
+                    self.replace_in_parent(pb, b, a);
+                }
+                self.nodes.get_mut(&a).unwrap().parent = pb;
+                self.nodes.get_mut(&b).unwrap().parent = pa;
+                self.swap_root(a, b);
+            }
+        }
+    }
+
+    fn swap_leave_children(&mut self, a: usize, b: usize) {
+        // the two nodes trade places, everything else stays where it is: that is the same as
+        // exchanging the two handles everywhere in the structure while keeping the data
+        let relabel = |h: usize| {
+            if h == a {
+                b
+            } else if h == b {
+                a
+            } else {
+                h
+            }
+        };
+
+        let old = self.nodes.clone();
+        for (&h, node) in &old {
+            let new = self.nodes.get_mut(&relabel(h)).unwrap();
+            new.parent = node.parent.map(relabel);
+            new.children = node.children.iter().cloned().map(relabel).collect();
+        }
+        self.root = self.root.map(relabel);
+    }
+
+    fn swap_children_only(&mut self, a: usize, b: usize) {
+        let a_children = self.nodes[&a].children.clone();
+        let b_children = self.nodes[&b].children.clone();
+
+        if let Some((lower, upper)) = self.lower_upper(a, b) {
+            let (mut upper_children, lower_children) = if upper == a {
+                (a_children, b_children)
+            } else {
+                (b_children, a_children)
+            };
+
+            self.detach(lower);
+            upper_children.retain(|&c| c != lower);
+
+            for &c in &upper_children {
+                self.nodes.get_mut(&c).unwrap().parent = Some(lower);
+            }
+            for &c in &lower_children {
+                self.nodes.get_mut(&c).unwrap().parent = Some(upper);
+            }
+            self.nodes.get_mut(&upper).unwrap().children = lower_children;
+            self.nodes.get_mut(&lower).unwrap().children = upper_children;
+            self.attach(upper, lower);
+        } else {
+            for &c in &a_children {
+                self.nodes.get_mut(&c).unwrap().parent = Some(b);
+            }
+            for &c in &b_children {
+                self.nodes.get_mut(&c).unwrap().parent = Some(a);
+            }
+            self.nodes.get_mut(&a).unwrap().children = b_children;
+            self.nodes.get_mut(&b).unwrap().children = a_children;
+        }
+    }
+
+    fn sort(&mut self, h: usize) {
+        let mut children = self.nodes[&h].children.clone();
+        children.sort_by_key(|c| self.nodes[c].data);
+        self.nodes.get_mut(&h).unwrap().children = children;
+    }
+
+    fn make_nth_sibling(&mut self, h: usize, pos: usize) -> Result<(), NodeIdError> {
+        let p = self.parent(h).ok_or(NodeIdError::NotASibling)?;
+        let children = &mut self.nodes.get_mut(&p).unwrap().children;
+        if pos >= children.len() {
+            return Err(NodeIdError::PositionOutOfRange);
+        }
+        children.retain(|&c| c != h);
+        children.insert(pos, h);
+        Ok(())
+    }
+
+    fn make_last_sibling(&mut self, h: usize) -> bool {
+        match self.parent(h) {
+            Some(p) => {
+                let children = &mut self.nodes.get_mut(&p).unwrap().children;
+                let moved = children.last() != Some(&h);
+                children.retain(|&c| c != h);
+                children.push(h);
+                moved
+            }
+            None => false,
+        }
+    }
+}
+
+///
+/// Applies every operation to both the `Tree` and the `Model` and checks that they agree after
+/// each one.
+///
+fn run(ops: Vec<Op>) -> Result<(), TestCaseError> {
+    let mut tree: Tree<i32> = Tree::new();
+    let mut model = Model::default();
+    let mut ids: HashMap<usize, NodeId> = HashMap::new();
+
+    for op in ops {
+        match op {
+            Op::InsertRoot(data) => {
+                let id = tree.insert(Node::new(data), AsRoot).unwrap();
+                ids.insert(model.insert_root(data), id);
+            }
+            Op::InsertUnder(p, data) => {
+                if let Some(p) = model.pick(p) {
+                    let id = tree.insert(Node::new(data), UnderNode(&ids[&p])).unwrap();
+                    ids.insert(model.insert_under(p, data), id);
+                }
+            }
+            Op::Remove(n, kind) => {
+                if let Some(h) = model.pick(n) {
+                    let behavior = match kind {
+                        RemoveKind::Drop => RemoveBehavior::DropChildren,
+                        RemoveKind::Lift => RemoveBehavior::LiftChildren,
+                        RemoveKind::Orphan => RemoveBehavior::OrphanChildren,
+                    };
+                    let removed: Vec<usize> = model
+                        .nodes
+                        .keys()
+                        .cloned()
+                        .filter(|&k| k == h || is_below(&model, h, k, kind))
+                        .collect();
+
+                    let node = tree.remove_node(ids[&h].clone(), behavior).unwrap();
+                    prop_assert_eq!(*node.data(), model.nodes[&h].data);
+                    model.remove(h, kind);
+                    for k in removed {
+                        ids.remove(&k);
+                    }
+                }
+            }
+            Op::MoveToRoot(n) => {
+                if let Some(h) = model.pick(n) {
+                    tree.move_node(&ids[&h], ToRoot).unwrap();
+                    model.move_to_root(h);
+                }
+            }
+            Op::MoveToParent(n, p) => {
+                if let (Some(h), Some(p)) = (model.pick(n), model.pick(p)) {
+                    let result = tree.move_node(&ids[&h], ToParent(&ids[&p]));
+                    prop_assert_eq!(result, model.move_to_parent(h, p));
+                }
+            }
+            Op::Swap(a, b, kind) => {
+                if let (Some(a), Some(b)) = (model.pick(a), model.pick(b)) {
+                    let behavior = match kind {
+                        SwapKind::Take => SwapBehavior::TakeChildren,
+                        SwapKind::Leave => SwapBehavior::LeaveChildren,
+                        SwapKind::ChildrenOnly => SwapBehavior::ChildrenOnly,
+                    };
+                    let result = tree.swap_nodes(&ids[&a], &ids[&b], behavior);
+                    prop_assert_eq!(result, model.swap(a, b, kind));
+                }
+            }
+            Op::Sort(n) => {
+                if let Some(h) = model.pick(n) {
+                    tree.sort_children_by_data(&ids[&h]).unwrap();
+                    model.sort(h);
+                }
+            }
+            Op::MakeNthSibling(n, pos) => {
+                if let Some(h) = model.pick(n) {
+                    let result = tree.make_nth_sibling(&ids[&h], pos);
+                    prop_assert_eq!(result, model.make_nth_sibling(h, pos));
+                }
+            }
+            Op::MakeLastSibling(n) => {
+                if let Some(h) = model.pick(n) {
+                    let moved = tree.make_last_sibling(&ids[&h]).unwrap();
+                    prop_assert_eq!(moved, model.make_last_sibling(h));
+                }
+            }
+        }
+
+        check(&tree, &model, &ids)?;
+    }
+
+    Ok(())
+}
+
+/// Whether `k` is removed along with `h` when removing `h` with the given kind.
+fn is_below(model: &Model, h: usize, k: usize, kind: RemoveKind) -> bool {
+    match kind {
+        RemoveKind::Drop => model.child_towards(h, k).is_some(),
+        RemoveKind::Lift | RemoveKind::Orphan => false,
+    }
+}
+
+fn check(
+    tree: &Tree<i32>,
+    model: &Model,
+    ids: &HashMap<usize, NodeId>,
+) -> Result<(), TestCaseError> {
+    prop_assert_eq!(tree.validate(), Ok(()));
+    prop_assert_eq!(ids.len(), model.nodes.len());
+
+    let handles: HashMap<&NodeId, usize> = ids.iter().map(|(&h, id)| (id, h)).collect();
+    let to_handle = |id: &NodeId| handles[id];
+
+    prop_assert_eq!(tree.root_node_id().map(&to_handle), model.root);
+
+    for (h, expected) in &model.nodes {
+        let node = tree.get(&ids[h]).unwrap();
+        prop_assert_eq!(node.data(), &expected.data);
+        prop_assert_eq!(node.parent().map(&to_handle), expected.parent);
+        let children: Vec<usize> = node.children().iter().map(&to_handle).collect();
+        prop_assert_eq!(&children, &expected.children);
+    }
+
+    if let Some(root) = model.root {
+        let traversed: Vec<usize> = tree
+            .traverse_pre_order_ids(&ids[&root])
+            .unwrap()
+            .map(|id| to_handle(&id))
+            .collect();
+        prop_assert_eq!(traversed, model_pre_order(model, root));
+    }
+
+    Ok(())
+}
+
+fn model_pre_order(model: &Model, start: usize) -> Vec<usize> {
+    let mut order = Vec::new();
+    let mut stack = vec![start];
+    while let Some(h) = stack.pop() {
+        order.push(h);
+        stack.extend(model.nodes[&h].children.iter().rev());
+    }
+    order
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn random_operations_match_model(ops in prop::collection::vec(op_strategy(), 1..80)) {
+        run(ops)?;
+    }
+}